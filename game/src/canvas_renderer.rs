@@ -0,0 +1,122 @@
+use crate::render::{DrawCommand, DrawList, RenderError, Renderer};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+/// Draws onto a 2D canvas, with sprites cut from an `<img>` sheet
+pub struct CanvasRenderer {
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+    sprite_sheet: HtmlImageElement,
+    width: f64,
+    height: f64,
+}
+
+impl CanvasRenderer {
+    /// Take over `canvas` and start loading the sheet at `sprite_sheet_url`
+    pub fn new(canvas: HtmlCanvasElement, sprite_sheet_url: &str) -> Result<Self, JsValue> {
+        let ctx = canvas
+            .get_context("2d")?
+            .ok_or("should have a 2d context")?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        let sprite_sheet = HtmlImageElement::new()?;
+        sprite_sheet.set_src(sprite_sheet_url);
+
+        Ok(Self {
+            width: canvas.width() as f64,
+            height: canvas.height() as f64,
+            canvas,
+            ctx,
+            sprite_sheet,
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    fn fill_circle(&self, x: f64, y: f64, radius: f64) -> Result<(), JsValue> {
+        self.ctx.begin_path();
+        self.ctx
+            .arc(x, y, radius, 0.0, 2.0 * std::f64::consts::PI)?;
+        self.ctx.fill();
+        Ok(())
+    }
+
+    #[allow(deprecated)] // TODO: Update to use new fill_style API when stable
+    fn draw(&self, command: &DrawCommand) -> Result<(), JsValue> {
+        match command {
+            DrawCommand::Clear => self.ctx.clear_rect(0.0, 0.0, self.width, self.height),
+            DrawCommand::FillRect { x, y, w, h, color } => {
+                self.ctx.set_fill_style(&JsValue::from_str(&color.css()));
+                self.ctx.fill_rect(*x, *y, *w, *h);
+            }
+            DrawCommand::FillCircle {
+                x,
+                y,
+                radius,
+                color,
+            } => {
+                self.ctx.set_fill_style(&JsValue::from_str(&color.css()));
+                self.fill_circle(*x, *y, *radius)?;
+            }
+            DrawCommand::Text {
+                text,
+                x,
+                y,
+                size,
+                color,
+            } => {
+                self.ctx.set_fill_style(&JsValue::from_str(&color.css()));
+                self.ctx.set_font(&format!("{size}px sans-serif"));
+                self.ctx.set_text_align("center");
+                self.ctx.fill_text(text, *x, *y)?;
+            }
+            DrawCommand::Sprite { frame, x, y } => {
+                self.ctx
+                    .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        &self.sprite_sheet,
+                        frame.x,
+                        frame.y,
+                        frame.w,
+                        frame.h,
+                        x - frame.w / 2.0,
+                        y - frame.h / 2.0,
+                        frame.w,
+                        frame.h,
+                    )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for CanvasRenderer {
+    fn sprites_ready(&self) -> bool {
+        self.sprite_sheet.complete() && self.sprite_sheet.natural_width() > 0
+    }
+
+    fn load_sprite_sheet(&mut self, url: &str) -> Result<(), RenderError> {
+        self.sprite_sheet.set_src(url);
+        Ok(())
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        self.width = width;
+        self.height = height;
+        self.canvas.set_width(width as u32);
+        self.canvas.set_height(height as u32);
+    }
+
+    fn render(&mut self, list: &DrawList) -> Result<(), RenderError> {
+        for command in list.commands() {
+            self.draw(command).map_err(|e| {
+                RenderError::Backend(e.as_string().unwrap_or_else(|| format!("{e:?}")))
+            })?;
+        }
+        Ok(())
+    }
+}
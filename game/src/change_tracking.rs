@@ -0,0 +1,102 @@
+/// Parts of the game state the frontend can receive independently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateSection {
+    Screen,
+    Region,
+    Profile,
+    Status,
+    PlayerPosition,
+    Ball,
+    Zone,
+    Progress,
+    Gold,
+    Inventory,
+    Party,
+    Trade,
+}
+
+impl StateSection {
+    pub const ALL: [StateSection; 12] = [
+        StateSection::Screen,
+        StateSection::Region,
+        StateSection::Profile,
+        StateSection::Status,
+        StateSection::PlayerPosition,
+        StateSection::Ball,
+        StateSection::Zone,
+        StateSection::Progress,
+        StateSection::Gold,
+        StateSection::Inventory,
+        StateSection::Party,
+        StateSection::Trade,
+    ];
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Set of sections changed since the frontend last synced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirtySections(u16);
+
+impl DirtySections {
+    pub fn mark(&mut self, section: StateSection) {
+        self.0 |= section.bit();
+    }
+
+    pub fn mark_all(&mut self) {
+        for section in StateSection::ALL {
+            self.mark(section);
+        }
+    }
+
+    pub fn is_dirty(&self, section: StateSection) -> bool {
+        self.0 & section.bit() != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Return the current set and start tracking from a clean slate
+    pub fn take(&mut self) -> DirtySections {
+        std::mem::take(self)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StateSection> + '_ {
+        StateSection::ALL
+            .into_iter()
+            .filter(|section| self.is_dirty(*section))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_and_take() {
+        let mut dirty = DirtySections::default();
+        assert!(dirty.is_empty());
+
+        dirty.mark(StateSection::Inventory);
+        dirty.mark(StateSection::Screen);
+        assert!(dirty.is_dirty(StateSection::Inventory));
+        assert!(!dirty.is_dirty(StateSection::Ball));
+
+        let taken = dirty.take();
+        assert!(dirty.is_empty());
+        assert_eq!(
+            taken.iter().collect::<Vec<_>>(),
+            vec![StateSection::Screen, StateSection::Inventory]
+        );
+    }
+
+    #[test]
+    fn test_mark_all() {
+        let mut dirty = DirtySections::default();
+        dirty.mark_all();
+        assert_eq!(dirty.iter().count(), StateSection::ALL.len());
+    }
+}
@@ -0,0 +1,485 @@
+use crate::protocol::PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Longest chat message, in characters, after trimming
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+/// Messages a player may send within `CHAT_RATE_WINDOW_MS`
+pub const CHAT_RATE_LIMIT: usize = 5;
+
+/// Sliding window the rate limit is counted over
+pub const CHAT_RATE_WINDOW_MS: f64 = 5_000.0;
+
+/// Received chat lines kept before the oldest are dropped
+pub const MAX_CHAT_HISTORY: usize = 100;
+
+/// Who hears a chat message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub enum ChatChannel {
+    /// Players in the sender's area of interest
+    Local,
+    /// Players in the same zone
+    Zone,
+    /// Everyone on the server
+    Global,
+    /// The sender's party
+    Party,
+    /// One player, by name
+    Whisper { to: String },
+}
+
+impl ChatChannel {
+    /// Split typed chat into a channel and the message, using slash
+    /// prefixes like `/g hello` or `/w Name hi`. Names with spaces are
+    /// quoted, as in `/w "Big Bob" hi`. Plain text goes to Local.
+    pub fn parse(input: &str) -> (ChatChannel, &str) {
+        let input = input.trim_start();
+        let Some(rest) = input.strip_prefix('/') else {
+            return (ChatChannel::Local, input);
+        };
+        let (command, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        match command.to_ascii_lowercase().as_str() {
+            "l" | "local" | "s" | "say" => (ChatChannel::Local, rest),
+            "z" | "zone" => (ChatChannel::Zone, rest),
+            "g" | "global" => (ChatChannel::Global, rest),
+            "p" | "party" => (ChatChannel::Party, rest),
+            "w" | "whisper" | "tell" => {
+                let rest = rest.trim_start();
+                let (to, text) = match rest.strip_prefix('"').and_then(|r| r.split_once('"')) {
+                    Some((to, text)) => (to.trim(), text.trim_start()),
+                    None => rest.split_once(' ').unwrap_or((rest, "")),
+                };
+                (ChatChannel::Whisper { to: to.to_string() }, text)
+            }
+            // Unknown commands are sent as typed
+            _ => (ChatChannel::Local, input),
+        }
+    }
+}
+
+/// Reasons a chat message isn't delivered
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatError {
+    Empty,
+    TooLong {
+        max: usize,
+    },
+    RateLimited {
+        retry_in_ms: u64,
+    },
+    /// The filter refused the message, with its reason
+    Blocked(String),
+    Muted,
+    NotInParty,
+    UnknownRecipient(String),
+    NotConnected,
+    /// The server refused the message, with its explanation
+    Rejected(String),
+}
+
+impl ChatError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChatError::Empty => "chat_empty",
+            ChatError::TooLong { .. } => "chat_too_long",
+            ChatError::RateLimited { .. } => "chat_rate_limited",
+            ChatError::Blocked(_) => "chat_blocked",
+            ChatError::Muted => "chat_muted",
+            ChatError::NotInParty => "chat_not_in_party",
+            ChatError::UnknownRecipient(_) => "chat_unknown_recipient",
+            ChatError::NotConnected => "chat_not_connected",
+            ChatError::Rejected(_) => "chat_rejected",
+        }
+    }
+}
+
+impl fmt::Display for ChatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChatError::Empty => write!(f, "Type a message first"),
+            ChatError::TooLong { max } => write!(f, "Messages can be at most {max} characters"),
+            ChatError::RateLimited { retry_in_ms } => write!(
+                f,
+                "You're sending messages too quickly, wait {:.1}s",
+                *retry_in_ms as f64 / 1000.0
+            ),
+            ChatError::Blocked(reason) => write!(f, "Message blocked: {reason}"),
+            ChatError::Muted => write!(f, "You are muted"),
+            ChatError::NotInParty => write!(f, "You're not in a party"),
+            ChatError::UnknownRecipient(name) => write!(f, "No player named '{name}' is online"),
+            ChatError::NotConnected => write!(f, "Connect to a server to chat"),
+            ChatError::Rejected(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ChatError {}
+
+/// What a `ChatFilter` decided about a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterVerdict {
+    Allow,
+    /// Deliver this text instead, e.g. with words masked
+    Replace(String),
+    /// Don't deliver, with a reason for the sender
+    Block(String),
+}
+
+/// Hook for profanity filtering; servers plug in whatever list or service
+/// they use
+pub trait ChatFilter {
+    fn check(&self, text: &str) -> FilterVerdict;
+}
+
+/// Filter that lets everything through
+#[derive(Debug, Clone, Default)]
+pub struct NoFilter;
+
+impl ChatFilter for NoFilter {
+    fn check(&self, _text: &str) -> FilterVerdict {
+        FilterVerdict::Allow
+    }
+}
+
+/// Masks listed words with asterisks, ignoring case
+#[derive(Debug, Clone, Default)]
+pub struct WordListFilter {
+    words: HashSet<String>,
+}
+
+impl WordListFilter {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words
+                .into_iter()
+                .map(|word| word.as_ref().to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl ChatFilter for WordListFilter {
+    fn check(&self, text: &str) -> FilterVerdict {
+        let mut masked = String::with_capacity(text.len());
+        let mut changed = false;
+        let mut word = String::new();
+        let mut flush = |word: &mut String, masked: &mut String| {
+            if self.words.contains(&word.to_lowercase()) {
+                masked.extend(word.chars().map(|_| '*'));
+                changed = true;
+            } else {
+                masked.push_str(word);
+            }
+            word.clear();
+        };
+
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else {
+                flush(&mut word, &mut masked);
+                masked.push(c);
+            }
+        }
+        flush(&mut word, &mut masked);
+
+        if changed {
+            FilterVerdict::Replace(masked)
+        } else {
+            FilterVerdict::Allow
+        }
+    }
+}
+
+/// Trim a message and check it isn't empty or too long. Control characters
+/// are dropped so messages can't fake extra lines.
+pub fn validate_text(raw: &str) -> Result<String, ChatError> {
+    let text: String = raw.trim().chars().filter(|c| !c.is_control()).collect();
+    if text.is_empty() {
+        return Err(ChatError::Empty);
+    }
+    if text.chars().count() > MAX_CHAT_LENGTH {
+        return Err(ChatError::TooLong {
+            max: MAX_CHAT_LENGTH,
+        });
+    }
+    Ok(text)
+}
+
+/// Sliding-window limit on how often one sender may chat
+#[derive(Debug, Clone)]
+pub struct ChatRateLimiter {
+    limit: usize,
+    window_ms: f64,
+    sent: VecDeque<f64>,
+}
+
+impl Default for ChatRateLimiter {
+    fn default() -> Self {
+        Self::new(CHAT_RATE_LIMIT, CHAT_RATE_WINDOW_MS)
+    }
+}
+
+impl ChatRateLimiter {
+    pub fn new(limit: usize, window_ms: f64) -> Self {
+        Self {
+            limit: limit.max(1),
+            window_ms,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Count a message sent at `now_ms`, or say how long until one may be
+    pub fn try_send(&mut self, now_ms: f64) -> Result<(), ChatError> {
+        while self
+            .sent
+            .front()
+            .is_some_and(|&at| now_ms - at >= self.window_ms)
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.limit {
+            let oldest = self.sent.front().copied().unwrap_or(now_ms);
+            return Err(ChatError::RateLimited {
+                retry_in_ms: (oldest + self.window_ms - now_ms).ceil().max(0.0) as u64,
+            });
+        }
+        self.sent.push_back(now_ms);
+        Ok(())
+    }
+}
+
+/// Server-side checks every message passes before it's routed: mutes,
+/// length, the filter hook and per-sender rate limits
+pub struct ChatModerator {
+    filter: Box<dyn ChatFilter>,
+    limiters: HashMap<PlayerId, ChatRateLimiter>,
+    /// Muted player names, lowercased. Names outlive a session's
+    /// `PlayerId`, so reconnecting doesn't lift a mute.
+    muted: HashSet<String>,
+}
+
+impl fmt::Debug for ChatModerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChatModerator")
+            .field("limiters", &self.limiters)
+            .field("muted", &self.muted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ChatModerator {
+    fn default() -> Self {
+        Self::new(Box::new(NoFilter))
+    }
+}
+
+impl ChatModerator {
+    pub fn new(filter: Box<dyn ChatFilter>) -> Self {
+        Self {
+            filter,
+            limiters: HashMap::new(),
+            muted: HashSet::new(),
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: Box<dyn ChatFilter>) {
+        self.filter = filter;
+    }
+
+    /// Mute or unmute a player by name, whether or not they're online
+    pub fn set_muted(&mut self, name: &str, muted: bool) {
+        let key = mute_key(name);
+        if muted {
+            self.muted.insert(key);
+        } else {
+            self.muted.remove(&key);
+        }
+    }
+
+    pub fn is_muted(&self, name: &str) -> bool {
+        self.muted.contains(&mute_key(name))
+    }
+
+    /// Drop a player's rate limit when they leave; mutes stay
+    pub fn forget(&mut self, id: PlayerId) {
+        self.limiters.remove(&id);
+    }
+
+    /// Check a message from `sender`, called `name`, returning the text to
+    /// deliver. Rejected messages don't count against the rate limit.
+    pub fn review(
+        &mut self,
+        sender: PlayerId,
+        name: &str,
+        raw: &str,
+        now_ms: f64,
+    ) -> Result<String, ChatError> {
+        if self.is_muted(name) {
+            return Err(ChatError::Muted);
+        }
+        let text = validate_text(raw)?;
+        let text = match self.filter.check(&text) {
+            FilterVerdict::Allow => text,
+            FilterVerdict::Replace(replacement) => replacement,
+            FilterVerdict::Block(reason) => return Err(ChatError::Blocked(reason)),
+        };
+        self.limiters.entry(sender).or_default().try_send(now_ms)?;
+        Ok(text)
+    }
+}
+
+fn mute_key(name: &str) -> String {
+    name.trim().to_ascii_lowercase()
+}
+
+/// A received chat message, as shown in the chat box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct ChatLine {
+    pub channel: ChatChannel,
+    pub from: String,
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_text() {
+        assert_eq!(validate_text("  hi there \n"), Ok("hi there".to_string()));
+        assert_eq!(validate_text("a\u{0}b"), Ok("ab".to_string()));
+        assert_eq!(validate_text("   "), Err(ChatError::Empty));
+        assert_eq!(
+            validate_text(&"é".repeat(MAX_CHAT_LENGTH + 1)),
+            Err(ChatError::TooLong {
+                max: MAX_CHAT_LENGTH
+            })
+        );
+        assert!(validate_text(&"é".repeat(MAX_CHAT_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limiter_slides() {
+        let mut limiter = ChatRateLimiter::new(2, 1000.0);
+        assert!(limiter.try_send(0.0).is_ok());
+        assert!(limiter.try_send(400.0).is_ok());
+        assert_eq!(
+            limiter.try_send(700.0),
+            Err(ChatError::RateLimited { retry_in_ms: 300 })
+        );
+        assert!(limiter.try_send(1000.0).is_ok());
+        assert!(limiter.try_send(1300.0).is_err());
+        assert!(limiter.try_send(1400.0).is_ok());
+    }
+
+    #[test]
+    fn test_word_list_filter_masks_whole_words() {
+        let filter = WordListFilter::new(["darn"]);
+        assert_eq!(
+            filter.check("Darn it, darned thing!"),
+            FilterVerdict::Replace("**** it, darned thing!".to_string())
+        );
+        assert_eq!(filter.check("all good"), FilterVerdict::Allow);
+    }
+
+    struct BlockLinks;
+
+    impl ChatFilter for BlockLinks {
+        fn check(&self, text: &str) -> FilterVerdict {
+            if text.contains("://") {
+                FilterVerdict::Block("links aren't allowed".to_string())
+            } else {
+                FilterVerdict::Allow
+            }
+        }
+    }
+
+    #[test]
+    fn test_moderator_applies_mute_filter_and_limit() {
+        let mut moderator = ChatModerator::new(Box::new(BlockLinks));
+        assert_eq!(
+            moderator.review(1, "Ann", " hello ", 0.0),
+            Ok("hello".to_string())
+        );
+        assert!(matches!(
+            moderator.review(1, "Ann", "see http://x", 0.0),
+            Err(ChatError::Blocked(_))
+        ));
+
+        for _ in 1..CHAT_RATE_LIMIT {
+            assert!(moderator.review(1, "Ann", "spam", 10.0).is_ok());
+        }
+        assert!(matches!(
+            moderator.review(1, "Ann", "spam", 20.0),
+            Err(ChatError::RateLimited { .. })
+        ));
+        // Limits are per sender
+        assert!(moderator.review(2, "Bob", "hi", 20.0).is_ok());
+
+        moderator.set_muted("bob", true);
+        assert_eq!(
+            moderator.review(2, "Bob", "hi", 99_999.0),
+            Err(ChatError::Muted)
+        );
+        // Mutes follow the name, not the session
+        moderator.forget(2);
+        assert_eq!(
+            moderator.review(5, "Bob", "hi", 99_999.0),
+            Err(ChatError::Muted)
+        );
+        moderator.set_muted("Bob", false);
+        assert!(!moderator.is_muted("BOB"));
+    }
+
+    #[test]
+    fn test_parse_slash_commands() {
+        assert_eq!(ChatChannel::parse("hello"), (ChatChannel::Local, "hello"));
+        assert_eq!(
+            ChatChannel::parse("/g hi all"),
+            (ChatChannel::Global, "hi all")
+        );
+        assert_eq!(ChatChannel::parse("/Zone hey"), (ChatChannel::Zone, "hey"));
+        assert_eq!(ChatChannel::parse("/p"), (ChatChannel::Party, ""));
+        assert_eq!(
+            ChatChannel::parse("/w Bob meet at the gate"),
+            (
+                ChatChannel::Whisper {
+                    to: "Bob".to_string()
+                },
+                "meet at the gate"
+            )
+        );
+        assert_eq!(
+            ChatChannel::parse("/tell   \"Big Bob\" meet at the gate"),
+            (
+                ChatChannel::Whisper {
+                    to: "Big Bob".to_string()
+                },
+                "meet at the gate"
+            )
+        );
+        assert_eq!(
+            ChatChannel::parse("/w  Bob"),
+            (
+                ChatChannel::Whisper {
+                    to: "Bob".to_string()
+                },
+                ""
+            )
+        );
+        assert_eq!(
+            ChatChannel::parse("/shrug ok"),
+            (ChatChannel::Local, "/shrug ok")
+        );
+    }
+}
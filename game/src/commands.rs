@@ -0,0 +1,380 @@
+use crate::change_tracking::StateSection;
+use crate::crafting::{CraftError, CraftingStation, RecipeRegistry};
+use crate::game_state::{GameScreen, GameState, Region, ScreenError};
+use crate::network::NetworkError;
+use crate::profile::{PlayerProfile, ProfileError};
+use crate::shop::{PurchaseError, ShopCatalog};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Commands the UI can send to the game core through `Game::dispatch`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[serde(tag = "type")]
+pub enum UiCommand {
+    TransitionTo {
+        screen: GameScreen,
+    },
+    PushScreen {
+        screen: GameScreen,
+    },
+    PopScreen,
+    ReplaceScreen {
+        screen: GameScreen,
+    },
+    SetRegion {
+        region: Region,
+    },
+    SetPlayerName {
+        name: String,
+    },
+    SetProfile {
+        profile: PlayerProfile,
+    },
+    EnterZone {
+        zone: String,
+    },
+    BuyItem {
+        item_id: String,
+        quantity: u32,
+    },
+    Craft {
+        recipe_id: String,
+        station: Option<CraftingStation>,
+    },
+    EquipItem {
+        item_id: String,
+    },
+    UseAbility {
+        ability_id: String,
+    },
+}
+
+/// Reasons a command can fail
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// The JSON didn't describe a known command
+    InvalidCommand(String),
+    Craft(CraftError),
+    Purchase(PurchaseError),
+    Screen(ScreenError),
+    Profile(ProfileError),
+    /// The command targets a system that doesn't exist yet
+    Unsupported(&'static str),
+    /// The command couldn't reach the server
+    Network(NetworkError),
+    /// The server refused the command, with its explanation
+    Rejected(String),
+}
+
+impl CommandError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::InvalidCommand(_) => "invalid_command",
+            CommandError::Craft(_) => "craft_failed",
+            CommandError::Purchase(_) => "purchase_failed",
+            CommandError::Screen(_) => "screen_not_allowed",
+            CommandError::Profile(e) => e.code(),
+            CommandError::Unsupported(_) => "unsupported",
+            CommandError::Network(e) => e.code(),
+            CommandError::Rejected(_) => "command_rejected",
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::InvalidCommand(reason) => write!(f, "Invalid command: {reason}"),
+            CommandError::Craft(e) => e.fmt(f),
+            CommandError::Purchase(e) => e.fmt(f),
+            CommandError::Screen(e) => e.fmt(f),
+            CommandError::Profile(e) => e.fmt(f),
+            CommandError::Unsupported(what) => write!(f, "{what} is not available yet"),
+            CommandError::Network(e) => e.fmt(f),
+            CommandError::Rejected(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<CraftError> for CommandError {
+    fn from(e: CraftError) -> Self {
+        CommandError::Craft(e)
+    }
+}
+
+impl From<PurchaseError> for CommandError {
+    fn from(e: PurchaseError) -> Self {
+        CommandError::Purchase(e)
+    }
+}
+
+impl From<ProfileError> for CommandError {
+    fn from(e: ProfileError) -> Self {
+        CommandError::Profile(e)
+    }
+}
+
+impl From<ScreenError> for CommandError {
+    fn from(e: ScreenError) -> Self {
+        CommandError::Screen(e)
+    }
+}
+
+/// Outcome of a dispatched command as seen by the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[serde(tag = "status")]
+pub enum CommandResult {
+    Ok,
+    Error { code: String, message: String },
+}
+
+impl From<Result<(), CommandError>> for CommandResult {
+    fn from(result: Result<(), CommandError>) -> Self {
+        match result {
+            Ok(()) => CommandResult::Ok,
+            Err(e) => CommandResult::Error {
+                code: e.code().to_string(),
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Static game data commands need besides the mutable state
+pub struct CommandContext<'a> {
+    pub recipes: &'a RecipeRegistry,
+    pub shop: &'a ShopCatalog,
+}
+
+impl UiCommand {
+    /// Parse a command from its JSON form
+    pub fn from_json(json: &str) -> Result<Self, CommandError> {
+        serde_json::from_str(json).map_err(|e| CommandError::InvalidCommand(e.to_string()))
+    }
+
+    /// Apply the command to the game state
+    pub fn execute(
+        self,
+        state: &mut GameState,
+        ctx: &CommandContext<'_>,
+    ) -> Result<(), CommandError> {
+        match self {
+            UiCommand::TransitionTo { screen } => state.transition_to(screen)?,
+            UiCommand::PushScreen { screen } => state.push_screen(screen)?,
+            UiCommand::PopScreen => {
+                state.pop_screen();
+            }
+            UiCommand::ReplaceScreen { screen } => state.replace_screen(screen)?,
+            UiCommand::SetRegion { region } => state.set_region(region),
+            UiCommand::SetPlayerName { name } => {
+                state.set_player_name(&name).map_err(ProfileError::from)?
+            }
+            UiCommand::SetProfile { profile } => state.set_profile(profile)?,
+            UiCommand::EnterZone { zone } => {
+                state.enter_zone(&zone);
+            }
+            UiCommand::BuyItem { item_id, quantity } => {
+                ctx.shop.buy(state, &item_id, quantity)?;
+            }
+            UiCommand::Craft { recipe_id, station } => {
+                // `craft` leaves the inventory alone on failure, so it's
+                // only flagged as changed once the craft succeeds
+                ctx.recipes
+                    .craft(&recipe_id, &mut state.inventory, station)?;
+                state.dirty.mark(StateSection::Inventory);
+            }
+            UiCommand::EquipItem { .. } => return Err(CommandError::Unsupported("Equipment")),
+            UiCommand::UseAbility { .. } => return Err(CommandError::Unsupported("Abilities")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::PlayerClass;
+
+    fn run(state: &mut GameState, json: &str) -> CommandResult {
+        let recipes = RecipeRegistry::with_default_recipes();
+        let shop = ShopCatalog::with_default_items();
+        let ctx = CommandContext {
+            recipes: &recipes,
+            shop: &shop,
+        };
+        UiCommand::from_json(json)
+            .and_then(|command| command.execute(state, &ctx))
+            .into()
+    }
+
+    #[test]
+    fn test_transition_and_region() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        assert_eq!(
+            run(&mut state, r#"{"type": "TransitionTo", "screen": "Shop"}"#),
+            CommandResult::Ok
+        );
+        assert_eq!(state.current_screen, GameScreen::Shop);
+
+        assert_eq!(
+            run(&mut state, r#"{"type": "SetRegion", "region": "Asia"}"#),
+            CommandResult::Ok
+        );
+        assert_eq!(state.selected_region, Some(Region::Asia));
+    }
+
+    #[test]
+    fn test_screen_stack_commands() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        run(&mut state, r#"{"type": "PushScreen", "screen": "Shop"}"#);
+        run(
+            &mut state,
+            r#"{"type": "PushScreen", "screen": "HelpModal"}"#,
+        );
+        assert_eq!(
+            run(&mut state, r#"{"type": "PushScreen", "screen": "Shop"}"#),
+            CommandResult::Error {
+                code: "screen_not_allowed".to_string(),
+                message: "Shop can't be opened on top of HelpModal".to_string(),
+            }
+        );
+
+        assert_eq!(
+            run(&mut state, r#"{"type": "PopScreen"}"#),
+            CommandResult::Ok
+        );
+        assert_eq!(state.current_screen, GameScreen::Shop);
+    }
+
+    #[test]
+    fn test_profile_errors_have_specific_codes() {
+        let mut state = GameState::new(800.0, 600.0);
+
+        assert_eq!(
+            run(&mut state, r#"{"type": "SetPlayerName", "name": "  "}"#),
+            CommandResult::Error {
+                code: "name_empty".to_string(),
+                message: "Enter a name".to_string(),
+            }
+        );
+        assert_eq!(state.current_screen, GameScreen::LoginScreen);
+
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "SetProfile", "profile": {"name": "Hero", "class": "Ranger", "appearance": 2}}"#
+            ),
+            CommandResult::Ok
+        );
+        assert_eq!(
+            state.profile(),
+            Some(PlayerProfile::new("Hero", PlayerClass::Ranger, 2).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_unknown_values_are_rejected() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        for json in [
+            r#"{"type": "TransitionTo", "screen": "Narnia"}"#,
+            r#"{"type": "SetRegion", "region": "Mars"}"#,
+            r#"{"type": "Teleport"}"#,
+            "not json",
+        ] {
+            let result = run(&mut state, json);
+            assert!(
+                matches!(&result, CommandResult::Error { code, .. } if code == "invalid_command"),
+                "{json} gave {result:?}"
+            );
+        }
+        assert_eq!(state.current_screen, GameScreen::GameHUD);
+        assert_eq!(state.selected_region, Some(Region::EU));
+    }
+
+    #[test]
+    fn test_buy_and_craft() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "BuyItem", "item_id": "cloth", "quantity": 2}"#
+            ),
+            CommandResult::Ok
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "Craft", "recipe_id": "bandage", "station": null}"#
+            ),
+            CommandResult::Ok
+        );
+        assert_eq!(state.inventory.count("bandage"), 1);
+    }
+
+    #[test]
+    fn test_failed_craft_leaves_inventory_clean() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        state.dirty.take();
+
+        let result = run(
+            &mut state,
+            r#"{"type": "Craft", "recipe_id": "bandage", "station": null}"#,
+        );
+        assert!(matches!(result, CommandResult::Error { .. }));
+        assert!(!state.dirty.is_dirty(StateSection::Inventory));
+    }
+
+    #[test]
+    fn test_failures_carry_code_and_message() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        state.gold = 0;
+
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "BuyItem", "item_id": "coal", "quantity": 1}"#
+            ),
+            CommandResult::Error {
+                code: "purchase_failed".to_string(),
+                message: "Costs 3 gold, you have 0".to_string(),
+            }
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "Craft", "recipe_id": "iron_ingot", "station": "Anvil"}"#
+            ),
+            CommandResult::Error {
+                code: "craft_failed".to_string(),
+                message: "This recipe requires a Forge".to_string(),
+            }
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                r#"{"type": "EquipItem", "item_id": "iron_sword"}"#
+            ),
+            CommandResult::Error {
+                code: "unsupported".to_string(),
+                message: "Equipment is not available yet".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_result_serialization() {
+        assert_eq!(
+            serde_json::to_value(CommandResult::Ok).unwrap(),
+            serde_json::json!({"status": "Ok"})
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::inventory::{Inventory, ItemStack};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Workstations a recipe can require
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub enum CraftingStation {
+    Workbench,
+    Forge,
+    Anvil,
+}
+
+impl CraftingStation {
+    /// Parse a station name coming from the frontend
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Workbench" => Some(CraftingStation::Workbench),
+            "Forge" => Some(CraftingStation::Forge),
+            "Anvil" => Some(CraftingStation::Anvil),
+            _ => None,
+        }
+    }
+}
+
+/// A single crafting recipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub id: String,
+    pub name: String,
+    pub inputs: Vec<ItemStack>,
+    pub outputs: Vec<ItemStack>,
+    /// Station the player must be at, `None` for hand-craftable recipes
+    pub station: Option<CraftingStation>,
+}
+
+/// An ingredient the player is short of
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingIngredient {
+    pub item_id: String,
+    pub required: u32,
+    pub available: u32,
+}
+
+/// Reasons a craft can be rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum CraftError {
+    UnknownRecipe(String),
+    WrongStation {
+        required: CraftingStation,
+        current: Option<CraftingStation>,
+    },
+    MissingIngredients(Vec<MissingIngredient>),
+    InventoryFull {
+        item_id: String,
+    },
+}
+
+impl fmt::Display for CraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CraftError::UnknownRecipe(id) => write!(f, "Unknown recipe: {id}"),
+            CraftError::WrongStation { required, .. } => {
+                write!(f, "This recipe requires a {required:?}")
+            }
+            CraftError::MissingIngredients(missing) => {
+                let items: Vec<String> = missing
+                    .iter()
+                    .map(|m| format!("{}x {}", m.required - m.available, m.item_id))
+                    .collect();
+                write!(f, "Missing ingredients: {}", items.join(", "))
+            }
+            CraftError::InventoryFull { item_id } => {
+                write!(f, "Not enough inventory space for {item_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CraftError {}
+
+/// Registry of all known recipes, keyed by recipe id
+#[derive(Debug, Clone, Default)]
+pub struct RecipeRegistry {
+    recipes: HashMap<String, Recipe>,
+}
+
+impl RecipeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry pre-filled with the blacksmith's starter recipes
+    pub fn with_default_recipes() -> Self {
+        let mut registry = Self::new();
+        registry.register(Recipe {
+            id: "iron_ingot".to_string(),
+            name: "Iron Ingot".to_string(),
+            inputs: vec![stack("iron_ore", 2), stack("coal", 1)],
+            outputs: vec![stack("iron_ingot", 1)],
+            station: Some(CraftingStation::Forge),
+        });
+        registry.register(Recipe {
+            id: "iron_sword".to_string(),
+            name: "Iron Sword".to_string(),
+            inputs: vec![stack("iron_ingot", 3), stack("wood", 1)],
+            outputs: vec![stack("iron_sword", 1)],
+            station: Some(CraftingStation::Anvil),
+        });
+        registry.register(Recipe {
+            id: "bandage".to_string(),
+            name: "Bandage".to_string(),
+            inputs: vec![stack("cloth", 2)],
+            outputs: vec![stack("bandage", 1)],
+            station: None,
+        });
+        registry
+    }
+
+    /// Add or replace a recipe
+    pub fn register(&mut self, recipe: Recipe) {
+        self.recipes.insert(recipe.id.clone(), recipe);
+    }
+
+    pub fn get(&self, recipe_id: &str) -> Option<&Recipe> {
+        self.recipes.get(recipe_id)
+    }
+
+    /// All recipes craftable at the given station (including hand recipes)
+    pub fn recipes_for_station(&self, station: Option<CraftingStation>) -> Vec<&Recipe> {
+        let mut recipes: Vec<&Recipe> = self
+            .recipes
+            .values()
+            .filter(|r| r.station.is_none() || r.station == station)
+            .collect();
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+        recipes
+    }
+
+    /// Craft a recipe, consuming inputs and producing outputs as one step.
+    /// The inventory is left untouched if any check fails.
+    pub fn craft(
+        &self,
+        recipe_id: &str,
+        inventory: &mut Inventory,
+        station: Option<CraftingStation>,
+    ) -> Result<&Recipe, CraftError> {
+        let recipe = self
+            .get(recipe_id)
+            .ok_or_else(|| CraftError::UnknownRecipe(recipe_id.to_string()))?;
+
+        if let Some(required) = recipe.station {
+            if station != Some(required) {
+                return Err(CraftError::WrongStation {
+                    required,
+                    current: station,
+                });
+            }
+        }
+
+        let missing: Vec<MissingIngredient> = recipe
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let available = inventory.count(&input.item_id);
+                (available < input.quantity).then(|| MissingIngredient {
+                    item_id: input.item_id.clone(),
+                    required: input.quantity,
+                    available,
+                })
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(CraftError::MissingIngredients(missing));
+        }
+
+        // Apply to a copy so a failure part way through can't lose items
+        let mut staged = inventory.clone();
+        for input in &recipe.inputs {
+            staged.remove(&input.item_id, input.quantity).map_err(|_| {
+                CraftError::MissingIngredients(vec![MissingIngredient {
+                    item_id: input.item_id.clone(),
+                    required: input.quantity,
+                    available: inventory.count(&input.item_id),
+                }])
+            })?;
+        }
+        for output in &recipe.outputs {
+            staged.add(&output.item_id, output.quantity).map_err(|_| {
+                CraftError::InventoryFull {
+                    item_id: output.item_id.clone(),
+                }
+            })?;
+        }
+
+        *inventory = staged;
+        Ok(recipe)
+    }
+}
+
+fn stack(item_id: &str, quantity: u32) -> ItemStack {
+    ItemStack {
+        item_id: item_id.to_string(),
+        quantity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::MAX_STACK_SIZE;
+
+    fn stocked_inventory() -> Inventory {
+        let mut inventory = Inventory::new(4);
+        inventory.add("iron_ore", 4).unwrap();
+        inventory.add("coal", 2).unwrap();
+        inventory
+    }
+
+    #[test]
+    fn test_craft_consumes_inputs_and_produces_outputs() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = stocked_inventory();
+
+        let recipe = registry
+            .craft("iron_ingot", &mut inventory, Some(CraftingStation::Forge))
+            .unwrap();
+
+        assert_eq!(recipe.name, "Iron Ingot");
+        assert_eq!(inventory.count("iron_ore"), 2);
+        assert_eq!(inventory.count("coal"), 1);
+        assert_eq!(inventory.count("iron_ingot"), 1);
+    }
+
+    #[test]
+    fn test_unknown_recipe() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = stocked_inventory();
+
+        assert_eq!(
+            registry.craft("mithril_crown", &mut inventory, None),
+            Err(CraftError::UnknownRecipe("mithril_crown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_wrong_station() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = stocked_inventory();
+        let before = inventory.clone();
+
+        assert_eq!(
+            registry.craft("iron_ingot", &mut inventory, Some(CraftingStation::Anvil)),
+            Err(CraftError::WrongStation {
+                required: CraftingStation::Forge,
+                current: Some(CraftingStation::Anvil),
+            })
+        );
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn test_missing_ingredients_leave_inventory_untouched() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = Inventory::new(4);
+        inventory.add("iron_ingot", 1).unwrap();
+        let before = inventory.clone();
+
+        let result = registry.craft("iron_sword", &mut inventory, Some(CraftingStation::Anvil));
+
+        assert_eq!(
+            result,
+            Err(CraftError::MissingIngredients(vec![
+                MissingIngredient {
+                    item_id: "iron_ingot".to_string(),
+                    required: 3,
+                    available: 1,
+                },
+                MissingIngredient {
+                    item_id: "wood".to_string(),
+                    required: 1,
+                    available: 0,
+                },
+            ]))
+        );
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn test_inventory_full_rolls_back() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = Inventory::new(2);
+        inventory.add("cloth", MAX_STACK_SIZE).unwrap();
+        inventory.add("rock", 1).unwrap();
+        let before = inventory.clone();
+
+        assert_eq!(
+            registry.craft("bandage", &mut inventory, None),
+            Err(CraftError::InventoryFull {
+                item_id: "bandage".to_string(),
+            })
+        );
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn test_consumed_slot_makes_room_for_output() {
+        let registry = RecipeRegistry::with_default_recipes();
+        let mut inventory = Inventory::new(1);
+        inventory.add("cloth", 2).unwrap();
+
+        registry.craft("bandage", &mut inventory, None).unwrap();
+        assert_eq!(inventory.count("bandage"), 1);
+        assert_eq!(inventory.count("cloth"), 0);
+    }
+
+    #[test]
+    fn test_recipes_for_station() {
+        let registry = RecipeRegistry::with_default_recipes();
+
+        let ids: Vec<&str> = registry
+            .recipes_for_station(Some(CraftingStation::Anvil))
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["bandage", "iron_sword"]);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::chat::ChatError;
+use crate::commands::CommandError;
+use crate::network::NetworkError;
+use crate::party::PartyError;
+use crate::render::RenderError;
+use crate::save::SaveError;
+use crate::save_slots::SlotError;
+use crate::trade::TradeError;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+/// Maximum number of undrained errors kept before the oldest are dropped
+pub const MAX_QUEUED_ERRORS: usize = 50;
+
+/// How loudly the UI should surface an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorSeverity {
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+/// Everything that can go wrong in the game core
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    /// Input payload from the frontend couldn't be parsed
+    InputParse {
+        event_type: String,
+        data: String,
+    },
+    /// Input event type the game doesn't know
+    UnknownInput {
+        event_type: String,
+    },
+    Command(CommandError),
+    Save(SaveError),
+    Slot(SlotError),
+    /// Autosave failed; the game keeps running
+    Autosave(SlotError),
+    Network(NetworkError),
+    /// A chat message wasn't sent
+    Chat(ChatError),
+    /// A party action was refused
+    Party(PartyError),
+    /// A trade action was refused
+    Trade(TradeError),
+    /// A frame couldn't be drawn
+    Render(RenderError),
+    /// A Rust panic caught by the panic hook
+    Panic {
+        message: String,
+    },
+    /// Free-form message set through `GameState::set_error`
+    Message(String),
+}
+
+impl GameError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::InputParse { .. } => "input_parse",
+            GameError::UnknownInput { .. } => "unknown_input",
+            GameError::Command(e) => e.code(),
+            GameError::Save(_) => "save_failed",
+            GameError::Slot(_) => "save_slot_failed",
+            GameError::Autosave(_) => "autosave_failed",
+            GameError::Network(e) => e.code(),
+            GameError::Chat(e) => e.code(),
+            GameError::Party(e) => e.code(),
+            GameError::Trade(e) => e.code(),
+            GameError::Render(e) => e.code(),
+            GameError::Panic { .. } => "panic",
+            GameError::Message(_) => "message",
+        }
+    }
+
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            GameError::UnknownInput { .. } => ErrorSeverity::Info,
+            GameError::InputParse { .. }
+            | GameError::Autosave(_)
+            | GameError::Chat(_)
+            | GameError::Party(_)
+            | GameError::Trade(_) => ErrorSeverity::Warning,
+            GameError::Network(e) if e.is_recoverable() => ErrorSeverity::Warning,
+            GameError::Command(_)
+            | GameError::Save(_)
+            | GameError::Slot(_)
+            | GameError::Network(_)
+            | GameError::Render(_)
+            | GameError::Message(_) => ErrorSeverity::Error,
+            GameError::Panic { .. } => ErrorSeverity::Fatal,
+        }
+    }
+
+    /// Extra key-value details for bug reports
+    pub fn context(&self) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        match self {
+            GameError::InputParse { event_type, data } => {
+                context.insert("event_type".to_string(), event_type.clone());
+                context.insert("data".to_string(), data.clone());
+            }
+            GameError::UnknownInput { event_type } => {
+                context.insert("event_type".to_string(), event_type.clone());
+            }
+            GameError::Slot(SlotError::SlotNotFound(slot) | SlotError::InvalidSlotName(slot)) => {
+                context.insert("slot".to_string(), slot.clone());
+            }
+            _ => {}
+        }
+        context
+    }
+
+    /// Serializable snapshot of the error for the frontend
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code().to_string(),
+            severity: self.severity(),
+            message: self.to_string(),
+            context: self.context(),
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::InputParse { event_type, data } => {
+                write!(f, "Failed to parse {event_type} input: {data}")
+            }
+            GameError::UnknownInput { event_type } => {
+                write!(f, "Unknown input event type: {event_type}")
+            }
+            GameError::Command(e) => e.fmt(f),
+            GameError::Save(e) => e.fmt(f),
+            GameError::Slot(e) => e.fmt(f),
+            GameError::Autosave(e) => write!(f, "Autosave failed: {e}"),
+            GameError::Network(e) => e.fmt(f),
+            GameError::Chat(e) => e.fmt(f),
+            GameError::Party(e) => e.fmt(f),
+            GameError::Trade(e) => e.fmt(f),
+            GameError::Render(e) => e.fmt(f),
+            GameError::Panic { message } => write!(f, "The game crashed: {message}"),
+            GameError::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<CommandError> for GameError {
+    fn from(e: CommandError) -> Self {
+        GameError::Command(e)
+    }
+}
+
+impl From<SaveError> for GameError {
+    fn from(e: SaveError) -> Self {
+        GameError::Save(e)
+    }
+}
+
+impl From<ChatError> for GameError {
+    fn from(e: ChatError) -> Self {
+        GameError::Chat(e)
+    }
+}
+
+impl From<PartyError> for GameError {
+    fn from(e: PartyError) -> Self {
+        GameError::Party(e)
+    }
+}
+
+impl From<TradeError> for GameError {
+    fn from(e: TradeError) -> Self {
+        GameError::Trade(e)
+    }
+}
+
+impl From<NetworkError> for GameError {
+    fn from(e: NetworkError) -> Self {
+        GameError::Network(e)
+    }
+}
+
+impl From<SlotError> for GameError {
+    fn from(e: SlotError) -> Self {
+        GameError::Slot(e)
+    }
+}
+
+/// Error as delivered to the UI's `ErrorToast`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct ErrorReport {
+    pub code: String,
+    pub severity: ErrorSeverity,
+    pub message: String,
+    pub context: BTreeMap<String, String>,
+}
+
+/// Bounded FIFO of errors waiting for the UI to show them
+#[derive(Debug, Clone, Default)]
+pub struct ErrorQueue {
+    reports: VecDeque<ErrorReport>,
+}
+
+impl ErrorQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, report: ErrorReport) {
+        if self.reports.len() >= MAX_QUEUED_ERRORS {
+            self.reports.pop_front();
+        }
+        self.reports.push_back(report);
+    }
+
+    /// Remove and return all queued errors, oldest first
+    pub fn drain(&mut self) -> Vec<ErrorReport> {
+        self.reports.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+}
+
+thread_local! {
+    // Panics are recorded here because the hook can't reach the `Game` instance
+    static PANIC_REPORTS: RefCell<ErrorQueue> = RefCell::new(ErrorQueue::new());
+}
+
+/// Install a panic hook that logs to the console and records the panic
+/// so the UI can pick it up with the other errors
+pub fn install_panic_hook() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            console_error_panic_hook::hook(info);
+            record_panic(info.to_string());
+        }));
+    });
+}
+
+/// Queue a panic report
+pub fn record_panic(message: String) {
+    let report = GameError::Panic { message }.report();
+    // Never panic inside the panic hook, even if the queue is mid-borrow
+    let _ = PANIC_REPORTS.try_with(|reports| {
+        if let Ok(mut reports) = reports.try_borrow_mut() {
+            reports.push(report);
+        }
+    });
+}
+
+/// Take all recorded panic reports
+pub fn take_panic_reports() -> Vec<ErrorReport> {
+    PANIC_REPORTS
+        .try_with(|reports| reports.borrow_mut().drain())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shop::PurchaseError;
+
+    #[test]
+    fn test_report_fields() {
+        let error = GameError::InputParse {
+            event_type: "mouseclick".to_string(),
+            data: "oops".to_string(),
+        };
+        let report = error.report();
+
+        assert_eq!(report.code, "input_parse");
+        assert_eq!(report.severity, ErrorSeverity::Warning);
+        assert_eq!(report.message, "Failed to parse mouseclick input: oops");
+        assert_eq!(report.context["event_type"], "mouseclick");
+        assert_eq!(report.context["data"], "oops");
+    }
+
+    #[test]
+    fn test_command_errors_keep_their_code() {
+        let error: GameError = CommandError::Purchase(PurchaseError::InvalidQuantity).into();
+        assert_eq!(error.code(), "purchase_failed");
+        assert_eq!(error.severity(), ErrorSeverity::Error);
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut queue = ErrorQueue::new();
+        for i in 0..MAX_QUEUED_ERRORS + 5 {
+            queue.push(GameError::Message(i.to_string()).report());
+        }
+
+        assert_eq!(queue.len(), MAX_QUEUED_ERRORS);
+        assert_eq!(queue.drain()[0].message, "5");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_panics_are_recorded() {
+        record_panic("index out of bounds".to_string());
+
+        let reports = take_panic_reports();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "panic");
+        assert_eq!(reports[0].severity, ErrorSeverity::Fatal);
+        assert!(take_panic_reports().is_empty());
+    }
+
+    #[test]
+    fn test_network_errors_downgrade_while_reconnecting() {
+        let lost = GameError::from(NetworkError::ConnectionLost);
+        assert_eq!(lost.severity(), ErrorSeverity::Warning);
+        assert_eq!(lost.code(), "network_connection_lost");
+
+        let gave_up = GameError::from(NetworkError::GaveUp { attempts: 8 });
+        assert_eq!(gave_up.severity(), ErrorSeverity::Error);
+
+        let limited = GameError::from(ChatError::RateLimited { retry_in_ms: 1500 });
+        assert_eq!(limited.severity(), ErrorSeverity::Warning);
+        assert_eq!(limited.code(), "chat_rate_limited");
+        assert!(limited.to_string().contains("1.5s"));
+    }
+
+    #[test]
+    fn test_severity_serialized_lowercase() {
+        assert_eq!(
+            serde_json::to_value(ErrorSeverity::Warning).unwrap(),
+            serde_json::json!("warning")
+        );
+    }
+}
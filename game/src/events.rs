@@ -0,0 +1,114 @@
+use crate::game_state::GameScreen;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Maximum number of undelivered events kept before the oldest are dropped
+pub const MAX_QUEUED_EVENTS: usize = 256;
+
+/// Something the UI may want to react to immediately
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    ScreenChanged { from: GameScreen, to: GameScreen },
+    ItemPickedUp { item_id: String, quantity: u32 },
+    DamageTaken { amount: f64, remaining_hp: f64 },
+    ErrorRaised { message: String },
+}
+
+/// Bounded FIFO of events waiting to be delivered to the frontend
+#[derive(Debug, Clone, Default)]
+pub struct EventQueue {
+    events: VecDeque<GameEvent>,
+    dropped: u32,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        if self.events.len() >= MAX_QUEUED_EVENTS {
+            self.events.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.events.push_back(event);
+    }
+
+    /// Remove and return all queued events, oldest first
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Number of events discarded because nobody drained the queue in time
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_in_order() {
+        let mut queue = EventQueue::new();
+        queue.push(GameEvent::ErrorRaised {
+            message: "first".to_string(),
+        });
+        queue.push(GameEvent::ItemPickedUp {
+            item_id: "coal".to_string(),
+            quantity: 2,
+        });
+
+        let events = queue.drain();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            GameEvent::ErrorRaised {
+                message: "first".to_string()
+            }
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut queue = EventQueue::new();
+        for i in 0..MAX_QUEUED_EVENTS + 3 {
+            queue.push(GameEvent::ErrorRaised {
+                message: i.to_string(),
+            });
+        }
+
+        assert_eq!(queue.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(queue.dropped(), 3);
+        assert_eq!(
+            queue.drain()[0],
+            GameEvent::ErrorRaised {
+                message: "3".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_serialized_with_type_tag() {
+        let event = GameEvent::ScreenChanged {
+            from: GameScreen::GameHUD,
+            to: GameScreen::Shop,
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "ScreenChanged", "from": "GameHUD", "to": "Shop"})
+        );
+    }
+}
@@ -1,31 +1,191 @@
+use crate::change_tracking::{DirtySections, StateSection};
+use crate::errors::{ErrorQueue, GameError};
+use crate::events::{EventQueue, GameEvent};
+use crate::inventory::{Inventory, InventoryError};
+use crate::party::PartyView;
+use crate::profile::{self, NameError, PlayerClass, PlayerProfile, ProfileError};
+use crate::trade::{TradeError, TradeOffer, TradeView};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use wasm_bindgen::prelude::*;
 
-/// Represents the different screens/states of the RPG game
-/// Now simplified to only include the game HUD and modal overlays
+/// Represents the different screens/states of the RPG game:
+/// the front-end flow, the game HUD and modal overlays
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
 #[wasm_bindgen]
 pub enum GameScreen {
+    LoginScreen,
+    ServerSelection,
+    MainMenu,
     GameHUD,
     Inventory,
     Shop,
+    Trade,
     HelpModal,
 }
 
+impl GameScreen {
+    /// Parse a screen name coming from the frontend
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "LoginScreen" => Some(GameScreen::LoginScreen),
+            "ServerSelection" => Some(GameScreen::ServerSelection),
+            "MainMenu" => Some(GameScreen::MainMenu),
+            "GameHUD" => Some(GameScreen::GameHUD),
+            "Inventory" => Some(GameScreen::Inventory),
+            "Shop" => Some(GameScreen::Shop),
+            "Trade" => Some(GameScreen::Trade),
+            "HelpModal" => Some(GameScreen::HelpModal),
+            _ => None,
+        }
+    }
+
+    /// Whether this screen sits at the bottom of the screen stack
+    pub fn is_root(&self) -> bool {
+        self.is_front_end() || *self == GameScreen::GameHUD
+    }
+
+    /// Whether this screen is part of the flow before entering the world
+    pub fn is_front_end(&self) -> bool {
+        matches!(
+            self,
+            GameScreen::LoginScreen | GameScreen::ServerSelection | GameScreen::MainMenu
+        )
+    }
+
+    /// The front-end screen `Escape` goes back to
+    pub fn previous_in_flow(&self) -> Option<GameScreen> {
+        match self {
+            GameScreen::ServerSelection => Some(GameScreen::LoginScreen),
+            GameScreen::MainMenu => Some(GameScreen::ServerSelection),
+            _ => None,
+        }
+    }
+
+    /// How this screen affects the world while it's open
+    pub fn pause_policy(&self) -> PausePolicy {
+        match self {
+            GameScreen::GameHUD => PausePolicy::Overlay,
+            GameScreen::Inventory | GameScreen::Shop | GameScreen::Trade => {
+                PausePolicy::BlockMovement
+            }
+            GameScreen::LoginScreen
+            | GameScreen::ServerSelection
+            | GameScreen::MainMenu
+            | GameScreen::HelpModal => PausePolicy::Pause,
+        }
+    }
+
+    /// Whether this screen may be opened on top of `below`
+    pub fn can_stack_on(&self, below: &GameScreen) -> bool {
+        match self {
+            GameScreen::LoginScreen
+            | GameScreen::ServerSelection
+            | GameScreen::MainMenu
+            | GameScreen::GameHUD => false,
+            GameScreen::Inventory => matches!(below, GameScreen::GameHUD | GameScreen::Shop),
+            GameScreen::Shop | GameScreen::Trade => matches!(below, GameScreen::GameHUD),
+            GameScreen::HelpModal => *below != GameScreen::HelpModal,
+        }
+    }
+}
+
+/// What an open screen does to the simulation, from least to most strict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PausePolicy {
+    /// The world runs and takes input as normal
+    Overlay,
+    /// The world runs but the player can't move
+    BlockMovement,
+    /// The world stops
+    Pause,
+}
+
+/// Reasons a screen stack operation can be rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScreenError {
+    CannotStack {
+        screen: GameScreen,
+        on: GameScreen,
+    },
+    /// The screen can't be reached before the player has a name
+    MissingPlayerName(GameScreen),
+    /// The screen can't be reached before a region is selected
+    MissingRegion(GameScreen),
+    /// The trade window only opens while a trade is in progress
+    NoActiveTrade,
+}
+
+impl fmt::Display for ScreenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenError::CannotStack { screen, on } => {
+                write!(f, "{screen:?} can't be opened on top of {on:?}")
+            }
+            ScreenError::MissingPlayerName(screen) => {
+                write!(f, "Choose a player name before opening {screen:?}")
+            }
+            ScreenError::MissingRegion(screen) => {
+                write!(f, "Select a region before opening {screen:?}")
+            }
+            ScreenError::NoActiveTrade => write!(f, "You're not trading with anyone"),
+        }
+    }
+}
+
+impl std::error::Error for ScreenError {}
+
 /// Available regions for server selection
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
 pub enum Region {
     EU,
     Asia,
     Vietnam,
 }
 
+impl Region {
+    /// Parse a region name coming from the frontend
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "EU" => Some(Region::EU),
+            "Asia" => Some(Region::Asia),
+            "Vietnam" => Some(Region::Vietnam),
+            _ => None,
+        }
+    }
+}
+
+/// Zone the player spawns in
+pub const STARTING_ZONE: &str = "Starter Village";
+
+/// Gold a new character starts with
+pub const STARTING_GOLD: u32 = 100;
+
+/// Maximum HP at level 1
+pub const BASE_MAX_HP: f64 = 100.0;
+
+/// Maximum HP gained per level
+pub const MAX_HP_PER_LEVEL: f64 = 10.0;
+
+/// XP needed to go from `level` to the next one
+pub fn xp_to_next_level(level: u32) -> u32 {
+    level.max(1).saturating_mul(100)
+}
+
 /// Core game state that manages the entire game flow
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
+    /// Top of the screen stack, the screen the player is looking at
     pub current_screen: GameScreen,
+    // Screens beneath `current_screen`, bottom first
+    #[serde(skip)]
+    pub screen_stack: Vec<GameScreen>,
     pub selected_region: Option<Region>,
     pub player_name: Option<String>,
+    pub player_class: PlayerClass,
+    pub appearance: u8,
     pub is_loading: bool,
     pub error_message: Option<String>,
     // Game world state (for when in GameHUD)
@@ -33,85 +193,560 @@ pub struct GameState {
     pub player_y: f64,
     pub world_width: f64,
     pub world_height: f64,
+    pub current_zone: String,
+    pub player_level: u32,
+    /// XP towards the next level
+    pub player_xp: u32,
+    pub player_hp: f64,
+    pub player_max_hp: f64,
+    pub playtime_secs: f64,
+    pub gold: u32,
+    pub inventory: Inventory,
     // Legacy ball physics (keeping for backward compatibility)
     pub ball_x: f64,
     pub ball_y: f64,
     pub ball_dx: f64,
     pub ball_dy: f64,
+    // Paused from outside the game, e.g. while the browser tab is hidden
+    #[serde(skip)]
+    pub paused: bool,
+    // Sections changed since the frontend last pulled a state delta
+    #[serde(skip)]
+    pub dirty: DirtySections,
+    // Events waiting to be delivered to the frontend
+    #[serde(skip)]
+    pub events: EventQueue,
+    // Errors waiting for the UI's error toast
+    #[serde(skip)]
+    pub errors: ErrorQueue,
+    // Party the player is in while connected
+    #[serde(skip)]
+    pub party: Option<PartyView>,
+    // Name of whoever invited the player to a party
+    #[serde(skip)]
+    pub party_invite: Option<String>,
+    // Trade the player is in while connected
+    #[serde(skip)]
+    pub trade: Option<TradeView>,
+    // Name of whoever asked the player to trade
+    #[serde(skip)]
+    pub trade_request: Option<String>,
+    // The local save's gold and items, set aside while the server's are shown
+    #[serde(skip)]
+    pub offline_belongings: Option<(u32, Inventory)>,
 }
 
 impl GameState {
     pub fn new(width: f64, height: f64) -> Self {
         Self {
-            current_screen: GameScreen::GameHUD,     // Start directly in game
-            selected_region: Some(Region::EU),       // Default region
-            player_name: Some("Player".to_string()), // Default player name
+            current_screen: GameScreen::LoginScreen,
+            screen_stack: Vec::new(),
+            selected_region: None,
+            player_name: None,
+            player_class: PlayerClass::default(),
+            appearance: 0,
             is_loading: false,
             error_message: None,
             player_x: width / 2.0,
             player_y: height / 2.0,
             world_width: width,
             world_height: height,
+            current_zone: STARTING_ZONE.to_string(),
+            player_level: 1,
+            player_xp: 0,
+            player_hp: BASE_MAX_HP,
+            player_max_hp: BASE_MAX_HP,
+            playtime_secs: 0.0,
+            gold: STARTING_GOLD,
+            inventory: Inventory::default(),
             // Initialize legacy ball physics for compatibility
             ball_x: width / 2.0,
             ball_y: height / 2.0,
             ball_dx: 3.0,
             ball_dy: 2.0,
+            paused: false,
+            dirty: DirtySections::default(),
+            events: EventQueue::new(),
+            errors: ErrorQueue::new(),
+            party: None,
+            party_invite: None,
+            trade: None,
+            trade_request: None,
+            offline_belongings: None,
+        }
+    }
+
+    /// Navigate to a screen. Root screens clear the stack, screens already
+    /// open are unwound to, and anything else is opened on top of the
+    /// deepest screen that accepts it.
+    pub fn transition_to(&mut self, screen: GameScreen) -> Result<(), ScreenError> {
+        self.check_flow(&screen)?;
+        let mut stack = self.screens();
+        stack.pop();
+        if screen.is_root() {
+            stack.clear();
+        } else if let Some(depth) = stack.iter().position(|open| *open == screen) {
+            stack.truncate(depth);
+        } else {
+            if !screen.can_stack_on(&self.current_screen) {
+                while stack
+                    .last()
+                    .is_some_and(|below| !screen.can_stack_on(below))
+                {
+                    stack.pop();
+                }
+            } else {
+                stack.push(self.current_screen.clone());
+            }
+            if stack.is_empty() {
+                self.check_flow(&GameScreen::GameHUD)?;
+                stack.push(GameScreen::GameHUD);
+            }
+        }
+        self.screen_stack = stack;
+        self.set_screen(screen);
+        Ok(())
+    }
+
+    /// Rebuild the screen stack, which saves don't keep, under a loaded
+    /// screen. A save made with a modal open goes back under the HUD if the
+    /// player could be in the world, and to the login screen otherwise.
+    pub fn restore_screen_stack(&mut self) {
+        if !self.screen_stack.is_empty() || self.current_screen.is_root() {
+            return;
         }
+        let below = if self.check_flow(&GameScreen::GameHUD).is_ok() {
+            GameScreen::GameHUD
+        } else {
+            GameScreen::LoginScreen
+        };
+        if self.current_screen.can_stack_on(&below) {
+            self.screen_stack.push(below);
+        } else {
+            self.current_screen = below;
+        }
+    }
+
+    /// Whether the player is in the game world rather than the front-end flow
+    pub fn in_world(&self) -> bool {
+        self.screen_stack.first().unwrap_or(&self.current_screen) == &GameScreen::GameHUD
     }
 
-    /// Transition to a new screen
-    pub fn transition_to(&mut self, screen: GameScreen) {
+    /// Check the name and region the front-end flow requires for a screen
+    fn check_flow(&self, screen: &GameScreen) -> Result<(), ScreenError> {
+        let needs_name = matches!(
+            screen,
+            GameScreen::ServerSelection | GameScreen::MainMenu | GameScreen::GameHUD
+        );
+        let needs_region = matches!(screen, GameScreen::MainMenu | GameScreen::GameHUD);
+
+        if needs_name && self.player_name.is_none() {
+            return Err(ScreenError::MissingPlayerName(screen.clone()));
+        }
+        if needs_region && self.selected_region.is_none() {
+            return Err(ScreenError::MissingRegion(screen.clone()));
+        }
+        if *screen == GameScreen::Trade && self.trade.is_none() {
+            return Err(ScreenError::NoActiveTrade);
+        }
+        Ok(())
+    }
+
+    /// Open a screen on top of the current one
+    pub fn push_screen(&mut self, screen: GameScreen) -> Result<(), ScreenError> {
+        self.check_flow(&screen)?;
+        if !screen.can_stack_on(&self.current_screen) {
+            return Err(ScreenError::CannotStack {
+                screen,
+                on: self.current_screen.clone(),
+            });
+        }
+        self.screen_stack.push(self.current_screen.clone());
+        self.set_screen(screen);
+        Ok(())
+    }
+
+    /// Close the current screen, returning false if it's a root screen
+    pub fn pop_screen(&mut self) -> bool {
+        match self.screen_stack.pop() {
+            Some(below) => self.set_screen(below),
+            // Screens restored from a save have no stack beneath them
+            None if !self.current_screen.is_root() => self.set_screen(GameScreen::GameHUD),
+            None => return false,
+        }
+        true
+    }
+
+    /// Swap the current screen for another that fits in its place
+    pub fn replace_screen(&mut self, screen: GameScreen) -> Result<(), ScreenError> {
+        self.check_flow(&screen)?;
+        let allowed = match self.screen_stack.last() {
+            Some(below) => screen.can_stack_on(below),
+            None => screen.is_root() || screen.can_stack_on(&GameScreen::GameHUD),
+        };
+        if !allowed {
+            return Err(ScreenError::CannotStack {
+                screen,
+                on: self
+                    .screen_stack
+                    .last()
+                    .cloned()
+                    .unwrap_or(GameScreen::GameHUD),
+            });
+        }
+        self.set_screen(screen);
+        Ok(())
+    }
+
+    /// The whole screen stack, bottom first, ending with the current screen
+    pub fn screens(&self) -> Vec<GameScreen> {
+        let mut screens = self.screen_stack.clone();
+        screens.push(self.current_screen.clone());
+        screens
+    }
+
+    fn set_screen(&mut self, screen: GameScreen) {
+        if self.current_screen != screen {
+            self.events.push(GameEvent::ScreenChanged {
+                from: self.current_screen.clone(),
+                to: screen.clone(),
+            });
+        }
         self.current_screen = screen;
         self.error_message = None;
+        self.dirty.mark(StateSection::Screen);
+        self.dirty.mark(StateSection::Status);
     }
 
-    /// Set the selected region for multiplayer
+    /// Set the selected region for multiplayer, moving on to the main menu
+    /// when picked on the server selection screen
     pub fn set_region(&mut self, region: Region) {
         self.selected_region = Some(region);
+        self.dirty.mark(StateSection::Region);
+        if self.current_screen == GameScreen::ServerSelection {
+            self.set_screen(GameScreen::MainMenu);
+        }
+    }
+
+    /// Validate and set the player name, moving on to server selection when
+    /// entered on the login screen
+    pub fn set_player_name(&mut self, name: &str) -> Result<(), NameError> {
+        self.player_name = Some(profile::validate_name(name)?);
+        self.profile_changed();
+        Ok(())
+    }
+
+    /// Validate and apply a whole profile from character creation
+    pub fn set_profile(&mut self, profile: PlayerProfile) -> Result<(), ProfileError> {
+        let profile = profile.validated()?;
+        self.player_name = Some(profile.name);
+        self.player_class = profile.class;
+        self.appearance = profile.appearance;
+        self.profile_changed();
+        Ok(())
+    }
+
+    /// The player's profile, once they've picked a name
+    pub fn profile(&self) -> Option<PlayerProfile> {
+        self.player_name.as_ref().map(|name| PlayerProfile {
+            name: name.clone(),
+            class: self.player_class,
+            appearance: self.appearance,
+        })
+    }
+
+    fn profile_changed(&mut self) {
+        self.dirty.mark(StateSection::Profile);
+        if self.current_screen == GameScreen::LoginScreen {
+            self.set_screen(GameScreen::ServerSelection);
+        }
+    }
+
+    /// Leave the main menu for the game world
+    pub fn enter_world(&mut self) -> Result<(), ScreenError> {
+        self.transition_to(GameScreen::GameHUD)
     }
 
-    /// Set player name (from login screen)
-    pub fn set_player_name(&mut self, name: String) {
-        self.player_name = Some(name);
+    /// Pause or resume the simulation regardless of the open screens
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused {
+            self.paused = paused;
+            self.dirty.mark(StateSection::Status);
+        }
+    }
+
+    /// Strictest policy among all open screens, or `Pause` if paused explicitly
+    pub fn pause_policy(&self) -> PausePolicy {
+        if self.paused {
+            return PausePolicy::Pause;
+        }
+        self.screen_stack
+            .iter()
+            .chain(std::iter::once(&self.current_screen))
+            .map(GameScreen::pause_policy)
+            .max()
+            .unwrap_or(PausePolicy::Overlay)
+    }
+
+    /// Whether the world is currently frozen
+    pub fn is_simulation_paused(&self) -> bool {
+        self.pause_policy() == PausePolicy::Pause
+    }
+
+    /// Advance the world by one frame, honouring the pause policy
+    pub fn step(&mut self, elapsed_secs: f64, dx: f64, dy: f64) {
+        let policy = self.pause_policy();
+        if policy == PausePolicy::Pause {
+            return;
+        }
+
+        self.add_playtime(elapsed_secs);
+        if policy == PausePolicy::Overlay && (dx != 0.0 || dy != 0.0) {
+            self.move_player(dx, dy);
+        }
+        self.update_ball_physics();
     }
 
     /// Set loading state
     pub fn set_loading(&mut self, loading: bool) {
         self.is_loading = loading;
+        self.dirty.mark(StateSection::Status);
     }
 
     /// Set error message
     pub fn set_error(&mut self, message: String) {
+        self.report_error(GameError::Message(message.clone()));
         self.error_message = Some(message);
+        self.dirty.mark(StateSection::Status);
+    }
+
+    /// Queue a structured error for the UI without touching `error_message`
+    pub fn report_error(&mut self, error: GameError) {
+        let report = error.report();
+        self.events.push(GameEvent::ErrorRaised {
+            message: report.message.clone(),
+        });
+        self.errors.push(report);
     }
 
     /// Clear error message
     pub fn clear_error(&mut self) {
         self.error_message = None;
+        self.dirty.mark(StateSection::Status);
     }
 
     /// Update player position (for movement in game world)
     pub fn move_player(&mut self, dx: f64, dy: f64) {
         // Player can always move when game is active
+        let (old_x, old_y) = (self.player_x, self.player_y);
         self.player_x = (self.player_x + dx).clamp(0.0, self.world_width);
         self.player_y = (self.player_y + dy).clamp(0.0, self.world_height);
+        if (self.player_x, self.player_y) != (old_x, old_y) {
+            self.dirty.mark(StateSection::PlayerPosition);
+        }
+    }
+
+    /// Place the player at an absolute position, e.g. one dictated by the server
+    pub fn set_player_position(&mut self, x: f64, y: f64) {
+        let (old_x, old_y) = (self.player_x, self.player_y);
+        self.player_x = x.clamp(0.0, self.world_width);
+        self.player_y = y.clamp(0.0, self.world_height);
+        if (self.player_x, self.player_y) != (old_x, old_y) {
+            self.dirty.mark(StateSection::PlayerPosition);
+        }
+    }
+
+    /// Move the player into a zone, returning whether the zone changed
+    pub fn enter_zone(&mut self, zone: &str) -> bool {
+        if self.current_zone == zone {
+            return false;
+        }
+        self.current_zone = zone.to_string();
+        self.dirty.mark(StateSection::Zone);
+        true
+    }
+
+    /// Accumulate time spent in game
+    pub fn add_playtime(&mut self, secs: f64) {
+        self.playtime_secs += secs.max(0.0);
+        self.dirty.mark(StateSection::Progress);
+    }
+
+    /// Add XP, levelling up as many times as it covers. Each level raises
+    /// maximum HP and heals fully. Returns the number of levels gained.
+    pub fn gain_xp(&mut self, amount: u32) -> u32 {
+        self.player_xp = self.player_xp.saturating_add(amount);
+        let mut levels = 0;
+        while self.player_xp >= xp_to_next_level(self.player_level) {
+            self.player_xp -= xp_to_next_level(self.player_level);
+            self.player_level += 1;
+            self.player_max_hp += MAX_HP_PER_LEVEL;
+            levels += 1;
+        }
+        if levels > 0 {
+            self.player_hp = self.player_max_hp;
+        }
+        self.dirty.mark(StateSection::Progress);
+        levels
+    }
+
+    /// Set the player's HP, telling the UI about any damage taken
+    pub fn set_player_hp(&mut self, hp: f64) {
+        let hp = if hp.is_finite() { hp } else { 0.0 };
+        let hp = hp.clamp(0.0, self.player_max_hp);
+        if hp < self.player_hp {
+            self.events.push(GameEvent::DamageTaken {
+                amount: self.player_hp - hp,
+                remaining_hp: hp,
+            });
+        }
+        self.player_hp = hp;
+        self.dirty.mark(StateSection::Progress);
+    }
+
+    pub fn set_party(&mut self, party: Option<PartyView>) {
+        if party.is_some() {
+            self.party_invite = None;
+        }
+        self.party = party;
+        self.dirty.mark(StateSection::Party);
+    }
+
+    pub fn set_party_invite(&mut self, from: Option<String>) {
+        self.party_invite = from;
+        self.dirty.mark(StateSection::Party);
+    }
+
+    pub fn set_trade(&mut self, trade: Option<TradeView>) {
+        if trade.is_some() {
+            self.trade_request = None;
+        }
+        self.trade = trade;
+        self.dirty.mark(StateSection::Trade);
+    }
+
+    pub fn set_trade_request(&mut self, from: Option<String>) {
+        self.trade_request = from;
+        self.dirty.mark(StateSection::Trade);
+    }
+
+    /// Apply a trade the server completed: hand over what was given and
+    /// take what was received. Nothing changes unless all of it fits.
+    pub fn apply_trade(
+        &mut self,
+        gave: &TradeOffer,
+        received: &TradeOffer,
+    ) -> Result<(), TradeError> {
+        if gave.gold > self.gold {
+            return Err(TradeError::NotEnoughGold);
+        }
+        let mut inventory = self.inventory.clone();
+        for stack in &gave.items {
+            inventory
+                .remove(&stack.item_id, stack.quantity)
+                .map_err(|_| TradeError::MissingItems(stack.item_id.clone()))?;
+        }
+        for stack in &received.items {
+            inventory.add(&stack.item_id, stack.quantity).map_err(|_| {
+                TradeError::NotEnoughSpace(self.player_name.clone().unwrap_or_default())
+            })?;
+        }
+
+        *self.inventory_mut() = inventory;
+        self.spend_gold(gave.gold);
+        self.add_gold(received.gold);
+        Ok(())
+    }
+
+    /// Take the gold and items the server holds for the player. The local
+    /// ones are set aside the first time, so saves keep them.
+    pub fn set_belongings(&mut self, gold: u32, inventory: Inventory) {
+        if self.offline_belongings.is_none() {
+            self.offline_belongings = Some((self.gold, self.inventory.clone()));
+        }
+        self.replace_belongings(gold, inventory);
+    }
+
+    /// Go back to the local gold and items after leaving the server
+    pub fn restore_offline_belongings(&mut self) {
+        if let Some((gold, inventory)) = self.offline_belongings.take() {
+            self.replace_belongings(gold, inventory);
+        }
+    }
+
+    fn replace_belongings(&mut self, gold: u32, inventory: Inventory) {
+        if self.gold != gold {
+            self.gold = gold;
+            self.dirty.mark(StateSection::Gold);
+        }
+        if self.inventory != inventory {
+            *self.inventory_mut() = inventory;
+        }
+    }
+
+    /// Add gold to the player's purse
+    pub fn add_gold(&mut self, amount: u32) {
+        self.gold = self.gold.saturating_add(amount);
+        self.dirty.mark(StateSection::Gold);
+    }
+
+    /// Take gold from the player's purse, returning false if they can't afford it
+    pub fn spend_gold(&mut self, amount: u32) -> bool {
+        if amount > self.gold {
+            return false;
+        }
+        self.gold -= amount;
+        self.dirty.mark(StateSection::Gold);
+        true
+    }
+
+    /// Pick up items from the world into the inventory
+    pub fn pick_up_item(&mut self, item_id: &str, quantity: u32) -> Result<(), InventoryError> {
+        self.inventory_mut().add(item_id, quantity)?;
+        self.events.push(GameEvent::ItemPickedUp {
+            item_id: item_id.to_string(),
+            quantity,
+        });
+        Ok(())
+    }
+
+    /// Mutable access to the inventory, flagging it as changed
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        self.dirty.mark(StateSection::Inventory);
+        &mut self.inventory
     }
 
     /// Reset to initial state
     pub fn reset(&mut self) {
-        self.current_screen = GameScreen::GameHUD; // Reset to game HUD
-        self.selected_region = Some(Region::EU); // Keep default region
-        self.player_name = Some("Player".to_string()); // Keep default name
+        self.current_screen = GameScreen::LoginScreen; // Back to the start of the flow
+        self.screen_stack.clear();
+        self.paused = false;
+        self.selected_region = None;
+        self.player_name = None;
+        self.player_class = PlayerClass::default();
+        self.appearance = 0;
         self.is_loading = false;
         self.error_message = None;
         self.player_x = self.world_width / 2.0;
         self.player_y = self.world_height / 2.0;
+        self.current_zone = STARTING_ZONE.to_string();
+        self.player_level = 1;
+        self.player_xp = 0;
+        self.player_hp = BASE_MAX_HP;
+        self.player_max_hp = BASE_MAX_HP;
+        self.party = None;
+        self.party_invite = None;
+        self.trade = None;
+        self.trade_request = None;
+        self.playtime_secs = 0.0;
+        self.gold = STARTING_GOLD;
+        self.inventory = Inventory::default();
         // Reset legacy ball physics
         self.ball_x = self.world_width / 2.0;
         self.ball_y = self.world_height / 2.0;
         self.ball_dx = 3.0;
         self.ball_dy = 2.0;
+        self.dirty.mark_all();
     }
 
     /// Legacy ball physics update (for backward compatibility)
@@ -137,6 +772,22 @@ impl GameState {
         self.ball_y = self
             .ball_y
             .clamp(BALL_RADIUS, self.world_height - BALL_RADIUS);
+        self.dirty.mark(StateSection::Ball);
+    }
+}
+
+#[cfg(test)]
+impl GameState {
+    /// A state that has already been through the login flow, for tests of
+    /// in-world behaviour
+    pub(crate) fn new_in_world(width: f64, height: f64) -> Self {
+        let mut state = Self::new(width, height);
+        state.set_player_name("Player").unwrap();
+        state.set_region(Region::EU);
+        state.enter_world().unwrap();
+        state.dirty.take();
+        state.events.drain();
+        state
     }
 }
 
@@ -148,9 +799,9 @@ mod tests {
     fn test_game_state_initialization() {
         let state = GameState::new(800.0, 600.0);
 
-        assert_eq!(state.current_screen, GameScreen::GameHUD);
-        assert_eq!(state.selected_region, Some(Region::EU));
-        assert_eq!(state.player_name, Some("Player".to_string()));
+        assert_eq!(state.current_screen, GameScreen::LoginScreen);
+        assert_eq!(state.selected_region, None);
+        assert_eq!(state.player_name, None);
         assert!(!state.is_loading);
         assert_eq!(state.error_message, None);
         assert_eq!(state.player_x, 400.0);
@@ -161,21 +812,122 @@ mod tests {
 
     #[test]
     fn test_screen_transitions() {
-        let mut state = GameState::new(800.0, 600.0);
+        let mut state = GameState::new_in_world(800.0, 600.0);
 
-        state.transition_to(GameScreen::Inventory);
+        state.transition_to(GameScreen::Inventory).unwrap();
         assert_eq!(state.current_screen, GameScreen::Inventory);
 
-        state.transition_to(GameScreen::Shop);
+        state.transition_to(GameScreen::Shop).unwrap();
         assert_eq!(state.current_screen, GameScreen::Shop);
 
-        state.transition_to(GameScreen::GameHUD);
+        state.transition_to(GameScreen::GameHUD).unwrap();
         assert_eq!(state.current_screen, GameScreen::GameHUD);
 
-        state.transition_to(GameScreen::HelpModal);
+        state.transition_to(GameScreen::HelpModal).unwrap();
         assert_eq!(state.current_screen, GameScreen::HelpModal);
     }
 
+    #[test]
+    fn test_front_end_flow() {
+        let mut state = GameState::new(800.0, 600.0);
+        assert!(!state.in_world());
+        assert!(state.is_simulation_paused());
+
+        // Can't skip ahead without a name and region
+        assert_eq!(
+            state.enter_world(),
+            Err(ScreenError::MissingPlayerName(GameScreen::GameHUD))
+        );
+        assert_eq!(
+            state.transition_to(GameScreen::Inventory),
+            Err(ScreenError::MissingPlayerName(GameScreen::GameHUD))
+        );
+
+        state.set_player_name("Hero").unwrap();
+        assert_eq!(state.current_screen, GameScreen::ServerSelection);
+        assert_eq!(
+            state.transition_to(GameScreen::MainMenu),
+            Err(ScreenError::MissingRegion(GameScreen::MainMenu))
+        );
+
+        state.set_region(Region::Vietnam);
+        assert_eq!(state.current_screen, GameScreen::MainMenu);
+        assert_eq!(
+            GameScreen::MainMenu.previous_in_flow(),
+            Some(GameScreen::ServerSelection)
+        );
+
+        state.enter_world().unwrap();
+        assert!(state.in_world());
+        assert!(!state.pop_screen());
+
+        // Changing the name in game doesn't restart the flow
+        state.set_player_name("Hero II").unwrap();
+        assert_eq!(state.current_screen, GameScreen::GameHUD);
+    }
+
+    #[test]
+    fn test_screen_stack() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        state.push_screen(GameScreen::Shop).unwrap();
+        state.push_screen(GameScreen::HelpModal).unwrap();
+        assert_eq!(
+            state.screens(),
+            vec![GameScreen::GameHUD, GameScreen::Shop, GameScreen::HelpModal]
+        );
+        assert_eq!(
+            state.push_screen(GameScreen::Inventory),
+            Err(ScreenError::CannotStack {
+                screen: GameScreen::Inventory,
+                on: GameScreen::HelpModal,
+            })
+        );
+
+        state.replace_screen(GameScreen::Inventory).unwrap();
+        assert_eq!(state.current_screen, GameScreen::Inventory);
+        assert!(state.pop_screen());
+        assert_eq!(state.current_screen, GameScreen::Shop);
+        assert!(state.pop_screen());
+        assert!(!state.pop_screen());
+        assert_eq!(state.screens(), vec![GameScreen::GameHUD]);
+    }
+
+    #[test]
+    fn test_transition_to_keeps_stack_consistent() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        state.transition_to(GameScreen::Shop).unwrap();
+        state.transition_to(GameScreen::Inventory).unwrap();
+        assert_eq!(
+            state.screens(),
+            vec![GameScreen::GameHUD, GameScreen::Shop, GameScreen::Inventory]
+        );
+
+        // Going back to an open screen unwinds to it
+        state.transition_to(GameScreen::Shop).unwrap();
+        assert_eq!(state.screens(), vec![GameScreen::GameHUD, GameScreen::Shop]);
+
+        // The shop only opens over the HUD, so everything above it closes
+        state.transition_to(GameScreen::GameHUD).unwrap();
+        state.transition_to(GameScreen::Inventory).unwrap();
+        state.transition_to(GameScreen::HelpModal).unwrap();
+        state.transition_to(GameScreen::Shop).unwrap();
+        assert_eq!(state.screens(), vec![GameScreen::GameHUD, GameScreen::Shop]);
+
+        state.transition_to(GameScreen::GameHUD).unwrap();
+        assert_eq!(state.screens(), vec![GameScreen::GameHUD]);
+    }
+
+    #[test]
+    fn test_pop_without_stack_returns_to_hud() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.current_screen = GameScreen::Inventory;
+
+        assert!(state.pop_screen());
+        assert_eq!(state.current_screen, GameScreen::GameHUD);
+    }
+
     #[test]
     fn test_region_selection() {
         let mut state = GameState::new(800.0, 600.0);
@@ -211,21 +963,47 @@ mod tests {
 
     #[test]
     fn test_movement_in_modal_screens() {
-        let mut state = GameState::new(800.0, 600.0);
-
-        // Movement should work even when in modal screens (overlay game)
-        state.transition_to(GameScreen::Inventory);
+        let mut state = GameState::new_in_world(800.0, 600.0);
         let initial_x = state.player_x;
-        let initial_y = state.player_y;
 
-        state.move_player(10.0, -5.0);
-        assert_eq!(state.player_x, initial_x + 10.0); // Should change
-        assert_eq!(state.player_y, initial_y - 5.0); // Should change
+        // The inventory blocks movement but the world keeps running
+        state.transition_to(GameScreen::Inventory).unwrap();
+        let ball_x = state.ball_x;
+        state.step(0.5, 10.0, -5.0);
+        assert_eq!(state.player_x, initial_x);
+        assert_ne!(state.ball_x, ball_x);
+        assert_eq!(state.playtime_secs, 0.5);
+
+        state.transition_to(GameScreen::GameHUD).unwrap();
+        state.step(0.5, 10.0, -5.0);
+        assert_eq!(state.player_x, initial_x + 10.0);
+    }
+
+    #[test]
+    fn test_pause_policy() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        assert_eq!(state.pause_policy(), PausePolicy::Overlay);
+
+        // Help over the shop pauses; the strictest open screen wins
+        state.push_screen(GameScreen::Shop).unwrap();
+        assert_eq!(state.pause_policy(), PausePolicy::BlockMovement);
+        state.push_screen(GameScreen::HelpModal).unwrap();
+        assert!(state.is_simulation_paused());
+
+        let (ball_x, playtime) = (state.ball_x, state.playtime_secs);
+        state.step(1.0, 0.0, 0.0);
+        assert_eq!((state.ball_x, state.playtime_secs), (ball_x, playtime));
+
+        state.transition_to(GameScreen::GameHUD).unwrap();
+        state.set_paused(true);
+        assert!(state.is_simulation_paused());
+        state.set_paused(false);
+        assert_eq!(state.pause_policy(), PausePolicy::Overlay);
     }
 
     #[test]
     fn test_error_handling() {
-        let mut state = GameState::new(800.0, 600.0);
+        let mut state = GameState::new_in_world(800.0, 600.0);
 
         state.set_error("Test error".to_string());
         assert_eq!(state.error_message, Some("Test error".to_string()));
@@ -235,18 +1013,18 @@ mod tests {
 
         // Transitioning should clear errors
         state.set_error("Another error".to_string());
-        state.transition_to(GameScreen::Shop);
+        state.transition_to(GameScreen::Shop).unwrap();
         assert_eq!(state.error_message, None);
     }
 
     #[test]
     fn test_reset() {
-        let mut state = GameState::new(800.0, 600.0);
+        let mut state = GameState::new_in_world(800.0, 600.0);
 
         // Modify state
-        state.transition_to(GameScreen::Inventory);
+        state.transition_to(GameScreen::Inventory).unwrap();
         state.set_region(Region::Asia);
-        state.set_player_name("TestPlayer".to_string());
+        state.set_player_name("TestPlayer").unwrap();
         state.set_loading(true);
         state.set_error("Test error".to_string());
         state.move_player(100.0, 50.0);
@@ -254,15 +1032,236 @@ mod tests {
         // Reset should restore initial state
         state.reset();
 
-        assert_eq!(state.current_screen, GameScreen::GameHUD);
-        assert_eq!(state.selected_region, Some(Region::EU));
-        assert_eq!(state.player_name, Some("Player".to_string()));
+        assert_eq!(state.current_screen, GameScreen::LoginScreen);
+        assert_eq!(state.selected_region, None);
+        assert_eq!(state.player_name, None);
         assert!(!state.is_loading);
         assert_eq!(state.error_message, None);
         assert_eq!(state.player_x, 400.0);
         assert_eq!(state.player_y, 300.0);
     }
 
+    #[test]
+    fn test_zone_changes() {
+        let mut state = GameState::new(800.0, 600.0);
+
+        assert_eq!(state.current_zone, STARTING_ZONE);
+        assert!(!state.enter_zone(STARTING_ZONE));
+        assert!(state.enter_zone("Dark Forest"));
+        assert_eq!(state.current_zone, "Dark Forest");
+
+        state.add_playtime(1.5);
+        state.add_playtime(-3.0);
+        assert_eq!(state.playtime_secs, 1.5);
+
+        state.reset();
+        assert_eq!(state.current_zone, STARTING_ZONE);
+        assert_eq!(state.playtime_secs, 0.0);
+    }
+
+    #[test]
+    fn test_mutations_mark_sections_dirty() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        assert!(state.dirty.is_empty());
+
+        state.transition_to(GameScreen::Shop).unwrap();
+        state.move_player(0.0, 0.0);
+        state.inventory_mut().add("coal", 1).unwrap();
+
+        let dirty = state.dirty.take();
+        assert!(dirty.is_dirty(StateSection::Screen));
+        assert!(dirty.is_dirty(StateSection::Status));
+        assert!(dirty.is_dirty(StateSection::Inventory));
+        // A move that goes nowhere isn't a change
+        assert!(!dirty.is_dirty(StateSection::PlayerPosition));
+
+        state.reset();
+        assert_eq!(state.dirty.iter().count(), StateSection::ALL.len());
+    }
+
+    #[test]
+    fn test_gold() {
+        let mut state = GameState::new(800.0, 600.0);
+        assert_eq!(state.gold, STARTING_GOLD);
+
+        assert!(state.spend_gold(STARTING_GOLD));
+        assert!(!state.spend_gold(1));
+        state.add_gold(25);
+        assert_eq!(state.gold, 25);
+        assert!(state.dirty.is_dirty(StateSection::Gold));
+    }
+
+    #[test]
+    fn test_xp_levels_up_and_heals() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.set_player_hp(40.0);
+        assert_eq!(
+            state.events.drain(),
+            [GameEvent::DamageTaken {
+                amount: BASE_MAX_HP - 40.0,
+                remaining_hp: 40.0,
+            }]
+        );
+
+        assert_eq!(state.gain_xp(99), 0);
+        assert_eq!(state.player_hp, 40.0);
+
+        // 1 XP finishes level 1, 200 more finishes level 2, 5 carry over
+        assert_eq!(state.gain_xp(206), 2);
+        assert_eq!(state.player_level, 3);
+        assert_eq!(state.player_xp, 5);
+        assert_eq!(state.player_max_hp, BASE_MAX_HP + 2.0 * MAX_HP_PER_LEVEL);
+        assert_eq!(state.player_hp, state.player_max_hp);
+        assert!(state.dirty.is_dirty(StateSection::Progress));
+
+        state.set_player_hp(-5.0);
+        assert_eq!(state.player_hp, 0.0);
+        state.set_player_hp(f64::NAN);
+        assert_eq!(state.player_hp, 0.0);
+        // Dropping to zero twice is damage; healing and staying at zero aren't
+        state.set_player_hp(10.0);
+        state.set_player_hp(0.0);
+        state.set_player_hp(0.0);
+        let damage: Vec<GameEvent> = state
+            .events
+            .drain()
+            .into_iter()
+            .filter(|event| matches!(event, GameEvent::DamageTaken { .. }))
+            .collect();
+        assert_eq!(damage.len(), 2);
+    }
+
+    #[test]
+    fn test_trade_screen_needs_a_trade() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        assert_eq!(
+            state.push_screen(GameScreen::Trade),
+            Err(ScreenError::NoActiveTrade)
+        );
+
+        state.set_trade(Some(TradeView {
+            partner_id: 2,
+            partner_name: "Bob".to_string(),
+            mine: Default::default(),
+            theirs: Default::default(),
+        }));
+        state.transition_to(GameScreen::Inventory).unwrap();
+        state.transition_to(GameScreen::Trade).unwrap();
+        assert_eq!(
+            state.screens(),
+            vec![GameScreen::GameHUD, GameScreen::Trade]
+        );
+        assert_eq!(state.pause_policy(), PausePolicy::BlockMovement);
+    }
+
+    #[test]
+    fn test_apply_trade() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.inventory.add("iron_ore", 5).unwrap();
+        let gave = TradeOffer {
+            items: vec![crate::inventory::ItemStack {
+                item_id: "iron_ore".to_string(),
+                quantity: 2,
+            }],
+            gold: 40,
+        };
+        let received = TradeOffer {
+            items: vec![crate::inventory::ItemStack {
+                item_id: "coal".to_string(),
+                quantity: 3,
+            }],
+            gold: 5,
+        };
+
+        state.apply_trade(&gave, &received).unwrap();
+        assert_eq!(state.inventory.count("iron_ore"), 3);
+        assert_eq!(state.inventory.count("coal"), 3);
+        assert_eq!(state.gold, STARTING_GOLD - 40 + 5);
+        assert!(state.dirty.is_dirty(StateSection::Inventory));
+    }
+
+    #[test]
+    fn test_failed_trade_changes_nothing() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.inventory.add("iron_ore", 1).unwrap();
+        let gave = TradeOffer {
+            items: vec![crate::inventory::ItemStack {
+                item_id: "iron_ore".to_string(),
+                quantity: 2,
+            }],
+            gold: 40,
+        };
+        let received = TradeOffer {
+            items: Vec::new(),
+            gold: 5,
+        };
+        state.dirty.take();
+
+        assert_eq!(
+            state.apply_trade(&gave, &received),
+            Err(TradeError::MissingItems("iron_ore".to_string()))
+        );
+        assert_eq!(state.inventory.count("iron_ore"), 1);
+        assert_eq!(state.gold, STARTING_GOLD);
+        assert!(!state.dirty.is_dirty(StateSection::Gold));
+        assert!(!state.dirty.is_dirty(StateSection::Inventory));
+    }
+
+    #[test]
+    fn test_name_parsing() {
+        assert_eq!(GameScreen::from_name("Shop"), Some(GameScreen::Shop));
+        assert_eq!(
+            GameScreen::from_name("LoginScreen"),
+            Some(GameScreen::LoginScreen)
+        );
+        assert_eq!(GameScreen::from_name("Narnia"), None);
+        assert_eq!(Region::from_name("Vietnam"), Some(Region::Vietnam));
+        assert_eq!(Region::from_name("Mars"), None);
+    }
+
+    #[test]
+    fn test_events_emitted() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        state.transition_to(GameScreen::GameHUD).unwrap(); // no change, no event
+        state.transition_to(GameScreen::Shop).unwrap();
+        state.pick_up_item("coal", 2).unwrap();
+        state.set_error("Out of range".to_string());
+
+        assert_eq!(
+            state.events.drain(),
+            vec![
+                GameEvent::ScreenChanged {
+                    from: GameScreen::GameHUD,
+                    to: GameScreen::Shop,
+                },
+                GameEvent::ItemPickedUp {
+                    item_id: "coal".to_string(),
+                    quantity: 2,
+                },
+                GameEvent::ErrorRaised {
+                    message: "Out of range".to_string(),
+                },
+            ]
+        );
+        assert_eq!(state.inventory.count("coal"), 2);
+    }
+
+    #[test]
+    fn test_errors_survive_transitions() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+
+        state.set_error("Out of range".to_string());
+        state.report_error(GameError::UnknownInput {
+            event_type: "gamepad".to_string(),
+        });
+        state.transition_to(GameScreen::Shop).unwrap();
+
+        assert_eq!(state.error_message, None);
+        let codes: Vec<String> = state.errors.drain().into_iter().map(|r| r.code).collect();
+        assert_eq!(codes, vec!["message", "unknown_input"]);
+    }
+
     #[test]
     fn test_ball_physics_compatibility() {
         let mut state = GameState::new(800.0, 600.0);
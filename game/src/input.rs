@@ -9,6 +9,9 @@ pub enum InputEvent {
     MoveLeft,
     MoveRight,
 
+    // Actions in the world
+    Attack,
+
     // UI interactions
     MenuSelect,
     MenuBack,
@@ -41,6 +44,8 @@ pub struct InputState {
 pub struct InputHandler {
     state: InputState,
     movement_speed: f64,
+    /// While typing a chat message, keys belong to the text box
+    chat_active: bool,
 }
 
 impl Default for InputHandler {
@@ -54,11 +59,28 @@ impl InputHandler {
         Self {
             state: InputState::default(),
             movement_speed: 5.0,
+            chat_active: false,
+        }
+    }
+
+    /// Start or stop capturing keystrokes for chat. Held movement keys are
+    /// released so the player doesn't keep walking while typing.
+    pub fn set_chat_active(&mut self, active: bool) {
+        if active && !self.chat_active {
+            self.release_all();
         }
+        self.chat_active = active;
+    }
+
+    pub fn is_chat_active(&self) -> bool {
+        self.chat_active
     }
 
     /// Process a key down event
     pub fn handle_key_down(&mut self, key_code: &str) -> Option<InputEvent> {
+        if self.chat_active {
+            return None;
+        }
         match key_code {
             "KeyW" | "ArrowUp" => {
                 self.state.move_up = true;
@@ -76,6 +98,7 @@ impl InputHandler {
                 self.state.move_right = true;
                 Some(InputEvent::MoveRight)
             }
+            "KeyF" => Some(InputEvent::Attack),
             "KeyI" => Some(InputEvent::ToggleInventory),
             "KeyT" => Some(InputEvent::ToggleShop),
             "KeyH" | "F1" => Some(InputEvent::ToggleHelp),
@@ -160,6 +183,16 @@ impl InputHandler {
         self.movement_speed = speed;
     }
 
+    /// Forget held keys and buttons, e.g. when key-up events may be missed
+    pub fn release_all(&mut self) {
+        let (mouse_x, mouse_y) = (self.state.mouse_x, self.state.mouse_y);
+        self.state = InputState {
+            mouse_x,
+            mouse_y,
+            ..InputState::default()
+        };
+    }
+
     /// Check if any movement key is pressed
     pub fn is_moving(&self) -> bool {
         self.state.move_up || self.state.move_down || self.state.move_left || self.state.move_right
@@ -170,6 +203,36 @@ impl InputHandler {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_release_all() {
+        let mut handler = InputHandler::new();
+        handler.handle_key_down("KeyW");
+        handler.handle_mouse_down(3.0, 4.0);
+
+        handler.release_all();
+        assert!(!handler.is_moving());
+        assert!(!handler.get_state().is_mouse_down);
+        assert_eq!(handler.get_state().mouse_x, 3.0);
+    }
+
+    #[test]
+    fn test_chat_mode_captures_keys() {
+        let mut handler = InputHandler::new();
+        handler.handle_key_down("KeyD");
+
+        handler.set_chat_active(true);
+        assert!(handler.is_chat_active());
+        assert!(!handler.is_moving());
+        assert_eq!(handler.handle_key_down("KeyW"), None);
+        assert_eq!(handler.handle_key_down("KeyI"), None);
+        assert_eq!(handler.handle_key_down("Escape"), None);
+        assert_eq!(handler.get_movement_delta(), (0.0, 0.0));
+
+        handler.set_chat_active(false);
+        assert_eq!(handler.handle_key_down("KeyW"), Some(InputEvent::MoveUp));
+        assert!(handler.is_moving());
+    }
+
     #[test]
     fn test_input_handler_initialization() {
         let handler = InputHandler::new();
@@ -185,6 +248,7 @@ mod tests {
         assert_eq!(handler.handle_key_down("KeyS"), Some(InputEvent::MoveDown));
         assert_eq!(handler.handle_key_down("KeyA"), Some(InputEvent::MoveLeft));
         assert_eq!(handler.handle_key_down("KeyD"), Some(InputEvent::MoveRight));
+        assert_eq!(handler.handle_key_down("KeyF"), Some(InputEvent::Attack));
         assert_eq!(
             handler.handle_key_down("KeyI"),
             Some(InputEvent::ToggleInventory)
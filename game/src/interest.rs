@@ -0,0 +1,189 @@
+use crate::protocol::PlayerId;
+use std::collections::{BTreeSet, HashMap};
+
+/// Settings for deciding which entities each client hears about
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterestConfig {
+    /// Side length of a grid cell, in world units
+    pub cell_size: f64,
+    /// Size of the area a client's camera shows around its player
+    pub view_width: f64,
+    pub view_height: f64,
+    /// Extra distance past the camera edge at which entities are sent, so
+    /// they're already known when they scroll into view
+    pub margin: f64,
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 200.0,
+            view_width: 800.0,
+            view_height: 600.0,
+            margin: 100.0,
+        }
+    }
+}
+
+impl InterestConfig {
+    /// Area around `(x, y)` whose entities a client should know about,
+    /// widened by `margin` on every side
+    pub fn area_around(&self, x: f64, y: f64, margin: f64) -> Area {
+        let half_width = self.view_width / 2.0 + margin;
+        let half_height = self.view_height / 2.0 + margin;
+        Area {
+            min_x: x - half_width,
+            min_y: y - half_height,
+            max_x: x + half_width,
+            max_y: y + half_height,
+        }
+    }
+}
+
+/// Axis-aligned rectangle in world units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Area {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+}
+
+type Cell = (i64, i64);
+
+/// Uniform grid bucketing entities by position, so finding what's near a
+/// point only looks at a few cells instead of every entity
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f64,
+    cells: HashMap<Cell, BTreeSet<PlayerId>>,
+    entities: HashMap<PlayerId, (f64, f64)>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            cells: HashMap::new(),
+            entities: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    fn cell(&self, x: f64, y: f64) -> Cell {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+
+    /// Add an entity or move it to a new position
+    pub fn update(&mut self, id: PlayerId, x: f64, y: f64) {
+        let cell = self.cell(x, y);
+        if let Some((old_x, old_y)) = self.entities.insert(id, (x, y)) {
+            let old_cell = self.cell(old_x, old_y);
+            if old_cell == cell {
+                return;
+            }
+            self.remove_from_cell(old_cell, id);
+        }
+        self.cells.entry(cell).or_default().insert(id);
+    }
+
+    pub fn remove(&mut self, id: PlayerId) {
+        if let Some((x, y)) = self.entities.remove(&id) {
+            self.remove_from_cell(self.cell(x, y), id);
+        }
+    }
+
+    fn remove_from_cell(&mut self, cell: Cell, id: PlayerId) {
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Every entity inside `area`
+    pub fn query(&self, area: &Area) -> BTreeSet<PlayerId> {
+        let (min_cx, min_cy) = self.cell(area.min_x, area.min_y);
+        let (max_cx, max_cy) = self.cell(area.max_x, area.max_y);
+
+        let mut found = BTreeSet::new();
+        for cx in min_cx..=max_cx {
+            for cy in min_cy..=max_cy {
+                let Some(ids) = self.cells.get(&(cx, cy)) else {
+                    continue;
+                };
+                found.extend(ids.iter().copied().filter(|id| {
+                    let (x, y) = self.entities[id];
+                    area.contains(x, y)
+                }));
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_finds_entities_in_area() {
+        let mut grid = SpatialGrid::new(100.0);
+        grid.update(1, 50.0, 50.0);
+        grid.update(2, 250.0, 50.0);
+        grid.update(3, -120.0, 900.0);
+
+        let area = Area {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 200.0,
+            max_y: 100.0,
+        };
+        assert_eq!(grid.query(&area), BTreeSet::from([1]));
+
+        grid.update(2, 199.0, 99.0);
+        assert_eq!(grid.query(&area), BTreeSet::from([1, 2]));
+
+        grid.remove(1);
+        assert_eq!(grid.query(&area), BTreeSet::from([2]));
+        assert_eq!(grid.len(), 2);
+    }
+
+    #[test]
+    fn test_moving_across_cells_leaves_no_stale_entries() {
+        let mut grid = SpatialGrid::new(10.0);
+        for step in 0..100 {
+            grid.update(7, step as f64 * 3.0, 0.0);
+        }
+        assert_eq!(grid.cells.len(), 1);
+
+        grid.remove(7);
+        assert!(grid.is_empty());
+        assert!(grid.cells.is_empty());
+    }
+
+    #[test]
+    fn test_area_includes_margin() {
+        let config = InterestConfig::default();
+        let area = config.area_around(0.0, 0.0, config.margin);
+        assert!(area.contains(500.0, 400.0));
+        assert!(!area.contains(501.0, 0.0));
+    }
+}
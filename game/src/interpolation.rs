@@ -0,0 +1,371 @@
+use crate::protocol::{PlayerId, PlayerSnapshot, WorldSnapshot};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Samples kept per remote entity
+pub const MAX_BUFFERED_SAMPLES: usize = 32;
+
+/// How quickly the estimated server clock catches up when snapshots start
+/// arriving later than before, per snapshot
+const CLOCK_CATCH_UP: f64 = 0.05;
+
+/// How remote entities are smoothed between snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpolationConfig {
+    /// How far behind the newest snapshot remote entities are drawn, so
+    /// there's usually a later sample to interpolate towards
+    pub delay_ms: f64,
+    /// How long to keep moving an entity along its last velocity when
+    /// snapshots stop arriving
+    pub max_extrapolation_ms: f64,
+    /// Jumps longer than this are teleports and snap instead of sliding
+    pub teleport_distance: f64,
+    /// Length of a server tick, which turns snapshot ticks into server time
+    pub tick_ms: f64,
+}
+
+impl Default for InterpolationConfig {
+    fn default() -> Self {
+        Self {
+            delay_ms: 100.0,
+            max_extrapolation_ms: 250.0,
+            teleport_distance: 100.0,
+            tick_ms: 50.0,
+        }
+    }
+}
+
+/// A remote entity's position at a point in server time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionSample {
+    pub time_ms: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A remote entity ready to draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteEntity {
+    pub id: PlayerId,
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct EntityBuffer {
+    name: String,
+    samples: VecDeque<PositionSample>,
+}
+
+impl EntityBuffer {
+    fn push(&mut self, sample: PositionSample, config: &InterpolationConfig) {
+        if let Some(last) = self.samples.back() {
+            if sample.time_ms < last.time_ms {
+                return;
+            }
+            if (sample.x - last.x).hypot(sample.y - last.y) > config.teleport_distance {
+                self.samples.clear();
+            }
+        }
+
+        // Keep one sample at or before the render time to interpolate from
+        let render_time = sample.time_ms - config.delay_ms;
+        while self.samples.len() >= 2 && self.samples[1].time_ms <= render_time {
+            self.samples.pop_front();
+        }
+        if self.samples.len() >= MAX_BUFFERED_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    fn position_at(&self, render_time: f64, config: &InterpolationConfig) -> Option<(f64, f64)> {
+        let first = self.samples.front()?;
+        if render_time <= first.time_ms {
+            return Some((first.x, first.y));
+        }
+
+        for (from, to) in self.samples.iter().zip(self.samples.iter().skip(1)) {
+            if render_time <= to.time_ms {
+                let t = (render_time - from.time_ms) / (to.time_ms - from.time_ms);
+                return Some((lerp(from.x, to.x, t), lerp(from.y, to.y, t)));
+            }
+        }
+
+        // Past the newest sample: keep going along the last velocity for a while
+        let last = self.samples.back()?;
+        let Some(before) = self.samples.iter().rev().nth(1) else {
+            return Some((last.x, last.y));
+        };
+        let span = last.time_ms - before.time_ms;
+        if span <= 0.0 {
+            return Some((last.x, last.y));
+        }
+        let ahead = (render_time - last.time_ms).min(config.max_extrapolation_ms);
+        Some((
+            last.x + (last.x - before.x) / span * ahead,
+            last.y + (last.y - before.y) / span * ahead,
+        ))
+    }
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+/// Buffers snapshots of other players and draws them a little in the past,
+/// so movement looks smooth despite snapshots arriving in bursts. Samples
+/// are stamped with the server time of their tick rather than when they
+/// arrived, so a burst still spreads out over the ticks it covers.
+#[derive(Debug, Clone, Default)]
+pub struct Interpolator {
+    config: InterpolationConfig,
+    entities: BTreeMap<PlayerId, EntityBuffer>,
+    /// Local time minus server time, as estimated from snapshot arrivals
+    clock_offset_ms: Option<f64>,
+    /// Server time of the newest snapshot
+    server_time_ms: f64,
+}
+
+impl Interpolator {
+    pub fn new(config: InterpolationConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> &InterpolationConfig {
+        &self.config
+    }
+
+    /// Use the server's tick rate, from its `Welcome`
+    pub fn set_tick_rate(&mut self, tick_rate: u16) {
+        self.config.tick_ms = 1000.0 / f64::from(tick_rate.max(1));
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.clock_offset_ms = None;
+        self.server_time_ms = 0.0;
+    }
+
+    /// Buffer a snapshot received at `received_at_ms`, skipping the local
+    /// player (who is predicted instead) and forgetting players who left
+    pub fn push_snapshot(
+        &mut self,
+        snapshot: &WorldSnapshot,
+        local_player: Option<PlayerId>,
+        received_at_ms: f64,
+    ) {
+        let server_time_ms = snapshot.tick as f64 * self.config.tick_ms;
+        self.sync_clock(server_time_ms, received_at_ms);
+        self.entities
+            .retain(|id, _| snapshot.players.iter().any(|player| player.id == *id));
+
+        for player in &snapshot.players {
+            if Some(player.id) != local_player {
+                self.push_sample(player, server_time_ms);
+            }
+        }
+    }
+
+    /// Buffer one remote player's position, e.g. when they come into view,
+    /// as of the newest snapshot
+    pub fn push_player(&mut self, player: &PlayerSnapshot) {
+        self.push_sample(player, self.server_time_ms);
+    }
+
+    fn push_sample(&mut self, player: &PlayerSnapshot, time_ms: f64) {
+        let buffer = self.entities.entry(player.id).or_default();
+        buffer.name.clone_from(&player.name);
+        buffer.push(
+            PositionSample {
+                time_ms,
+                x: player.x,
+                y: player.y,
+            },
+            &self.config,
+        );
+    }
+
+    /// Track the offset between the local and server clocks. The quickest
+    /// delivery is the best estimate, so earlier arrivals take over at once
+    /// while later ones only pull the estimate along slowly.
+    fn sync_clock(&mut self, server_time_ms: f64, received_at_ms: f64) {
+        self.server_time_ms = self.server_time_ms.max(server_time_ms);
+        let offset = received_at_ms - server_time_ms;
+        self.clock_offset_ms = Some(match self.clock_offset_ms {
+            Some(current) if offset > current => current + (offset - current) * CLOCK_CATCH_UP,
+            _ => offset,
+        });
+    }
+
+    /// The server time to draw at `now_ms`
+    fn render_time(&self, now_ms: f64) -> f64 {
+        now_ms - self.clock_offset_ms.unwrap_or(0.0) - self.config.delay_ms
+    }
+
+    /// Stop drawing a player who left the area of interest
+    pub fn remove(&mut self, id: PlayerId) {
+        self.entities.remove(&id);
+    }
+
+    /// Where to draw `id` at `now_ms`
+    pub fn position(&self, id: PlayerId, now_ms: f64) -> Option<(f64, f64)> {
+        self.entities
+            .get(&id)?
+            .position_at(self.render_time(now_ms), &self.config)
+    }
+
+    /// Every remote entity at `now_ms`
+    pub fn entities(&self, now_ms: f64) -> Vec<RemoteEntity> {
+        let render_time = self.render_time(now_ms);
+        self.entities
+            .iter()
+            .filter_map(|(&id, buffer)| {
+                let (x, y) = buffer.position_at(render_time, &self.config)?;
+                Some(RemoteEntity {
+                    id,
+                    name: buffer.name.clone(),
+                    x,
+                    y,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(tick: u64, players: &[(PlayerId, f64, f64)]) -> WorldSnapshot {
+        WorldSnapshot {
+            tick,
+            last_processed_input: 0,
+            players: players
+                .iter()
+                .map(|&(id, x, y)| PlayerSnapshot {
+                    id,
+                    name: format!("P{id}"),
+                    x,
+                    y,
+                })
+                .collect(),
+        }
+    }
+
+    /// Remote player 2 walking right 10 units every 50ms, local player 1 still
+    fn walking() -> Interpolator {
+        let mut interpolator = Interpolator::new(InterpolationConfig::default());
+        for step in 0..4 {
+            let x = 10.0 * step as f64;
+            interpolator.push_snapshot(
+                &snapshot(step, &[(1, 0.0, 0.0), (2, x, 0.0)]),
+                Some(1),
+                50.0 * step as f64,
+            );
+        }
+        interpolator
+    }
+
+    #[test]
+    fn test_renders_between_snapshots_after_delay() {
+        let interpolator = walking();
+
+        // 175ms minus the 100ms delay is halfway between the 50ms and 100ms samples
+        assert_eq!(interpolator.position(2, 175.0), Some((15.0, 0.0)));
+        assert_eq!(interpolator.position(2, 250.0), Some((30.0, 0.0)));
+        assert_eq!(interpolator.position(1, 175.0), None);
+
+        let entities = interpolator.entities(175.0);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].name, "P2");
+    }
+
+    #[test]
+    fn test_extrapolates_briefly_on_gaps() {
+        let interpolator = walking();
+
+        // Last sample at 150ms; 50ms past it the player keeps walking
+        assert_eq!(interpolator.position(2, 300.0), Some((40.0, 0.0)));
+
+        // ...but stops after the extrapolation limit
+        let limit = interpolator.config().max_extrapolation_ms;
+        let stopped = Some((30.0 + limit / 5.0, 0.0));
+        assert_eq!(interpolator.position(2, 250.0 + limit), stopped);
+        assert_eq!(interpolator.position(2, 5_000.0), stopped);
+    }
+
+    #[test]
+    fn test_snapshots_arriving_together_stay_apart() {
+        let mut interpolator = Interpolator::new(InterpolationConfig::default());
+        interpolator.push_snapshot(&snapshot(0, &[(2, 0.0, 0.0)]), None, 0.0);
+        // Ticks 1 and 2 are held up and drained in the same frame
+        interpolator.push_snapshot(&snapshot(1, &[(2, 10.0, 0.0)]), None, 120.0);
+        interpolator.push_snapshot(&snapshot(2, &[(2, 20.0, 0.0)]), None, 120.0);
+
+        assert_eq!(interpolator.entities[&2].samples.len(), 3);
+        // The clock estimate stays close to the on-time snapshot, so the
+        // burst still plays out one tick at a time
+        let x_at = |now_ms| interpolator.position(2, now_ms).unwrap().0;
+        assert!((x_at(150.0) - 10.0).abs() < 1.0);
+        assert!((x_at(175.0) - 15.0).abs() < 1.0);
+        assert!((x_at(200.0) - 20.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_entering_players_join_at_the_newest_tick() {
+        let mut interpolator = walking();
+        let player = PlayerSnapshot {
+            id: 3,
+            name: "P3".to_string(),
+            x: 7.0,
+            y: 0.0,
+        };
+        interpolator.push_player(&player);
+
+        assert_eq!(interpolator.entities[&3].samples[0].time_ms, 150.0);
+        assert_eq!(interpolator.position(3, 250.0), Some((7.0, 0.0)));
+    }
+
+    #[test]
+    fn test_teleports_snap() {
+        let mut interpolator = walking();
+        interpolator.push_snapshot(&snapshot(4, &[(2, 500.0, 300.0)]), None, 200.0);
+
+        assert_eq!(interpolator.position(2, 250.0), Some((500.0, 300.0)));
+        assert_eq!(interpolator.position(2, 400.0), Some((500.0, 300.0)));
+    }
+
+    #[test]
+    fn test_players_who_leave_are_dropped() {
+        let mut interpolator = walking();
+        interpolator.push_snapshot(&snapshot(4, &[(1, 0.0, 0.0)]), Some(1), 200.0);
+        assert!(interpolator.entities(250.0).is_empty());
+
+        let mut interpolator = walking();
+        interpolator.remove(2);
+        assert_eq!(interpolator.position(2, 175.0), None);
+    }
+
+    #[test]
+    fn test_buffer_is_bounded_and_ignores_old_samples() {
+        let mut interpolator = Interpolator::new(InterpolationConfig {
+            delay_ms: 10_000.0,
+            ..InterpolationConfig::default()
+        });
+        for step in 0..100 {
+            interpolator.push_snapshot(&snapshot(step, &[(2, 1.0, 0.0)]), None, step as f64);
+        }
+        // A tick older than the newest one, arriving late
+        interpolator.push_snapshot(&snapshot(50, &[(2, 50.0, 0.0)]), None, 100.0);
+
+        assert_eq!(
+            interpolator.entities[&2].samples.len(),
+            MAX_BUFFERED_SAMPLES
+        );
+        assert_eq!(interpolator.position(2, 20_000.0), Some((1.0, 0.0)));
+    }
+}
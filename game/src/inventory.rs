@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Default number of slots in a player's bag
+pub const DEFAULT_INVENTORY_SLOTS: usize = 20;
+
+/// Maximum quantity of a single item that fits in one slot
+pub const MAX_STACK_SIZE: u32 = 99;
+
+/// A stack of identical items occupying one inventory slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct ItemStack {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+/// Errors returned by inventory operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryError {
+    /// Not enough free slots to hold the items
+    NotEnoughSpace { item_id: String, quantity: u32 },
+    /// Fewer items in the bag than requested
+    NotEnoughItems {
+        item_id: String,
+        required: u32,
+        available: u32,
+    },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::NotEnoughSpace { item_id, quantity } => {
+                write!(f, "Not enough inventory space for {quantity}x {item_id}")
+            }
+            InventoryError::NotEnoughItems {
+                item_id,
+                required,
+                available,
+            } => write!(f, "Need {required}x {item_id}, only have {available}"),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/// Slot-based player inventory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct Inventory {
+    pub capacity: usize,
+    pub slots: Vec<ItemStack>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(DEFAULT_INVENTORY_SLOTS)
+    }
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Total quantity of an item across all slots
+    pub fn count(&self, item_id: &str) -> u32 {
+        self.slots
+            .iter()
+            .filter(|stack| stack.item_id == item_id)
+            .map(|stack| stack.quantity)
+            .sum()
+    }
+
+    /// Number of unused slots
+    pub fn free_slots(&self) -> usize {
+        self.capacity.saturating_sub(self.slots.len())
+    }
+
+    /// Check whether the given quantity of an item would fit
+    pub fn can_add(&self, item_id: &str, quantity: u32) -> bool {
+        let room_in_stacks: u32 = self
+            .slots
+            .iter()
+            .filter(|stack| stack.item_id == item_id)
+            .map(|stack| MAX_STACK_SIZE.saturating_sub(stack.quantity))
+            .sum();
+        let overflow = quantity.saturating_sub(room_in_stacks);
+        let slots_needed = overflow.div_ceil(MAX_STACK_SIZE) as usize;
+        slots_needed <= self.free_slots()
+    }
+
+    /// Add items, topping up existing stacks before opening new slots
+    pub fn add(&mut self, item_id: &str, quantity: u32) -> Result<(), InventoryError> {
+        if !self.can_add(item_id, quantity) {
+            return Err(InventoryError::NotEnoughSpace {
+                item_id: item_id.to_string(),
+                quantity,
+            });
+        }
+
+        let mut remaining = quantity;
+        for stack in self.slots.iter_mut().filter(|s| s.item_id == item_id) {
+            let moved = remaining.min(MAX_STACK_SIZE.saturating_sub(stack.quantity));
+            stack.quantity += moved;
+            remaining -= moved;
+        }
+        while remaining > 0 {
+            let moved = remaining.min(MAX_STACK_SIZE);
+            self.slots.push(ItemStack {
+                item_id: item_id.to_string(),
+                quantity: moved,
+            });
+            remaining -= moved;
+        }
+        Ok(())
+    }
+
+    /// Remove items, draining the last stacks first and freeing empty slots
+    pub fn remove(&mut self, item_id: &str, quantity: u32) -> Result<(), InventoryError> {
+        let available = self.count(item_id);
+        if available < quantity {
+            return Err(InventoryError::NotEnoughItems {
+                item_id: item_id.to_string(),
+                required: quantity,
+                available,
+            });
+        }
+
+        let mut remaining = quantity;
+        for stack in self.slots.iter_mut().rev().filter(|s| s.item_id == item_id) {
+            let taken = remaining.min(stack.quantity);
+            stack.quantity -= taken;
+            remaining -= taken;
+            if remaining == 0 {
+                break;
+            }
+        }
+        self.slots.retain(|stack| stack.quantity > 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_count() {
+        let mut inventory = Inventory::new(4);
+
+        inventory.add("iron_ore", 10).unwrap();
+        inventory.add("iron_ore", 5).unwrap();
+        assert_eq!(inventory.count("iron_ore"), 15);
+        assert_eq!(inventory.slots.len(), 1);
+        assert_eq!(inventory.count("coal"), 0);
+    }
+
+    #[test]
+    fn test_stacks_split_at_max_size() {
+        let mut inventory = Inventory::new(4);
+
+        inventory.add("arrow", MAX_STACK_SIZE + 1).unwrap();
+        assert_eq!(inventory.slots.len(), 2);
+        assert_eq!(inventory.free_slots(), 2);
+    }
+
+    #[test]
+    fn test_add_rejects_when_full() {
+        let mut inventory = Inventory::new(1);
+        inventory.add("iron_ore", 1).unwrap();
+
+        assert!(inventory.can_add("iron_ore", MAX_STACK_SIZE - 1));
+        assert_eq!(
+            inventory.add("coal", 1),
+            Err(InventoryError::NotEnoughSpace {
+                item_id: "coal".to_string(),
+                quantity: 1,
+            })
+        );
+        assert_eq!(inventory.count("coal"), 0);
+    }
+
+    #[test]
+    fn test_oversized_stack_has_no_room() {
+        // Stacks above the limit can come from old saves
+        let mut inventory = Inventory::new(1);
+        inventory.slots.push(ItemStack {
+            item_id: "coal".to_string(),
+            quantity: MAX_STACK_SIZE + 10,
+        });
+
+        assert!(!inventory.can_add("coal", 1));
+        assert!(inventory.add("coal", 1).is_err());
+        assert_eq!(inventory.count("coal"), MAX_STACK_SIZE + 10);
+    }
+
+    #[test]
+    fn test_remove_frees_slots() {
+        let mut inventory = Inventory::new(2);
+        inventory.add("coal", 3).unwrap();
+
+        inventory.remove("coal", 3).unwrap();
+        assert_eq!(inventory.count("coal"), 0);
+        assert_eq!(inventory.free_slots(), 2);
+    }
+
+    #[test]
+    fn test_remove_rejects_missing_items() {
+        let mut inventory = Inventory::new(2);
+        inventory.add("coal", 2).unwrap();
+
+        assert_eq!(
+            inventory.remove("coal", 5),
+            Err(InventoryError::NotEnoughItems {
+                item_id: "coal".to_string(),
+                required: 5,
+                available: 2,
+            })
+        );
+        assert_eq!(inventory.count("coal"), 2);
+    }
+}
@@ -1,13 +1,103 @@
 #![allow(deprecated)]
+use std::collections::VecDeque;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{console, window, CanvasRenderingContext2d, HtmlCanvasElement};
-
+use web_sys::{console, window, HtmlCanvasElement};
+
+mod canvas_renderer;
+mod change_tracking;
+mod chat;
+mod commands;
+mod crafting;
+mod errors;
+mod events;
 mod game_state;
 mod input;
-
-pub use game_state::{GameScreen, GameState, Region};
+mod interest;
+mod interpolation;
+mod inventory;
+mod migrations;
+mod network;
+mod party;
+mod prediction;
+mod profile;
+mod protocol;
+mod render;
+mod save;
+mod save_slots;
+mod server;
+mod servers;
+mod shop;
+mod sprites;
+mod state_view;
+mod storage;
+mod trade;
+#[cfg(feature = "wgpu")]
+mod wgpu_renderer;
+
+pub use canvas_renderer::CanvasRenderer;
+pub use change_tracking::{DirtySections, StateSection};
+pub use chat::{
+    validate_text, ChatChannel, ChatError, ChatFilter, ChatLine, ChatModerator, ChatRateLimiter,
+    FilterVerdict, NoFilter, WordListFilter, CHAT_RATE_LIMIT, CHAT_RATE_WINDOW_MS,
+    MAX_CHAT_HISTORY, MAX_CHAT_LENGTH,
+};
+pub use commands::{CommandContext, CommandError, CommandResult, UiCommand};
+pub use crafting::{CraftError, CraftingStation, MissingIngredient, Recipe, RecipeRegistry};
+pub use errors::{ErrorQueue, ErrorReport, ErrorSeverity, GameError, MAX_QUEUED_ERRORS};
+pub use events::{EventQueue, GameEvent, MAX_QUEUED_EVENTS};
+pub use game_state::{GameScreen, GameState, PausePolicy, Region, ScreenError};
 pub use input::{InputEvent, InputHandler, InputState};
+pub use interest::{Area, InterestConfig, SpatialGrid};
+pub use interpolation::{
+    InterpolationConfig, Interpolator, PositionSample, RemoteEntity, MAX_BUFFERED_SAMPLES,
+};
+pub use inventory::{Inventory, InventoryError, ItemStack};
+pub use network::{
+    backoff_ms, ConnectionManager, ConnectionStatus, MockTransport, NetworkError, Transport,
+    TransportEvent, WebSocketTransport, CONNECTION_TIMEOUT_MS, HEARTBEAT_INTERVAL_MS,
+    INITIAL_BACKOFF_MS, MAX_BACKOFF_MS, MAX_CONNECT_ATTEMPTS, MAX_INBOX_MESSAGES,
+};
+pub use party::{
+    Party, PartyAction, PartyError, PartyId, PartyManager, PartyMember, PartyView, MAX_PARTY_SIZE,
+    PARTY_INVITE_TIMEOUT_MS, PARTY_XP_BONUS_PERCENT,
+};
+pub use prediction::{
+    apply_input, input_distance, Predictor, MAX_INPUT_HISTORY, MAX_INPUT_STEP, MAX_MOVE_SPEED,
+};
+pub use profile::{
+    validate_name, NameError, PlayerClass, PlayerProfile, ProfileError, APPEARANCE_PRESETS,
+    MAX_NAME_LENGTH, MIN_NAME_LENGTH,
+};
+pub use protocol::{
+    decode, encode, Belongings, ClientMessage, DisconnectReason, Encoding, PlayerId, PlayerInput,
+    PlayerSnapshot, ProtocolError, ServerMessage, WorldSnapshot, MAX_FRAME_BYTES, PROTOCOL_VERSION,
+};
+pub use render::{
+    Color, DrawCommand, DrawList, HeadlessRenderer, RenderBackend, RenderError, Renderer,
+    WorldScene, BACKGROUND_COLOR, BALL_COLOR, PLAYER_COLOR, PLAYER_RADIUS, REMOTE_PLAYER_COLOR,
+};
+pub use save::{SaveError, SaveSnapshot, SAVE_VERSION};
+pub use save_slots::{SaveSlotManager, SlotError, SlotMetadata, AUTOSAVE_SLOT};
+pub use server::{Outgoing, Recipient, ServerConfig, ServerWorld, MAX_PENDING_INPUTS};
+pub use servers::{
+    DirectoryError, LatencyProbe, MockLatencyProbe, RecordedLatencies, RegionServers,
+    ServerDirectory, ServerInfo, ServerStatus, LATENCY_SAMPLES, MANIFEST_VERSION,
+};
+pub use shop::{PurchaseError, ShopCatalog, ShopItem};
+pub use sprites::{
+    Action, AnimationClip, AnimationState, AtlasError, ClipFrame, Facing, FrameRect,
+    SpriteAnimator, SpriteAtlas, SpriteKey, PLAYER_SKIN, REMOTE_PLAYER_SKIN,
+};
+pub use state_view::{GameStateView, StateDelta, StateDeltaTracker, STATE_VIEW_VERSION};
+pub use storage::{LocalStorage, MemoryStorage, SaveStorage, StorageError};
+pub use trade::{
+    settle, TradeAction, TradeError, TradeId, TradeManager, TradeOffer, TradeSession,
+    TradeSideView, TradeView, MAX_TRADE_ITEMS, TRADE_REQUEST_TIMEOUT_MS,
+};
+
+#[cfg(feature = "wgpu")]
+pub use wgpu_renderer::WgpuRenderer;
 
 // Re-export for backward compatibility
 pub use game_state::GameState as LegacyGameState;
@@ -20,10 +110,26 @@ pub const DEFAULT_BALL_SPEED_Y: f64 = 2.0;
 // Enhanced Game structure with RPG state management
 #[wasm_bindgen]
 pub struct Game {
-    canvas: HtmlCanvasElement,
-    ctx: CanvasRenderingContext2d,
+    renderer: Box<dyn Renderer>,
     state: GameState,
     input_handler: InputHandler,
+    recipes: RecipeRegistry,
+    shop: ShopCatalog,
+    save_slots: SaveSlotManager<LocalStorage>,
+    servers: ServerDirectory,
+    server_latencies: RecordedLatencies,
+    connection: ConnectionManager<WebSocketTransport>,
+    predictor: Predictor,
+    remote_players: Interpolator,
+    sprite_atlas: SpriteAtlas,
+    sprites: SpriteAnimator,
+    chat_limiter: ChatRateLimiter,
+    chat_log: VecDeque<ChatLine>,
+    state_deltas: StateDeltaTracker,
+    last_update_ms: Option<f64>,
+    /// Last rendering failure, so a broken frame is reported once rather
+    /// than every frame
+    render_error: Option<RenderError>,
     width: f64,
     height: f64,
 }
@@ -86,106 +192,108 @@ impl LegacyBallState {
 impl Game {
     #[wasm_bindgen(constructor)]
     pub fn new(canvas_id: &str) -> Result<Game, JsValue> {
-        console_error_panic_hook::set_once();
-
-        let window = window().ok_or("no global `window` exists")?;
-        let document = window
-            .document()
-            .ok_or("should have a document on window")?;
-        let canvas = document
-            .get_element_by_id(canvas_id)
-            .ok_or("should have a canvas element")?
-            .dyn_into::<HtmlCanvasElement>()?;
-
-        let ctx = canvas
-            .get_context("2d")?
-            .ok_or("should have a 2d context")?
-            .dyn_into::<CanvasRenderingContext2d>()?;
-
-        let width = canvas.width() as f64;
-        let height = canvas.height() as f64;
-
-        console::log_1(&format!("RPG Game initialized: {width}x{height}").into());
-
-        Ok(Game {
-            canvas,
-            ctx,
-            state: GameState::new(width, height),
-            input_handler: InputHandler::new(),
+        errors::install_panic_hook();
+
+        let canvas = find_canvas(canvas_id)?;
+        let sprite_atlas = SpriteAtlas::with_default_atlas();
+        let renderer = CanvasRenderer::new(canvas, &sprite_atlas.image)?;
+        let (width, height) = (renderer.width(), renderer.height());
+        Ok(Game::with_renderer(
+            Box::new(renderer),
+            sprite_atlas,
             width,
             height,
-        })
+        ))
     }
 
     #[wasm_bindgen]
     pub fn update(&mut self) {
-        // Process continuous input (movement)
+        // Track playtime for save slot metadata
+        let now = js_sys::Date::now();
+        let elapsed_secs = self
+            .last_update_ms
+            .map_or(0.0, |last| (now - last) / 1000.0);
+        self.last_update_ms = Some(now);
+
+        self.update_connection(now);
+
+        // Process continuous input (movement) and the legacy ball physics,
+        // as far as the open screens allow
         let (dx, dy) = self.input_handler.get_movement_delta();
-        if dx != 0.0 || dy != 0.0 {
-            self.state.move_player(dx, dy);
+        match self.connection.player_id() {
+            Some(player_id) => self.step_online(player_id, now, elapsed_secs, dx, dy),
+            None => self.state.step(elapsed_secs, dx, dy),
         }
-
-        // Update legacy ball physics for backward compatibility
-        self.state.update_ball_physics();
+        self.animate_sprites(now, elapsed_secs * 1000.0);
     }
 
     #[wasm_bindgen]
-    #[allow(deprecated)] // TODO: Update to use new fill_style API when stable
-    pub fn render(&self) {
-        // Clear canvas
-        self.ctx.clear_rect(0.0, 0.0, self.width, self.height);
+    pub fn render(&mut self) {
+        let remote = if self.connection.player_id().is_some() {
+            self.remote_players.entities(js_sys::Date::now())
+        } else {
+            Vec::new()
+        };
+        let scene = WorldScene {
+            state: &self.state,
+            remote_players: &remote,
+            sprites: &self.sprites,
+            atlas: &self.sprite_atlas,
+            width: self.width,
+            height: self.height,
+        };
+        let list = scene.draw_list(self.renderer.sprites_ready());
+
+        match self.renderer.render(&list) {
+            Ok(()) => self.render_error = None,
+            Err(e) if self.render_error.as_ref() == Some(&e) => {}
+            Err(e) => {
+                self.render_error = Some(e.clone());
+                self.state.report_error(GameError::Render(e));
+            }
+        }
+    }
 
-        // Only render game world elements (no UI)
-        // Always render the game world since we start in GameHUD
-        self.render_game_world();
+    /// Pause or resume the game, e.g. when the browser tab is hidden
+    #[wasm_bindgen]
+    pub fn set_paused(&mut self, paused: bool) {
+        // Key-up events are lost while the tab is hidden
+        self.input_handler.release_all();
+        // Don't count time spent hidden as playtime
+        self.last_update_ms = None;
+        self.state.set_paused(paused);
     }
 
     /// Handle input events from the frontend
     #[wasm_bindgen]
     pub fn handle_input(&mut self, event_type: &str, data: &str) -> bool {
-        match event_type {
-            "keydown" => {
-                if let Some(input_event) = self.input_handler.handle_key_down(data) {
-                    self.process_input_event(input_event)
-                } else {
-                    false
-                }
-            }
-            "keyup" => {
-                self.input_handler.handle_key_up(data);
-                false
-            }
-            "mouseclick" => {
-                if let Ok(coords) = serde_json::from_str::<(f64, f64)>(data) {
-                    let input_event = self.input_handler.handle_mouse_click(coords.0, coords.1);
-                    self.process_input_event(input_event)
-                } else {
-                    console::log_1(&format!("Failed to parse mouse coordinates: {data}").into());
-                    false
-                }
-            }
-            "touch" | "touchstart" => {
-                // Handle both touch and touchstart events the same way
-                if let Ok(coords) = serde_json::from_str::<(f64, f64)>(data) {
-                    let input_event = self.input_handler.handle_touch(coords.0, coords.1);
-                    self.process_input_event(input_event)
-                } else {
-                    console::log_1(&format!("Failed to parse touch coordinates: {data}").into());
-                    false
-                }
-            }
-            "touchend" => {
-                // TouchEnd doesn't need coordinate processing, just acknowledge it
-                console::log_1(&"Touch ended".into());
-                false
-            }
-            _ => {
-                console::log_1(&format!("Unknown input event type: {event_type}").into());
-                false
-            }
+        self.route_input(event_type, data)
+    }
+
+    /// Run a JSON-encoded `UiCommand`, returning a JSON `CommandResult`
+    #[wasm_bindgen]
+    pub fn dispatch(&mut self, command_json: &str) -> String {
+        match UiCommand::from_json(command_json) {
+            Ok(command) => self.dispatch_command(command),
+            Err(e) => serde_json::to_string(&CommandResult::from(Err(e))).unwrap_or_default(),
         }
     }
 
+    /// Drain queued game events as a JSON array. Call once per frame, after
+    /// `update`, so handlers are free to call back into the game.
+    #[wasm_bindgen]
+    pub fn drain_events(&mut self) -> String {
+        serde_json::to_string(&self.state.events.drain()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Drain queued errors, including captured panics, as a JSON array of `ErrorReport`s
+    #[wasm_bindgen]
+    pub fn drain_errors(&mut self) -> String {
+        let mut reports = errors::take_panic_reports();
+        reports.extend(self.state.errors.drain());
+        serde_json::to_string(&reports).unwrap_or_else(|_| "[]".to_string())
+    }
+
     /// Get current game screen for the frontend
     #[wasm_bindgen]
     pub fn get_current_screen(&self) -> String {
@@ -195,24 +303,27 @@ impl Game {
     /// Get current game state as JSON for the frontend
     #[wasm_bindgen]
     pub fn get_game_state(&self) -> String {
-        serde_json::to_string(&serde_json::json!({
-            "screen": format!("{:?}", self.state.current_screen),
-            "region": self.state.selected_region.as_ref().map(|r| format!("{r:?}")),
-            "player_name": self.state.player_name,
-            "is_loading": self.state.is_loading,
-            "error": self.state.error_message,
-            "player_position": [self.state.player_x, self.state.player_y],
-            "ball_position": [self.state.ball_x, self.state.ball_y]
-        }))
-        .unwrap_or_default()
+        serde_json::to_string(&GameStateView::from_state(&self.state)).unwrap_or_default()
+    }
+
+    /// Get the fields changed since the previous call as a JSON `StateDelta`
+    #[wasm_bindgen]
+    pub fn take_state_delta(&mut self) -> String {
+        let delta = self.state_deltas.take_delta(&mut self.state);
+        serde_json::to_string(&delta).unwrap_or_default()
+    }
+
+    /// Make the next `take_state_delta` return the full state
+    #[wasm_bindgen]
+    pub fn request_state_resync(&mut self) {
+        self.state_deltas.request_full_resync();
     }
 
     #[wasm_bindgen]
     pub fn resize(&mut self, width: u32, height: u32) {
         self.width = width as f64;
         self.height = height as f64;
-        self.canvas.set_width(width);
-        self.canvas.set_height(height);
+        self.renderer.resize(self.width, self.height);
 
         // Update game state dimensions
         self.state.world_width = self.width;
@@ -224,6 +335,67 @@ impl Game {
         self.state.reset();
     }
 
+    /// Serialize the full game state to a versioned JSON snapshot
+    #[wasm_bindgen]
+    pub fn save_state(&self) -> String {
+        SaveSnapshot::new(&self.state).to_json().unwrap_or_default()
+    }
+
+    /// Restore game state from a snapshot produced by `save_state`
+    #[wasm_bindgen]
+    pub fn load_state(&mut self, json: &str) -> Result<(), JsValue> {
+        let snapshot =
+            SaveSnapshot::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.apply_loaded_state(snapshot.state);
+        Ok(())
+    }
+
+    /// Save the game into a named slot in localStorage
+    #[wasm_bindgen]
+    pub fn save_to_slot(&mut self, slot: &str) -> Result<(), JsValue> {
+        self.save_slots
+            .save(slot, &self.state, js_sys::Date::now())
+            .map(|_| ())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Load the game from a named slot in localStorage
+    #[wasm_bindgen]
+    pub fn load_from_slot(&mut self, slot: &str) -> Result<(), JsValue> {
+        let state = self
+            .save_slots
+            .load(slot)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.apply_loaded_state(state);
+        Ok(())
+    }
+
+    /// Delete a named save slot
+    #[wasm_bindgen]
+    pub fn delete_save_slot(&mut self, slot: &str) -> Result<(), JsValue> {
+        self.save_slots
+            .delete(slot)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Get metadata for all save slots as JSON, most recent first
+    #[wasm_bindgen]
+    pub fn list_save_slots(&self) -> String {
+        self.save_slots
+            .list_slots()
+            .ok()
+            .and_then(|slots| serde_json::to_string(&slots).ok())
+            .unwrap_or_else(|| "[]".to_string())
+    }
+
+    /// Move the player into a zone, autosaving when the zone changes
+    #[wasm_bindgen]
+    pub fn enter_zone(&mut self, zone: &str) {
+        self.run_legacy_command(UiCommand::EnterZone {
+            zone: zone.to_string(),
+        });
+    }
+
     // Legacy compatibility methods
     #[wasm_bindgen]
     pub fn get_ball_position(&self) -> Vec<f64> {
@@ -238,32 +410,32 @@ impl Game {
     /// Transition to a specific screen (called from SolidJS)
     #[wasm_bindgen]
     pub fn transition_to_screen(&mut self, screen: &str) {
-        let game_screen = match screen {
-            "GameHUD" => GameScreen::GameHUD,
-            "Inventory" => GameScreen::Inventory,
-            "Shop" => GameScreen::Shop,
-            "HelpModal" => GameScreen::HelpModal,
-            _ => return, // Invalid screen, ignore
-        };
-        self.state.transition_to(game_screen);
+        match GameScreen::from_name(screen) {
+            Some(screen) => self.run_legacy_command(UiCommand::TransitionTo { screen }),
+            None => self.state.report_error(
+                CommandError::InvalidCommand(format!("Unknown screen: {screen}")).into(),
+            ),
+        }
     }
 
-    /// Set player name (called from SolidJS)
+    /// Set player name (called from SolidJS), returning a JSON `CommandResult`
+    /// so the login form can show why a name was rejected
     #[wasm_bindgen]
-    pub fn set_player_name(&mut self, name: &str) {
-        self.state.set_player_name(name.to_string());
+    pub fn set_player_name(&mut self, name: &str) -> String {
+        self.dispatch_command(UiCommand::SetPlayerName {
+            name: name.to_string(),
+        })
     }
 
     /// Set selected region (called from SolidJS)
     #[wasm_bindgen]
     pub fn set_region(&mut self, region: &str) {
-        let game_region = match region {
-            "EU" => Region::EU,
-            "Asia" => Region::Asia,
-            "Vietnam" => Region::Vietnam,
-            _ => return, // Invalid region, ignore
-        };
-        self.state.set_region(game_region);
+        match Region::from_name(region) {
+            Some(region) => self.run_legacy_command(UiCommand::SetRegion { region }),
+            None => self.state.report_error(
+                CommandError::InvalidCommand(format!("Unknown region: {region}")).into(),
+            ),
+        }
     }
 
     /// Get player position for UI display
@@ -277,86 +449,719 @@ impl Game {
     pub fn is_player_moving(&self) -> bool {
         self.input_handler.is_moving()
     }
+
+    /// Craft a recipe at the given station ("" when not at a station)
+    #[wasm_bindgen]
+    pub fn craft_item(&mut self, recipe_id: &str, station: &str) -> Result<(), JsValue> {
+        let station = match station {
+            "" => None,
+            name => Some(
+                CraftingStation::from_name(name)
+                    .ok_or_else(|| JsValue::from_str(&format!("Unknown station: {name}")))?,
+            ),
+        };
+        self.run_command(UiCommand::Craft {
+            recipe_id: recipe_id.to_string(),
+            station,
+        })
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Get recipes available at a station as JSON ("" when not at a station)
+    #[wasm_bindgen]
+    pub fn get_recipes(&self, station: &str) -> String {
+        let recipes = self
+            .recipes
+            .recipes_for_station(CraftingStation::from_name(station));
+        serde_json::to_string(&recipes).unwrap_or_default()
+    }
+
+    /// Get the shop's stock as JSON
+    #[wasm_bindgen]
+    pub fn get_shop_items(&self) -> String {
+        serde_json::to_string(&self.shop.items()).unwrap_or_default()
+    }
+
+    /// Replace the bundled server list with a manifest fetched at runtime
+    #[wasm_bindgen]
+    pub fn load_server_manifest(&mut self, json: &str) -> Result<(), JsValue> {
+        self.servers =
+            ServerDirectory::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(())
+    }
+
+    /// Replace the bundled sprite atlas, loading the sheet image it names
+    #[wasm_bindgen]
+    pub fn load_sprite_atlas(&mut self, json: &str) -> Result<(), JsValue> {
+        self.sprite_atlas =
+            SpriteAtlas::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer
+            .load_sprite_sheet(&self.sprite_atlas.image)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Get every server with its measured latency as a JSON `ServerStatus` array
+    #[wasm_bindgen]
+    pub fn get_servers(&mut self) -> String {
+        serde_json::to_string(&self.servers.statuses(&mut self.server_latencies))
+            .unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Report a round-trip time the browser measured to a server
+    #[wasm_bindgen]
+    pub fn record_server_latency(&mut self, server_id: &str, latency_ms: f64) {
+        self.server_latencies.record(server_id, latency_ms);
+    }
+
+    /// Select the region of the lowest-latency server, returning its name,
+    /// or `undefined` if no server has been reached yet
+    #[wasm_bindgen]
+    pub fn auto_select_region(&mut self) -> Option<String> {
+        let best = self.servers.best_server(&mut self.server_latencies)?;
+        let name = format!("{:?}", best.region);
+        self.run_legacy_command(UiCommand::SetRegion {
+            region: best.region,
+        });
+        Some(name)
+    }
+
+    /// Connect to a server in the selected region, or to `endpoint` if
+    /// given (e.g. a local server). Progress shows through `is_loading`,
+    /// failures through the error queue.
+    #[wasm_bindgen]
+    pub fn connect(&mut self, endpoint: Option<String>) {
+        if let Err(e) = self.start_connection(endpoint) {
+            self.state.report_error(GameError::Network(e));
+        }
+        self.update_connection(js_sys::Date::now());
+    }
+
+    /// Leave the server and stop reconnecting
+    #[wasm_bindgen]
+    pub fn disconnect(&mut self) {
+        self.connection.disconnect();
+        self.update_connection(js_sys::Date::now());
+    }
+
+    #[wasm_bindgen]
+    pub fn is_connected(&self) -> bool {
+        self.connection.player_id().is_some()
+    }
+
+    /// Call when the chat box gains or loses focus; while it's active,
+    /// keystrokes are left to the text box instead of moving the player
+    #[wasm_bindgen]
+    pub fn set_chat_active(&mut self, active: bool) {
+        self.input_handler.set_chat_active(active);
+    }
+
+    #[wasm_bindgen]
+    pub fn is_chat_active(&self) -> bool {
+        self.input_handler.is_chat_active()
+    }
+
+    /// Send typed chat, picking the channel from a slash prefix such as
+    /// `/g` or `/w Name`. Returns whether it was sent; problems go to the
+    /// error queue.
+    #[wasm_bindgen]
+    pub fn send_chat(&mut self, input: &str) -> bool {
+        let sent = self.try_send_chat(input);
+        if let Err(e) = &sent {
+            self.state.report_error(e.clone());
+        }
+        sent.is_ok()
+    }
+
+    /// Invite a player to the party by name
+    #[wasm_bindgen]
+    pub fn party_invite(&mut self, name: &str) -> bool {
+        self.send_party(PartyAction::Invite {
+            name: name.to_string(),
+        })
+    }
+
+    /// Answer the pending party invite
+    #[wasm_bindgen]
+    pub fn party_accept(&mut self) -> bool {
+        self.send_party(PartyAction::Accept)
+    }
+
+    #[wasm_bindgen]
+    pub fn party_decline(&mut self) -> bool {
+        self.send_party(PartyAction::Decline)
+    }
+
+    #[wasm_bindgen]
+    pub fn party_leave(&mut self) -> bool {
+        self.send_party(PartyAction::Leave)
+    }
+
+    /// Remove a member; only the leader can
+    #[wasm_bindgen]
+    pub fn party_kick(&mut self, player_id: u32) -> bool {
+        self.send_party(PartyAction::Kick { id: player_id })
+    }
+
+    /// Make another member the leader; only the leader can
+    #[wasm_bindgen]
+    pub fn party_promote(&mut self, player_id: u32) -> bool {
+        self.send_party(PartyAction::Promote { id: player_id })
+    }
+
+    /// Ask a player to trade by name
+    #[wasm_bindgen]
+    pub fn trade_request(&mut self, name: &str) -> bool {
+        self.send_trade(TradeAction::Request {
+            name: name.to_string(),
+        })
+    }
+
+    /// Answer the pending trade request
+    #[wasm_bindgen]
+    pub fn trade_accept(&mut self) -> bool {
+        self.send_trade(TradeAction::Accept)
+    }
+
+    #[wasm_bindgen]
+    pub fn trade_decline(&mut self) -> bool {
+        self.send_trade(TradeAction::Decline)
+    }
+
+    /// Replace this side's offer with a JSON `TradeOffer`
+    #[wasm_bindgen]
+    pub fn trade_offer(&mut self, offer_json: &str) -> bool {
+        match serde_json::from_str::<TradeOffer>(offer_json) {
+            Ok(offer) => self.send_trade(TradeAction::Offer(offer)),
+            Err(_) => {
+                self.report_parse_error("trade_offer", offer_json);
+                false
+            }
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn trade_lock(&mut self) -> bool {
+        self.send_trade(TradeAction::Lock)
+    }
+
+    #[wasm_bindgen]
+    pub fn trade_unlock(&mut self) -> bool {
+        self.send_trade(TradeAction::Unlock)
+    }
+
+    /// Agree to the locked offers; the swap happens once both sides confirm
+    #[wasm_bindgen]
+    pub fn trade_confirm(&mut self) -> bool {
+        self.send_trade(TradeAction::Confirm)
+    }
+
+    #[wasm_bindgen]
+    pub fn trade_cancel(&mut self) -> bool {
+        self.send_trade(TradeAction::Cancel)
+    }
+
+    /// Drain received chat as a JSON array of `ChatLine`s
+    #[wasm_bindgen]
+    pub fn drain_chat(&mut self) -> String {
+        let lines: Vec<ChatLine> = self.chat_log.drain(..).collect();
+        serde_json::to_string(&lines).unwrap_or_else(|_| "[]".to_string())
+    }
 }
 
 impl Game {
-    /// Process input events and update game state accordingly
-    fn process_input_event(&mut self, event: InputEvent) -> bool {
-        match (&self.state.current_screen, event) {
-            // Game HUD - movement and UI toggles
-            (GameScreen::GameHUD, InputEvent::ToggleInventory) => {
-                self.state.transition_to(GameScreen::Inventory);
-                true
+    /// Assemble a game drawing with `renderer`
+    fn with_renderer(
+        renderer: Box<dyn Renderer>,
+        sprite_atlas: SpriteAtlas,
+        width: f64,
+        height: f64,
+    ) -> Game {
+        console::log_1(&format!("RPG Game initialized: {width}x{height}").into());
+
+        Game {
+            renderer,
+            state: GameState::new(width, height),
+            input_handler: InputHandler::new(),
+            recipes: RecipeRegistry::with_default_recipes(),
+            shop: ShopCatalog::with_default_items(),
+            save_slots: SaveSlotManager::new(LocalStorage::new()),
+            servers: ServerDirectory::with_default_servers(),
+            server_latencies: RecordedLatencies::new(),
+            connection: ConnectionManager::new(WebSocketTransport::new()),
+            predictor: Predictor::new(),
+            remote_players: Interpolator::default(),
+            sprite_atlas,
+            sprites: SpriteAnimator::new(),
+            chat_limiter: ChatRateLimiter::default(),
+            chat_log: VecDeque::new(),
+            state_deltas: StateDeltaTracker::new(),
+            last_update_ms: None,
+            render_error: None,
+            width,
+            height,
+        }
+    }
+
+    /// A game drawing into `canvas_id` with WebGPU
+    #[cfg(all(feature = "wgpu", target_arch = "wasm32"))]
+    async fn with_gpu(canvas_id: &str) -> Result<Game, JsValue> {
+        errors::install_panic_hook();
+
+        let canvas = find_canvas(canvas_id)?;
+        let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+        let sprite_atlas = SpriteAtlas::with_default_atlas();
+        let renderer = WgpuRenderer::for_canvas(canvas, &sprite_atlas.image)
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(Game::with_renderer(
+            Box::new(renderer),
+            sprite_atlas,
+            width,
+            height,
+        ))
+    }
+
+    #[cfg(not(all(feature = "wgpu", target_arch = "wasm32")))]
+    async fn with_gpu(_canvas_id: &str) -> Result<Game, JsValue> {
+        Err(JsValue::from_str(
+            "WebGPU rendering needs a browser build with the `wgpu` feature",
+        ))
+    }
+
+    fn start_connection(&mut self, endpoint: Option<String>) -> Result<(), NetworkError> {
+        let (Some(profile), Some(region)) =
+            (self.state.profile(), self.state.selected_region.clone())
+        else {
+            return Err(NetworkError::NotLoggedIn);
+        };
+        let endpoint = match endpoint {
+            Some(endpoint) => endpoint,
+            None => {
+                self.servers
+                    .server_for_region(&region, &mut self.server_latencies)
+                    .ok_or(NetworkError::NoServer(region.clone()))?
+                    .endpoint
+            }
+        };
+        self.connection
+            .connect(&endpoint, profile, region, js_sys::Date::now());
+        self.predictor.reset();
+        self.remote_players.clear();
+        Ok(())
+    }
+
+    /// Check chat locally before it goes out, so obvious mistakes don't
+    /// cost a round trip
+    fn try_send_chat(&mut self, input: &str) -> Result<(), GameError> {
+        if self.connection.player_id().is_none() {
+            return Err(ChatError::NotConnected.into());
+        }
+        let (channel, text) = ChatChannel::parse(input);
+        let text = validate_text(text)?;
+        self.chat_limiter.try_send(js_sys::Date::now())?;
+        self.connection
+            .send(&ClientMessage::Chat { channel, text })?;
+        Ok(())
+    }
+
+    /// Send a party action. The outcome arrives as a party update or a
+    /// rejection in the error queue.
+    fn send_party(&mut self, action: PartyAction) -> bool {
+        let answers_invite = matches!(action, PartyAction::Accept | PartyAction::Decline);
+        let sent = if self.connection.player_id().is_none() {
+            Err(GameError::Party(PartyError::NotConnected))
+        } else {
+            self.connection
+                .send(&ClientMessage::Party(action))
+                .map_err(GameError::from)
+        };
+        match &sent {
+            Ok(()) if answers_invite => self.state.set_party_invite(None),
+            Ok(()) => {}
+            Err(e) => self.state.report_error(e.clone()),
+        }
+        sent.is_ok()
+    }
+
+    /// Send a trade action. The outcome arrives as a trade update or a
+    /// rejection in the error queue.
+    fn send_trade(&mut self, action: TradeAction) -> bool {
+        let answers_request = matches!(action, TradeAction::Accept | TradeAction::Decline);
+        let sent = if self.connection.player_id().is_none() {
+            Err(GameError::Trade(TradeError::NotConnected))
+        } else {
+            self.connection
+                .send(&ClientMessage::Trade(action))
+                .map_err(GameError::from)
+        };
+        match &sent {
+            Ok(()) if answers_request => self.state.set_trade_request(None),
+            Ok(()) => {}
+            Err(e) => self.state.report_error(e.clone()),
+        }
+        sent.is_ok()
+    }
+
+    /// Show the trade window while a trade is open, and treat closing it
+    /// as walking away from the trade
+    fn sync_trade_screen(&mut self, trade: Option<TradeView>) {
+        let was_trading = self.state.trade.is_some();
+        self.state.set_trade(trade);
+        let open = self.state.screens().contains(&GameScreen::Trade);
+        match (self.state.trade.is_some(), open) {
+            (true, false) if !was_trading => {
+                if let Err(e) = self.state.transition_to(GameScreen::Trade) {
+                    self.state.report_error(GameError::Message(e.to_string()));
+                    self.trade_cancel();
+                    self.state.set_trade(None);
+                }
+            }
+            (true, false) => {
+                self.trade_cancel();
+                self.state.set_trade(None);
             }
-            (GameScreen::GameHUD, InputEvent::ToggleShop) => {
-                self.state.transition_to(GameScreen::Shop);
-                true
+            (false, true) => {
+                while self.state.screens().contains(&GameScreen::Trade) {
+                    self.state.pop_screen();
+                }
             }
-            (GameScreen::GameHUD, InputEvent::ToggleHelp) => {
-                self.state.transition_to(GameScreen::HelpModal);
-                true
+            _ => {}
+        }
+    }
+
+    /// Advance one frame while connected: reconcile with the latest
+    /// snapshots, then predict this frame's movement and send it
+    fn step_online(
+        &mut self,
+        player_id: PlayerId,
+        now_ms: f64,
+        elapsed_secs: f64,
+        dx: f64,
+        dy: f64,
+    ) {
+        if let Some(tick_rate) = self.connection.tick_rate() {
+            self.remote_players.set_tick_rate(tick_rate);
+        }
+        for message in self.connection.drain_messages() {
+            match message {
+                ServerMessage::Snapshot(snapshot) => {
+                    self.predictor
+                        .reconcile(&mut self.state, player_id, &snapshot);
+                    self.remote_players
+                        .push_snapshot(&snapshot, Some(player_id), now_ms);
+                }
+                ServerMessage::EntityEntered(player) => {
+                    self.remote_players.push_player(&player);
+                }
+                ServerMessage::EntityLeft { id } => self.remote_players.remove(id),
+                ServerMessage::Chat {
+                    name,
+                    channel,
+                    text,
+                    ..
+                } => {
+                    if self.chat_log.len() >= MAX_CHAT_HISTORY {
+                        self.chat_log.pop_front();
+                    }
+                    self.chat_log.push_back(ChatLine {
+                        channel,
+                        from: name,
+                        text,
+                    });
+                }
+                ServerMessage::ChatRejected { message, .. } => {
+                    self.state
+                        .report_error(GameError::Chat(ChatError::Rejected(message)));
+                }
+                ServerMessage::PartyInvite { name, .. } => self.state.set_party_invite(Some(name)),
+                ServerMessage::PartyUpdate(party) => self.state.set_party(party),
+                ServerMessage::PartyRejected { message, .. } => {
+                    self.state
+                        .report_error(GameError::Party(PartyError::Rejected(message)));
+                }
+                ServerMessage::XpGained { amount } => {
+                    self.state.gain_xp(amount);
+                }
+                ServerMessage::TradeRequest { name, .. } => {
+                    self.state.set_trade_request(Some(name));
+                }
+                ServerMessage::TradeUpdate(trade) => self.sync_trade_screen(trade),
+                ServerMessage::TradeRejected { message, .. } => {
+                    self.state
+                        .report_error(GameError::Trade(TradeError::Rejected(message)));
+                }
+                ServerMessage::TradeCompleted { gave, received } => {
+                    if let Err(e) = self.state.apply_trade(&gave, &received) {
+                        self.state.report_error(GameError::Trade(e));
+                    }
+                }
+                ServerMessage::Belongings(belongings) => {
+                    self.state
+                        .set_belongings(belongings.gold, belongings.inventory);
+                }
+                ServerMessage::CommandRejected { message, .. } => {
+                    self.state
+                        .report_error(GameError::Command(CommandError::Rejected(message)));
+                }
+                _ => {}
             }
+        }
 
-            // Modal screens - go back to game HUD
-            (
-                GameScreen::Inventory | GameScreen::Shop | GameScreen::HelpModal,
-                InputEvent::Escape,
-            ) => {
-                self.state.transition_to(GameScreen::GameHUD);
-                true
+        let (dx, dy) = if self.state.pause_policy() == PausePolicy::Overlay {
+            (dx, dy)
+        } else {
+            (0.0, 0.0)
+        };
+        for input in self
+            .predictor
+            .predict_frame(&mut self.state, elapsed_secs, dx, dy)
+        {
+            if let Err(e) = self.connection.send(&ClientMessage::Input(input)) {
+                self.state.report_error(GameError::Network(e));
+                break;
             }
-            (
-                GameScreen::Inventory | GameScreen::Shop | GameScreen::HelpModal,
-                InputEvent::MenuBack,
-            ) => {
-                self.state.transition_to(GameScreen::GameHUD);
-                true
+        }
+        // The player closed the trade window since the last frame
+        if self.state.trade.is_some() && !self.state.screens().contains(&GameScreen::Trade) {
+            self.sync_trade_screen(self.state.trade.clone());
+        }
+        self.state.step(elapsed_secs, 0.0, 0.0);
+    }
+
+    /// Drive the connection and mirror its state into the game state
+    fn update_connection(&mut self, now_ms: f64) {
+        for error in self.connection.update(now_ms) {
+            self.state.report_error(GameError::Network(error));
+        }
+        let pending = self.connection.status().is_pending();
+        if self.state.is_loading != pending {
+            self.state.set_loading(pending);
+        }
+        // Parties only exist on the server
+        let offline = self.connection.player_id().is_none();
+        if offline && (self.state.party.is_some() || self.state.party_invite.is_some()) {
+            self.state.set_party(None);
+            self.state.set_party_invite(None);
+        }
+        if offline && (self.state.trade.is_some() || self.state.trade_request.is_some()) {
+            self.sync_trade_screen(None);
+            self.state.set_trade_request(None);
+        }
+        // The gold and items shown while connected were the server's
+        if offline {
+            self.state.restore_offline_belongings();
+        }
+    }
+    /// Apply a command, autosaving if it moved the player to another zone
+    fn run_command(&mut self, command: UiCommand) -> Result<(), CommandError> {
+        // Gold and items belong to the server while connected; the result
+        // comes back as new belongings or a rejection
+        if self.connection.player_id().is_some() {
+            let message = match &command {
+                UiCommand::BuyItem { item_id, quantity } => Some(ClientMessage::Buy {
+                    item_id: item_id.clone(),
+                    quantity: *quantity,
+                }),
+                UiCommand::Craft { recipe_id, station } => Some(ClientMessage::Craft {
+                    recipe_id: recipe_id.clone(),
+                    station: *station,
+                }),
+                _ => None,
+            };
+            if let Some(message) = message {
+                return self
+                    .connection
+                    .send(&message)
+                    .map_err(CommandError::Network);
             }
+        }
+
+        let zone_before = self.state.current_zone.clone();
+        let ctx = CommandContext {
+            recipes: &self.recipes,
+            shop: &self.shop,
+        };
+        command.execute(&mut self.state, &ctx)?;
 
-            _ => false, // Unhandled event
-        }
-    }
-
-    /// Render only the game world elements (ball, player, etc.) - no UI
-    fn render_game_world(&self) {
-        // Set background
-        self.ctx.set_fill_style(&JsValue::from_str("#1e1e1e"));
-        self.ctx.fill_rect(0.0, 0.0, self.width, self.height);
-
-        // Draw bouncing ball (legacy compatibility)
-        self.ctx.begin_path();
-        self.ctx.set_fill_style(&JsValue::from_str("#4fc3f7"));
-        self.ctx
-            .arc(
-                self.state.ball_x,
-                self.state.ball_y,
-                BALL_RADIUS,
-                0.0,
-                2.0 * std::f64::consts::PI,
-            )
-            .unwrap();
-        self.ctx.fill();
-
-        // Draw player character
-        self.ctx.begin_path();
-        self.ctx.set_fill_style(&JsValue::from_str("#ff6b6b"));
-        self.ctx
-            .arc(
-                self.state.player_x,
-                self.state.player_y,
-                15.0,
-                0.0,
-                2.0 * std::f64::consts::PI,
-            )
-            .unwrap();
-        self.ctx.fill();
+        if self.state.current_zone != zone_before {
+            if let Err(e) = self.save_slots.autosave(&self.state, js_sys::Date::now()) {
+                self.state.report_error(GameError::Autosave(e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a command and describe the outcome as a JSON `CommandResult`
+    fn dispatch_command(&mut self, command: UiCommand) -> String {
+        let result: CommandResult = self.run_command(command).into();
+        serde_json::to_string(&result).unwrap_or_default()
+    }
+
+    /// Run a command for one of the older fire-and-forget entry points
+    fn run_legacy_command(&mut self, command: UiCommand) {
+        if let Err(e) = self.run_command(command) {
+            self.state.report_error(e.into());
+        }
+    }
+
+    /// Route a raw input event from the frontend
+    fn route_input(&mut self, event_type: &str, data: &str) -> bool {
+        match event_type {
+            "keydown" => {
+                if let Some(input_event) = self.input_handler.handle_key_down(data) {
+                    self.process_input_event(input_event)
+                } else {
+                    false
+                }
+            }
+            "keyup" => {
+                self.input_handler.handle_key_up(data);
+                false
+            }
+            "mouseclick" => {
+                if let Ok(coords) = serde_json::from_str::<(f64, f64)>(data) {
+                    let input_event = self.input_handler.handle_mouse_click(coords.0, coords.1);
+                    self.process_input_event(input_event)
+                } else {
+                    self.report_parse_error(event_type, data);
+                    false
+                }
+            }
+            "touch" | "touchstart" => {
+                // Handle both touch and touchstart events the same way
+                if let Ok(coords) = serde_json::from_str::<(f64, f64)>(data) {
+                    let input_event = self.input_handler.handle_touch(coords.0, coords.1);
+                    self.process_input_event(input_event)
+                } else {
+                    self.report_parse_error(event_type, data);
+                    false
+                }
+            }
+            "touchend" => {
+                // TouchEnd doesn't need coordinate processing, just acknowledge it
+                console::log_1(&"Touch ended".into());
+                false
+            }
+            _ => {
+                self.state.report_error(GameError::UnknownInput {
+                    event_type: event_type.to_string(),
+                });
+                false
+            }
+        }
+    }
+
+    fn report_parse_error(&mut self, event_type: &str, data: &str) {
+        self.state.report_error(GameError::InputParse {
+            event_type: event_type.to_string(),
+            data: data.to_string(),
+        });
+    }
+
+    /// Replace the current state with a loaded one
+    fn apply_loaded_state(&mut self, state: GameState) {
+        let previous = std::mem::replace(&mut self.state, state);
+        self.state.errors = previous.errors;
+        self.state_deltas.request_full_resync();
+        // While connected the server's gold and items stay on show, and the
+        // loaded ones wait until going offline
+        if previous.offline_belongings.is_some() {
+            self.state.set_belongings(previous.gold, previous.inventory);
+        }
+
+        // The canvas may have a different size than when the game was saved
+        self.state.world_width = self.width;
+        self.state.world_height = self.height;
+        self.state.move_player(0.0, 0.0);
+        // Trades don't survive a save
+        if self.state.current_screen == GameScreen::Trade {
+            self.state.pop_screen();
+        }
+    }
+
+    /// Process input events and update game state accordingly
+    fn process_input_event(&mut self, event: InputEvent) -> bool {
+        if self.state.current_screen.is_front_end() {
+            return self.process_front_end_input(event);
+        }
+
+        let toggled = match event {
+            // Swing only while nothing covers the world
+            InputEvent::Attack if self.state.pause_policy() == PausePolicy::Overlay => {
+                self.sprites.play(SpriteKey::LocalPlayer, Action::Attack);
+                return true;
+            }
+            InputEvent::ToggleInventory => GameScreen::Inventory,
+            InputEvent::ToggleShop => GameScreen::Shop,
+            InputEvent::ToggleHelp => GameScreen::HelpModal,
+            // Back closes one modal level at a time
+            InputEvent::Escape | InputEvent::MenuBack => return self.state.pop_screen(),
+            _ => return false, // Unhandled event
+        };
+
+        // Toggling the open screen closes it, otherwise open it on top if allowed
+        if self.state.current_screen == toggled {
+            self.state.pop_screen()
+        } else {
+            self.state.push_screen(toggled).is_ok()
+        }
+    }
+
+    /// Walk the login, server selection and main menu flow
+    fn process_front_end_input(&mut self, event: InputEvent) -> bool {
+        match (&self.state.current_screen, event) {
+            (_, InputEvent::ToggleHelp) => self.state.push_screen(GameScreen::HelpModal).is_ok(),
+            (GameScreen::MainMenu, InputEvent::Enter | InputEvent::MenuSelect) => self
+                .run_command(UiCommand::TransitionTo {
+                    screen: GameScreen::GameHUD,
+                })
+                .is_ok(),
+            (screen, InputEvent::Escape | InputEvent::MenuBack) => {
+                match screen.previous_in_flow() {
+                    Some(previous) => self.state.transition_to(previous).is_ok(),
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Advance every sprite's animation from how far it moved this frame
+    fn animate_sprites(&mut self, now_ms: f64, dt_ms: f64) {
+        let atlas = &self.sprite_atlas;
+        self.sprites.observe(
+            SpriteKey::LocalPlayer,
+            PLAYER_SKIN,
+            (self.state.player_x, self.state.player_y),
+            dt_ms,
+            atlas,
+        );
+
+        let remote = if self.connection.player_id().is_some() {
+            self.remote_players.entities(now_ms)
+        } else {
+            Vec::new()
+        };
+        for player in &remote {
+            self.sprites.observe(
+                SpriteKey::Remote(player.id),
+                REMOTE_PLAYER_SKIN,
+                (player.x, player.y),
+                dt_ms,
+                atlas,
+            );
+        }
+        self.sprites.retain(|key| match key {
+            SpriteKey::LocalPlayer => true,
+            SpriteKey::Remote(id) => remote.iter().any(|player| player.id == id),
+        });
     }
 }
 
 // Initialize the game
 #[wasm_bindgen(start)]
 pub fn main() {
-    console_error_panic_hook::set_once();
+    errors::install_panic_hook();
     console::log_1(&"WASM Game loaded!".into());
 }
 
@@ -372,6 +1177,34 @@ pub fn start_game(canvas_id: &str) -> Result<Game, JsValue> {
     Game::new(canvas_id)
 }
 
+/// Start the game drawing with `backend`, a `RenderBackend` name
+#[wasm_bindgen]
+pub async fn start_game_with(canvas_id: String, backend: String) -> Result<Game, JsValue> {
+    match RenderBackend::from_name(&backend) {
+        Some(RenderBackend::Canvas2d) => Game::new(&canvas_id),
+        Some(RenderBackend::WebGpu) => Game::with_gpu(&canvas_id).await,
+        None => Err(JsValue::from_str(&format!("Unknown renderer: {backend}"))),
+    }
+}
+
+fn find_canvas(canvas_id: &str) -> Result<HtmlCanvasElement, JsValue> {
+    let window = window().ok_or("no global `window` exists")?;
+    let document = window
+        .document()
+        .ok_or("should have a document on window")?;
+    Ok(document
+        .get_element_by_id(canvas_id)
+        .ok_or("should have a canvas element")?
+        .dyn_into::<HtmlCanvasElement>()?)
+}
+
+/// Drain captured panics as a JSON array of `ErrorReport`s. A free function
+/// because a panic inside a `Game` method leaves the `Game` itself unusable.
+#[wasm_bindgen]
+pub fn drain_panic_reports() -> String {
+    serde_json::to_string(&errors::take_panic_reports()).unwrap_or_else(|_| "[]".to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,9 +1243,9 @@ mod tests {
     fn test_new_game_state_initialization() {
         let state = GameState::new(800.0, 600.0);
 
-        assert_eq!(state.current_screen, GameScreen::GameHUD);
-        assert_eq!(state.selected_region, Some(Region::EU));
-        assert_eq!(state.player_name, Some("Player".to_string()));
+        assert_eq!(state.current_screen, GameScreen::LoginScreen);
+        assert_eq!(state.selected_region, None);
+        assert_eq!(state.player_name, None);
         assert!(!state.is_loading);
         assert_eq!(state.error_message, None);
         assert_eq!(state.player_x, 400.0);
@@ -423,15 +1256,15 @@ mod tests {
 
     #[test]
     fn test_screen_transitions() {
-        let mut state = GameState::new(800.0, 600.0);
+        let mut state = GameState::new_in_world(800.0, 600.0);
 
-        state.transition_to(GameScreen::Inventory);
+        state.transition_to(GameScreen::Inventory).unwrap();
         assert_eq!(state.current_screen, GameScreen::Inventory);
 
-        state.transition_to(GameScreen::Shop);
+        state.transition_to(GameScreen::Shop).unwrap();
         assert_eq!(state.current_screen, GameScreen::Shop);
 
-        state.transition_to(GameScreen::GameHUD);
+        state.transition_to(GameScreen::GameHUD).unwrap();
         assert_eq!(state.current_screen, GameScreen::GameHUD);
     }
 
@@ -439,7 +1272,8 @@ mod tests {
     fn test_player_movement() {
         let mut state = GameState::new(800.0, 600.0);
 
-        // Movement should work since we start in GameHUD
+        // The state moves the player on any screen; input handling decides
+        // when movement applies
         let initial_x = state.player_x;
         let initial_y = state.player_y;
 
@@ -1,4 +1,336 @@
-// This is the native entry point for development
+// Headless authoritative game server for running multiplayer locally.
+// The browser build uses the library through wasm-bindgen instead.
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    println!("This is a WASM-only project. Use 'wasm-pack build' to build for the web.");
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| native::DEFAULT_ADDR.to_string());
+    if let Err(e) = native::run(&addr, game::ServerConfig::default()) {
+        eprintln!("Server stopped: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use game::{
+        decode, encode, ClientMessage, DisconnectReason, Encoding, Outgoing, PlayerId,
+        ProtocolError, Recipient, ServerConfig, ServerMessage, ServerWorld,
+    };
+    use std::collections::HashMap;
+    use std::io;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError, TrySendError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use tungstenite::{Error as WsError, Message};
+
+    /// Address used when none is given on the command line
+    pub const DEFAULT_ADDR: &str = "127.0.0.1:9001";
+
+    /// How long a connection thread waits for a frame before checking its outbox
+    const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+    /// Clients that send nothing for this long are dropped
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// How long a new connection gets to finish the WebSocket handshake
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Frames queued for one client; a client that falls this far behind
+    /// is disconnected rather than buffered without limit
+    const OUTBOX_CAPACITY: usize = 256;
+
+    type ConnectionId = u64;
+
+    /// What connection threads report to the tick loop
+    enum NetEvent {
+        Opened(ConnectionId, SyncSender<Message>),
+        Frame(ConnectionId, Message),
+        Closed(ConnectionId),
+    }
+
+    struct Connection {
+        outbox: SyncSender<Message>,
+        /// Clients get replies in the encoding they last sent
+        encoding: Encoding,
+        player: Option<PlayerId>,
+        last_heard: Instant,
+    }
+
+    /// Accept WebSocket clients on `addr` and run the simulation forever
+    pub fn run(addr: &str, config: ServerConfig) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        println!(
+            "Game server listening on ws://{} at {} ticks/s",
+            listener.local_addr()?,
+            config.tick_rate
+        );
+
+        let (events, incoming) = mpsc::channel();
+        thread::spawn(move || accept_loop(listener, events));
+
+        let mut server = Server {
+            world: ServerWorld::new(config),
+            connections: HashMap::new(),
+        };
+        let tick = Duration::from_secs_f64(server.world.tick_duration_secs());
+        loop {
+            let started = Instant::now();
+            server.receive(&incoming);
+            server.drop_silent_clients();
+            server.world.step();
+            let interest_changes = server.world.take_outgoing();
+            server.deliver(interest_changes);
+            server.broadcast_snapshots();
+            if let Some(rest) = tick.checked_sub(started.elapsed()) {
+                thread::sleep(rest);
+            }
+        }
+    }
+
+    fn accept_loop(listener: TcpListener, events: Sender<NetEvent>) {
+        for (id, stream) in (1..).zip(listener.incoming()) {
+            match stream {
+                Ok(stream) => {
+                    let events = events.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = serve_connection(id, stream, &events) {
+                            eprintln!("Connection {id} failed: {e}");
+                        }
+                        let _ = events.send(NetEvent::Closed(id));
+                    });
+                }
+                Err(e) => eprintln!("Failed to accept a connection: {e}"),
+            }
+        }
+    }
+
+    /// Pump one socket: forward frames to the tick loop and write whatever it
+    /// sends back, until either side hangs up
+    fn serve_connection(
+        id: ConnectionId,
+        stream: TcpStream,
+        events: &Sender<NetEvent>,
+    ) -> Result<(), Box<WsError>> {
+        // A client that connects and says nothing can't hold the thread forever
+        stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .map_err(WsError::Io)?;
+        let mut socket = tungstenite::accept(stream).map_err(|e| match e {
+            tungstenite::HandshakeError::Failure(e) => e,
+            tungstenite::HandshakeError::Interrupted(_) => WsError::ConnectionClosed,
+        })?;
+        socket
+            .get_ref()
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(WsError::Io)?;
+
+        let (outbox, outgoing): (SyncSender<Message>, Receiver<Message>) =
+            mpsc::sync_channel(OUTBOX_CAPACITY);
+        if events.send(NetEvent::Opened(id, outbox)).is_err() {
+            return Ok(());
+        }
+
+        loop {
+            loop {
+                match outgoing.try_recv() {
+                    Ok(message) => socket.send(message)?,
+                    Err(TryRecvError::Empty) => break,
+                    // The tick loop dropped this client
+                    Err(TryRecvError::Disconnected) => {
+                        let _ = socket.close(None);
+                        let _ = socket.flush();
+                        return Ok(());
+                    }
+                }
+            }
+
+            match socket.read() {
+                Ok(Message::Close(_)) => return Ok(()),
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => {
+                    if events.send(NetEvent::Frame(id, message)).is_err() {
+                        return Ok(());
+                    }
+                }
+                Ok(_) => {}
+                Err(WsError::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(WsError::ConnectionClosed) => return Ok(()),
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+    }
+
+    struct Server {
+        world: ServerWorld,
+        connections: HashMap<ConnectionId, Connection>,
+    }
+
+    impl Server {
+        fn receive(&mut self, incoming: &Receiver<NetEvent>) {
+            while let Ok(event) = incoming.try_recv() {
+                match event {
+                    NetEvent::Opened(id, outbox) => {
+                        self.connections.insert(
+                            id,
+                            Connection {
+                                outbox,
+                                encoding: Encoding::Binary,
+                                player: None,
+                                last_heard: Instant::now(),
+                            },
+                        );
+                    }
+                    NetEvent::Frame(id, message) => self.handle_frame(id, message),
+                    NetEvent::Closed(id) => self.disconnect(id, None),
+                }
+            }
+        }
+
+        fn handle_frame(&mut self, id: ConnectionId, message: Message) {
+            let Some(connection) = self.connections.get_mut(&id) else {
+                return;
+            };
+            let (bytes, encoding) = match message {
+                Message::Text(text) => (text.into_bytes(), Encoding::Json),
+                Message::Binary(bytes) => (bytes, Encoding::Binary),
+                _ => return,
+            };
+            connection.encoding = encoding;
+            connection.last_heard = Instant::now();
+            let player = connection.player;
+
+            let message = match decode::<ClientMessage>(&bytes, encoding) {
+                Ok(message) => message,
+                Err(ProtocolError::VersionMismatch { expected, found }) => {
+                    let reason = DisconnectReason::VersionMismatch {
+                        server: expected,
+                        client: found,
+                    };
+                    return self.disconnect(id, Some(reason));
+                }
+                Err(e) => {
+                    eprintln!("Connection {id} sent a bad frame: {e}");
+                    return;
+                }
+            };
+
+            match (player, message) {
+                (None, ClientMessage::Join { profile, region }) => {
+                    match self.world.join(profile, region) {
+                        Ok(player_id) => {
+                            if let Some(connection) = self.connections.get_mut(&id) {
+                                connection.player = Some(player_id);
+                            }
+                            let welcome = ServerMessage::Welcome {
+                                player_id,
+                                tick_rate: self.world.config().tick_rate,
+                            };
+                            self.send(id, &welcome);
+                            println!("Player {player_id} joined on connection {id}");
+                        }
+                        Err(reason) => self.disconnect(id, Some(reason)),
+                    }
+                }
+                // Nothing but a join is accepted before joining
+                (None, _) => {}
+                (Some(_), ClientMessage::Leave) => self.disconnect(id, None),
+                (Some(player_id), message) => {
+                    let outgoing = self.world.handle(player_id, message);
+                    self.deliver(outgoing);
+                }
+            }
+        }
+
+        fn drop_silent_clients(&mut self) {
+            let silent: Vec<ConnectionId> = self
+                .connections
+                .iter()
+                .filter(|(_, connection)| connection.last_heard.elapsed() > CLIENT_TIMEOUT)
+                .map(|(&id, _)| id)
+                .collect();
+            for id in silent {
+                self.disconnect(id, Some(DisconnectReason::Timeout));
+            }
+        }
+
+        fn broadcast_snapshots(&mut self) {
+            let snapshots: Vec<(ConnectionId, ServerMessage)> = self
+                .connections
+                .iter()
+                .filter_map(|(&id, connection)| {
+                    let snapshot = self.world.snapshot_for(connection.player?)?;
+                    Some((id, ServerMessage::Snapshot(snapshot)))
+                })
+                .collect();
+            for (id, snapshot) in snapshots {
+                self.send(id, &snapshot);
+            }
+        }
+
+        fn deliver(&mut self, outgoing: Vec<Outgoing>) {
+            for Outgoing { to, message } in outgoing {
+                let targets: Vec<ConnectionId> = self
+                    .connections
+                    .iter()
+                    .filter(|(_, connection)| match to {
+                        Recipient::Everyone => connection.player.is_some(),
+                        Recipient::Player(player_id) => connection.player == Some(player_id),
+                    })
+                    .map(|(&id, _)| id)
+                    .collect();
+                for id in targets {
+                    self.send(id, &message);
+                }
+            }
+        }
+
+        fn send(&mut self, id: ConnectionId, message: &ServerMessage) {
+            let Some(connection) = self.connections.get(&id) else {
+                return;
+            };
+            let frame = match encode(message, connection.encoding) {
+                Ok(bytes) if connection.encoding == Encoding::Json => {
+                    Message::Text(String::from_utf8(bytes).expect("JSON frames are UTF-8"))
+                }
+                Ok(bytes) => Message::Binary(bytes),
+                Err(e) => {
+                    eprintln!("Couldn't encode a message for connection {id}: {e}");
+                    return;
+                }
+            };
+            match connection.outbox.try_send(frame) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    println!("Connection {id} isn't keeping up; disconnecting");
+                    self.disconnect(id, None);
+                }
+                // A closed connection reports itself through `NetEvent::Closed`
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+
+        /// Forget a connection, telling the client why if there's a reason;
+        /// dropping its outbox makes the connection thread close the socket
+        fn disconnect(&mut self, id: ConnectionId, reason: Option<DisconnectReason>) {
+            if let Some(reason) = reason {
+                println!("Disconnecting connection {id}: {reason}");
+                self.send(id, &ServerMessage::Disconnect(reason));
+            }
+            if let Some(connection) = self.connections.remove(&id) {
+                if let Some(player_id) = connection.player {
+                    self.world.leave(player_id);
+                    println!("Player {player_id} left");
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,197 @@
+use crate::game_state::{BASE_MAX_HP, MAX_HP_PER_LEVEL, STARTING_GOLD, STARTING_ZONE};
+use crate::profile::PlayerClass;
+use crate::save::{SaveError, SAVE_VERSION};
+use serde_json::{json, Value};
+
+/// Upgrades a snapshot's `state` object by exactly one version
+type Migration = fn(&mut serde_json::Map<String, Value>) -> Result<(), SaveError>;
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to version `i + 2`.
+/// Append a step here every time `SAVE_VERSION` is bumped, and check in a
+/// fixture of the old version under `fixtures/saves/`.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+];
+
+/// Oldest save version that can still be loaded
+pub const MIN_SUPPORTED_VERSION: u32 = 1;
+
+/// Upgrade a snapshot from `from_version` to `SAVE_VERSION` one step at a time
+pub fn migrate(mut snapshot: Value, from_version: u32) -> Result<Value, SaveError> {
+    if !(MIN_SUPPORTED_VERSION..=SAVE_VERSION).contains(&from_version) {
+        return Err(SaveError::UnsupportedVersion {
+            found: from_version,
+            current: SAVE_VERSION,
+        });
+    }
+
+    for version in from_version..SAVE_VERSION {
+        let step = MIGRATIONS[(version - MIN_SUPPORTED_VERSION) as usize];
+        let state = snapshot
+            .get_mut("state")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| SaveError::Malformed("missing `state` object".to_string()))?;
+        step(state)?;
+        snapshot["version"] = json!(version + 1);
+    }
+    Ok(snapshot)
+}
+
+/// v2 added zone, level and playtime for save slot metadata
+fn migrate_v1_to_v2(state: &mut serde_json::Map<String, Value>) -> Result<(), SaveError> {
+    state.insert("current_zone".to_string(), json!(STARTING_ZONE));
+    state.insert("player_level".to_string(), json!(1));
+    state.insert("playtime_secs".to_string(), json!(0.0));
+    Ok(())
+}
+
+/// v3 added gold for the shop
+fn migrate_v2_to_v3(state: &mut serde_json::Map<String, Value>) -> Result<(), SaveError> {
+    state.insert("gold".to_string(), json!(STARTING_GOLD));
+    Ok(())
+}
+
+/// v4 added the character class and appearance to the profile
+fn migrate_v3_to_v4(state: &mut serde_json::Map<String, Value>) -> Result<(), SaveError> {
+    state.insert("player_class".to_string(), json!(PlayerClass::default()));
+    state.insert("appearance".to_string(), json!(0));
+    Ok(())
+}
+
+/// v5 added XP and HP; existing characters start at full health for
+/// their level
+fn migrate_v4_to_v5(state: &mut serde_json::Map<String, Value>) -> Result<(), SaveError> {
+    let level = state
+        .get("player_level")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1);
+    let max_hp = BASE_MAX_HP + MAX_HP_PER_LEVEL * (level - 1) as f64;
+    state.insert("player_xp".to_string(), json!(0));
+    state.insert("player_hp".to_string(), json!(max_hp));
+    state.insert("player_max_hp".to_string(), json!(max_hp));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GameScreen;
+    use crate::save::SaveSnapshot;
+    use std::path::PathBuf;
+
+    fn fixtures_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/saves")
+    }
+
+    fn fixture_version(json: &str) -> u32 {
+        let value: Value = serde_json::from_str(json).unwrap();
+        value["version"].as_u64().unwrap() as u32
+    }
+
+    #[test]
+    fn test_one_migration_per_version() {
+        assert_eq!(
+            MIGRATIONS.len() as u32,
+            SAVE_VERSION - MIN_SUPPORTED_VERSION
+        );
+    }
+
+    #[test]
+    fn test_every_version_has_a_fixture() {
+        let mut versions: Vec<u32> = std::fs::read_dir(fixtures_dir())
+            .unwrap()
+            .map(|entry| fixture_version(&std::fs::read_to_string(entry.unwrap().path()).unwrap()))
+            .collect();
+        versions.sort_unstable();
+
+        let expected: Vec<u32> = (MIN_SUPPORTED_VERSION..=SAVE_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_every_fixture_loads_into_current_state() {
+        for entry in std::fs::read_dir(fixtures_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let json = std::fs::read_to_string(&path).unwrap();
+
+            let snapshot = SaveSnapshot::from_json(&json)
+                .unwrap_or_else(|e| panic!("{} failed to load: {e}", path.display()));
+            assert_eq!(snapshot.version, SAVE_VERSION);
+            assert_eq!(snapshot.state.player_name, Some("Fixture".to_string()));
+            assert_eq!(snapshot.state.inventory.count("iron_ore"), 5);
+        }
+    }
+
+    #[test]
+    fn test_v1_to_v2_fills_defaults() {
+        let json = std::fs::read_to_string(fixtures_dir().join("v1.json")).unwrap();
+        assert_eq!(fixture_version(&json), 1);
+
+        let snapshot = SaveSnapshot::from_json(&json).unwrap();
+        assert_eq!(snapshot.state.current_zone, STARTING_ZONE);
+        assert_eq!(snapshot.state.player_level, 1);
+        assert_eq!(snapshot.state.playtime_secs, 0.0);
+    }
+
+    #[test]
+    fn test_v2_to_v3_grants_starting_gold() {
+        let json = std::fs::read_to_string(fixtures_dir().join("v2.json")).unwrap();
+
+        let snapshot = SaveSnapshot::from_json(&json).unwrap();
+        assert_eq!(snapshot.state.gold, STARTING_GOLD);
+        assert_eq!(snapshot.state.current_zone, "Dark Forest");
+    }
+
+    #[test]
+    fn test_v3_to_v4_defaults_profile() {
+        let json = std::fs::read_to_string(fixtures_dir().join("v3.json")).unwrap();
+
+        let snapshot = SaveSnapshot::from_json(&json).unwrap();
+        assert_eq!(snapshot.state.player_class, PlayerClass::Warrior);
+        assert_eq!(snapshot.state.appearance, 0);
+        assert_eq!(snapshot.state.gold, 250);
+    }
+
+    #[test]
+    fn test_v4_to_v5_heals_to_level_max() {
+        let json = std::fs::read_to_string(fixtures_dir().join("v4.json")).unwrap();
+
+        let snapshot = SaveSnapshot::from_json(&json).unwrap();
+        assert_eq!(snapshot.state.player_level, 3);
+        assert_eq!(snapshot.state.player_xp, 0);
+        assert_eq!(snapshot.state.player_max_hp, 120.0);
+        assert_eq!(snapshot.state.player_hp, 120.0);
+    }
+
+    #[test]
+    fn test_save_with_modal_open_loads_in_world() {
+        let json = std::fs::read_to_string(fixtures_dir().join("v5.json")).unwrap();
+
+        let snapshot = SaveSnapshot::from_json(&json).unwrap();
+        assert_eq!(snapshot.state.current_screen, GameScreen::Shop);
+        assert_eq!(
+            snapshot.state.screens(),
+            vec![GameScreen::GameHUD, GameScreen::Shop]
+        );
+        assert!(snapshot.state.in_world());
+    }
+
+    #[test]
+    fn test_rejects_versions_outside_chain() {
+        assert_eq!(
+            migrate(json!({"version": 0, "state": {}}), 0).unwrap_err(),
+            SaveError::UnsupportedVersion {
+                found: 0,
+                current: SAVE_VERSION,
+            }
+        );
+        assert!(matches!(
+            migrate(json!({"version": 1}), 1),
+            Err(SaveError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,727 @@
+use crate::game_state::Region;
+use crate::profile::PlayerProfile;
+use crate::protocol::{
+    decode, encode, ClientMessage, DisconnectReason, Encoding, PlayerId, ProtocolError,
+    ServerMessage,
+};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// How often a connected client pings the server
+pub const HEARTBEAT_INTERVAL_MS: f64 = 2_000.0;
+
+/// Silence after which the connection is considered dead
+pub const CONNECTION_TIMEOUT_MS: f64 = 6_000.0;
+
+/// Delay before the first reconnect; doubled for every failed attempt
+pub const INITIAL_BACKOFF_MS: f64 = 500.0;
+
+/// Longest delay between reconnect attempts
+pub const MAX_BACKOFF_MS: f64 = 15_000.0;
+
+/// Connection attempts made before giving up
+pub const MAX_CONNECT_ATTEMPTS: u32 = 8;
+
+/// Server messages kept for the game to consume before the oldest are dropped
+pub const MAX_INBOX_MESSAGES: usize = 256;
+
+/// Something that happened on the underlying socket
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransportEvent {
+    Opened,
+    Frame(Vec<u8>),
+    Closed,
+}
+
+/// A message-oriented connection to one endpoint
+pub trait Transport {
+    fn open(&mut self, endpoint: &str) -> Result<(), NetworkError>;
+    /// Send one frame; JSON frames go out as text so they're readable in dev tools
+    fn send(&mut self, frame: &[u8], encoding: Encoding) -> Result<(), NetworkError>;
+    /// Events received since the last poll, oldest first
+    fn poll(&mut self) -> Vec<TransportEvent>;
+    fn close(&mut self);
+}
+
+/// Reasons the multiplayer connection fails
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    /// Connecting needs a player name and region first
+    NotLoggedIn,
+    NoServer(Region),
+    Transport(String),
+    Protocol(ProtocolError),
+    Rejected(DisconnectReason),
+    /// A live connection dropped; the client is reconnecting
+    ConnectionLost,
+    GaveUp {
+        attempts: u32,
+    },
+}
+
+impl NetworkError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            NetworkError::NotLoggedIn => "network_not_logged_in",
+            NetworkError::NoServer(_) => "network_no_server",
+            NetworkError::Transport(_) => "network_transport",
+            NetworkError::Protocol(_) => "network_protocol",
+            NetworkError::Rejected(_) => "network_rejected",
+            NetworkError::ConnectionLost => "network_connection_lost",
+            NetworkError::GaveUp { .. } => "network_gave_up",
+        }
+    }
+
+    /// Whether the client keeps trying after this error
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            NetworkError::Transport(_) | NetworkError::Protocol(_) | NetworkError::ConnectionLost
+        )
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::NotLoggedIn => write!(f, "Pick a name and region before connecting"),
+            NetworkError::NoServer(region) => write!(f, "No server is available in {region:?}"),
+            NetworkError::Transport(reason) => write!(f, "Network error: {reason}"),
+            NetworkError::Protocol(e) => e.fmt(f),
+            NetworkError::Rejected(reason) => write!(f, "Disconnected: {reason}"),
+            NetworkError::ConnectionLost => write!(f, "Connection lost, reconnecting..."),
+            NetworkError::GaveUp { attempts } => {
+                write!(f, "Couldn't reach the server after {attempts} attempts")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// Where the connection to the game server stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    Disconnected,
+    /// Opening the socket
+    Connecting {
+        attempt: u32,
+    },
+    /// Socket open, waiting for the server's `Welcome`
+    Handshaking {
+        attempt: u32,
+    },
+    Connected {
+        player_id: PlayerId,
+    },
+    /// Waiting out the backoff before the next attempt
+    WaitingToRetry {
+        attempt: u32,
+        retry_at_ms: f64,
+    },
+    /// Stopped trying; `connect` starts over
+    Failed,
+}
+
+impl ConnectionStatus {
+    /// Whether the UI should show "Connecting..."
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            ConnectionStatus::Connecting { .. }
+                | ConnectionStatus::Handshaking { .. }
+                | ConnectionStatus::WaitingToRetry { .. }
+        )
+    }
+}
+
+/// Delay before attempt `attempt + 1` after `attempt` failed
+pub fn backoff_ms(attempt: u32) -> f64 {
+    let doublings = attempt.saturating_sub(1).min(16);
+    (INITIAL_BACKOFF_MS * f64::from(1u32 << doublings)).min(MAX_BACKOFF_MS)
+}
+
+/// Keeps one connection to a game server alive: handshake, heartbeat,
+/// timeouts and reconnecting with exponential backoff. Driven by `update`
+/// from the game loop.
+#[derive(Debug)]
+pub struct ConnectionManager<T: Transport> {
+    transport: T,
+    encoding: Encoding,
+    endpoint: Option<String>,
+    join: Option<ClientMessage>,
+    status: ConnectionStatus,
+    last_heard_ms: f64,
+    last_ping_ms: f64,
+    latency_ms: Option<f64>,
+    /// Server ticks per second, from its `Welcome`
+    tick_rate: Option<u16>,
+    inbox: VecDeque<ServerMessage>,
+    errors: Vec<NetworkError>,
+}
+
+impl<T: Transport> ConnectionManager<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            encoding: Encoding::Binary,
+            endpoint: None,
+            join: None,
+            status: ConnectionStatus::Disconnected,
+            last_heard_ms: 0.0,
+            last_ping_ms: 0.0,
+            latency_ms: None,
+            tick_rate: None,
+            inbox: VecDeque::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Use JSON frames, e.g. to read traffic in the browser's dev tools
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn status(&self) -> &ConnectionStatus {
+        &self.status
+    }
+
+    pub fn player_id(&self) -> Option<PlayerId> {
+        match self.status {
+            ConnectionStatus::Connected { player_id } => Some(player_id),
+            _ => None,
+        }
+    }
+
+    /// Server ticks per second, once it has welcomed us
+    pub fn tick_rate(&self) -> Option<u16> {
+        self.tick_rate
+    }
+
+    /// Round-trip time from the last heartbeat
+    pub fn latency_ms(&self) -> Option<f64> {
+        self.latency_ms
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Start connecting to `endpoint`, dropping any current connection
+    pub fn connect(&mut self, endpoint: &str, profile: PlayerProfile, region: Region, now_ms: f64) {
+        self.transport.close();
+        self.endpoint = Some(endpoint.to_string());
+        self.join = Some(ClientMessage::Join { profile, region });
+        self.inbox.clear();
+        self.open(1, now_ms);
+    }
+
+    /// Leave the server and stop reconnecting
+    pub fn disconnect(&mut self) {
+        if matches!(self.status, ConnectionStatus::Connected { .. }) {
+            let _ = self.send(&ClientMessage::Leave);
+        }
+        self.transport.close();
+        self.endpoint = None;
+        self.join = None;
+        self.status = ConnectionStatus::Disconnected;
+    }
+
+    /// Send a message over the live connection
+    pub fn send(&mut self, message: &ClientMessage) -> Result<(), NetworkError> {
+        if !matches!(self.status, ConnectionStatus::Connected { .. }) {
+            return Err(NetworkError::Transport("not connected".to_string()));
+        }
+        self.send_frame(message)
+    }
+
+    /// Take the server messages received so far
+    pub fn drain_messages(&mut self) -> Vec<ServerMessage> {
+        self.inbox.drain(..).collect()
+    }
+
+    /// Process socket events and timers, returning errors worth showing
+    pub fn update(&mut self, now_ms: f64) -> Vec<NetworkError> {
+        for event in self.transport.poll() {
+            self.handle_event(event, now_ms);
+        }
+
+        match self.status {
+            ConnectionStatus::WaitingToRetry {
+                attempt,
+                retry_at_ms,
+            } if now_ms >= retry_at_ms => self.open(attempt, now_ms),
+            ConnectionStatus::Connecting { attempt }
+            | ConnectionStatus::Handshaking { attempt }
+                if now_ms - self.last_heard_ms > CONNECTION_TIMEOUT_MS =>
+            {
+                self.transport.close();
+                self.retry_later(attempt, now_ms);
+            }
+            ConnectionStatus::Connected { .. } => {
+                if now_ms - self.last_heard_ms > CONNECTION_TIMEOUT_MS {
+                    self.connection_lost(now_ms);
+                } else if now_ms - self.last_ping_ms >= HEARTBEAT_INTERVAL_MS {
+                    self.last_ping_ms = now_ms;
+                    if let Err(e) = self.send_frame(&ClientMessage::Ping { sent_at_ms: now_ms }) {
+                        self.errors.push(e);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        std::mem::take(&mut self.errors)
+    }
+
+    fn handle_event(&mut self, event: TransportEvent, now_ms: f64) {
+        match (event, self.status.clone()) {
+            (TransportEvent::Opened, ConnectionStatus::Connecting { attempt }) => {
+                self.status = ConnectionStatus::Handshaking { attempt };
+                self.last_heard_ms = now_ms;
+                if let Some(join) = self.join.clone() {
+                    if let Err(e) = self.send_frame(&join) {
+                        self.errors.push(e);
+                    }
+                }
+            }
+            (TransportEvent::Frame(bytes), status) => {
+                self.last_heard_ms = now_ms;
+                match decode::<ServerMessage>(&bytes, self.encoding) {
+                    Ok(message) => self.handle_message(message, status, now_ms),
+                    Err(ProtocolError::VersionMismatch { expected, found }) => {
+                        self.fail(NetworkError::Rejected(DisconnectReason::VersionMismatch {
+                            server: found,
+                            client: expected,
+                        }));
+                    }
+                    Err(e) => self.errors.push(NetworkError::Protocol(e)),
+                }
+            }
+            (TransportEvent::Closed, ConnectionStatus::Connected { .. }) => {
+                self.connection_lost(now_ms)
+            }
+            (
+                TransportEvent::Closed,
+                ConnectionStatus::Connecting { attempt }
+                | ConnectionStatus::Handshaking { attempt },
+            ) => self.retry_later(attempt, now_ms),
+            _ => {}
+        }
+    }
+
+    fn handle_message(&mut self, message: ServerMessage, status: ConnectionStatus, now_ms: f64) {
+        match (message, status) {
+            (
+                ServerMessage::Welcome {
+                    player_id,
+                    tick_rate,
+                },
+                ConnectionStatus::Handshaking { .. },
+            ) => {
+                self.status = ConnectionStatus::Connected { player_id };
+                self.tick_rate = Some(tick_rate);
+                self.last_ping_ms = now_ms;
+            }
+            (ServerMessage::Pong { sent_at_ms }, _) => {
+                self.latency_ms = Some((now_ms - sent_at_ms).max(0.0));
+            }
+            (ServerMessage::Disconnect(reason), status) => {
+                self.transport.close();
+                if !reason.is_retryable() {
+                    return self.fail(NetworkError::Rejected(reason));
+                }
+                let attempt = match status {
+                    ConnectionStatus::Connecting { attempt }
+                    | ConnectionStatus::Handshaking { attempt } => attempt,
+                    _ => 1,
+                };
+                self.errors.push(NetworkError::Rejected(reason));
+                self.retry_later(attempt, now_ms);
+            }
+            (message, ConnectionStatus::Connected { .. }) => {
+                if self.inbox.len() >= MAX_INBOX_MESSAGES {
+                    self.inbox.pop_front();
+                }
+                self.inbox.push_back(message);
+            }
+            _ => {}
+        }
+    }
+
+    fn open(&mut self, attempt: u32, now_ms: f64) {
+        let Some(endpoint) = self.endpoint.clone() else {
+            self.status = ConnectionStatus::Disconnected;
+            return;
+        };
+        self.status = ConnectionStatus::Connecting { attempt };
+        self.last_heard_ms = now_ms;
+        if let Err(e) = self.transport.open(&endpoint) {
+            self.errors.push(e);
+            self.retry_later(attempt, now_ms);
+        }
+    }
+
+    /// Schedule the attempt after `attempt`, or give up
+    fn retry_later(&mut self, attempt: u32, now_ms: f64) {
+        if attempt >= MAX_CONNECT_ATTEMPTS {
+            self.fail(NetworkError::GaveUp { attempts: attempt });
+            return;
+        }
+        self.status = ConnectionStatus::WaitingToRetry {
+            attempt: attempt + 1,
+            retry_at_ms: now_ms + backoff_ms(attempt),
+        };
+    }
+
+    fn connection_lost(&mut self, now_ms: f64) {
+        self.transport.close();
+        self.errors.push(NetworkError::ConnectionLost);
+        // Reconnect right away; the backoff applies if that fails too
+        self.status = ConnectionStatus::WaitingToRetry {
+            attempt: 1,
+            retry_at_ms: now_ms,
+        };
+    }
+
+    fn fail(&mut self, error: NetworkError) {
+        self.transport.close();
+        self.status = ConnectionStatus::Failed;
+        self.errors.push(error);
+    }
+
+    fn send_frame(&mut self, message: &ClientMessage) -> Result<(), NetworkError> {
+        let frame = encode(message, self.encoding).map_err(NetworkError::Protocol)?;
+        self.transport.send(&frame, self.encoding)
+    }
+}
+
+/// Scripted transport for native builds and tests
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    pub opened: Vec<String>,
+    pub sent: Vec<Vec<u8>>,
+    pub is_open: bool,
+    pending: VecDeque<TransportEvent>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an event for the next poll
+    pub fn push(&mut self, event: TransportEvent) {
+        self.pending.push_back(event);
+    }
+}
+
+impl Transport for MockTransport {
+    fn open(&mut self, endpoint: &str) -> Result<(), NetworkError> {
+        self.opened.push(endpoint.to_string());
+        self.is_open = true;
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &[u8], _encoding: Encoding) -> Result<(), NetworkError> {
+        self.sent.push(frame.to_vec());
+        Ok(())
+    }
+
+    fn poll(&mut self) -> Vec<TransportEvent> {
+        self.pending.drain(..).collect()
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+}
+
+/// Browser `WebSocket` backend
+#[derive(Default)]
+pub struct WebSocketTransport {
+    socket: Option<web_sys::WebSocket>,
+    events: Rc<RefCell<VecDeque<TransportEvent>>>,
+    // Kept alive for as long as the socket can call them
+    callbacks: Vec<Closure<dyn FnMut(JsValue)>>,
+}
+
+impl fmt::Debug for WebSocketTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("open", &self.socket.is_some())
+            .finish()
+    }
+}
+
+impl WebSocketTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn callback(&mut self, handler: impl FnMut(JsValue) + 'static) -> js_sys::Function {
+        let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut(JsValue)>);
+        let function = closure.as_ref().unchecked_ref::<js_sys::Function>().clone();
+        self.callbacks.push(closure);
+        function
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn open(&mut self, endpoint: &str) -> Result<(), NetworkError> {
+        self.close();
+        let socket = web_sys::WebSocket::new(endpoint)
+            .map_err(|e| NetworkError::Transport(format!("{e:?}")))?;
+        socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+        let events = Rc::clone(&self.events);
+        let onopen = self.callback(move |_| events.borrow_mut().push_back(TransportEvent::Opened));
+        let events = Rc::clone(&self.events);
+        let onmessage = self.callback(move |event| {
+            let Ok(event) = event.dyn_into::<web_sys::MessageEvent>() else {
+                return;
+            };
+            let data = event.data();
+            let bytes = match data.as_string() {
+                Some(text) => text.into_bytes(),
+                None => js_sys::Uint8Array::new(&data).to_vec(),
+            };
+            events.borrow_mut().push_back(TransportEvent::Frame(bytes));
+        });
+        // Browsers always follow `error` with `close`, so only `close` is reported
+        let events = Rc::clone(&self.events);
+        let onclose = self.callback(move |_| events.borrow_mut().push_back(TransportEvent::Closed));
+
+        socket.set_onopen(Some(&onopen));
+        socket.set_onmessage(Some(&onmessage));
+        socket.set_onclose(Some(&onclose));
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    fn send(&mut self, frame: &[u8], encoding: Encoding) -> Result<(), NetworkError> {
+        let socket = self
+            .socket
+            .as_ref()
+            .ok_or_else(|| NetworkError::Transport("socket is closed".to_string()))?;
+        let sent = match (encoding, std::str::from_utf8(frame)) {
+            (Encoding::Json, Ok(text)) => socket.send_with_str(text),
+            _ => socket.send_with_u8_array(frame),
+        };
+        sent.map_err(|e| NetworkError::Transport(format!("{e:?}")))
+    }
+
+    fn poll(&mut self) -> Vec<TransportEvent> {
+        self.events.borrow_mut().drain(..).collect()
+    }
+
+    fn close(&mut self) {
+        if let Some(socket) = self.socket.take() {
+            socket.set_onopen(None);
+            socket.set_onmessage(None);
+            socket.set_onclose(None);
+            let _ = socket.close();
+        }
+        self.callbacks.clear();
+        self.events.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatChannel;
+    use crate::profile::PlayerClass;
+
+    fn manager() -> ConnectionManager<MockTransport> {
+        let mut manager = ConnectionManager::new(MockTransport::new());
+        let profile = PlayerProfile::new("Hero", PlayerClass::Mage, 0).unwrap();
+        manager.connect("ws://localhost:9001", profile, Region::EU, 0.0);
+        manager
+    }
+
+    fn receive(manager: &mut ConnectionManager<MockTransport>, message: ServerMessage) {
+        let frame = encode(&message, Encoding::Binary).unwrap();
+        manager.transport_mut().push(TransportEvent::Frame(frame));
+    }
+
+    fn sent(manager: &ConnectionManager<MockTransport>) -> Vec<ClientMessage> {
+        manager
+            .transport()
+            .sent
+            .iter()
+            .map(|frame| decode(frame, Encoding::Binary).unwrap())
+            .collect()
+    }
+
+    fn connected() -> ConnectionManager<MockTransport> {
+        let mut manager = manager();
+        manager.transport_mut().push(TransportEvent::Opened);
+        manager.update(10.0);
+        receive(
+            &mut manager,
+            ServerMessage::Welcome {
+                player_id: 4,
+                tick_rate: 20,
+            },
+        );
+        manager.update(20.0);
+        manager
+    }
+
+    #[test]
+    fn test_handshake_sends_join() {
+        let mut manager = manager();
+        assert_eq!(
+            manager.status(),
+            &ConnectionStatus::Connecting { attempt: 1 }
+        );
+        assert!(manager.status().is_pending());
+
+        manager.transport_mut().push(TransportEvent::Opened);
+        assert!(manager.update(10.0).is_empty());
+        assert_eq!(
+            manager.status(),
+            &ConnectionStatus::Handshaking { attempt: 1 }
+        );
+        assert!(matches!(sent(&manager)[0], ClientMessage::Join { .. }));
+
+        receive(
+            &mut manager,
+            ServerMessage::Welcome {
+                player_id: 4,
+                tick_rate: 20,
+            },
+        );
+        manager.update(20.0);
+        assert_eq!(manager.player_id(), Some(4));
+        assert!(!manager.status().is_pending());
+    }
+
+    #[test]
+    fn test_heartbeat_measures_latency() {
+        let mut manager = connected();
+
+        manager.update(20.0 + HEARTBEAT_INTERVAL_MS);
+        assert_eq!(
+            sent(&manager).last(),
+            Some(&ClientMessage::Ping {
+                sent_at_ms: 20.0 + HEARTBEAT_INTERVAL_MS
+            })
+        );
+
+        receive(
+            &mut manager,
+            ServerMessage::Pong {
+                sent_at_ms: 20.0 + HEARTBEAT_INTERVAL_MS,
+            },
+        );
+        manager.update(80.0 + HEARTBEAT_INTERVAL_MS);
+        assert_eq!(manager.latency_ms(), Some(60.0));
+    }
+
+    #[test]
+    fn test_silent_server_triggers_reconnect() {
+        let mut manager = connected();
+
+        let errors = manager.update(30.0 + CONNECTION_TIMEOUT_MS);
+        assert_eq!(errors, vec![NetworkError::ConnectionLost]);
+        assert!(!manager.transport().is_open);
+
+        manager.update(40.0 + CONNECTION_TIMEOUT_MS);
+        assert_eq!(
+            manager.status(),
+            &ConnectionStatus::Connecting { attempt: 1 }
+        );
+        assert_eq!(manager.transport().opened.len(), 2);
+    }
+
+    #[test]
+    fn test_backoff_then_give_up() {
+        assert_eq!(backoff_ms(1), INITIAL_BACKOFF_MS);
+        assert_eq!(backoff_ms(3), INITIAL_BACKOFF_MS * 4.0);
+        assert_eq!(backoff_ms(30), MAX_BACKOFF_MS);
+
+        let mut manager = manager();
+        let mut now = 0.0;
+        for attempt in 1..MAX_CONNECT_ATTEMPTS {
+            manager.transport_mut().push(TransportEvent::Closed);
+            assert!(manager.update(now).is_empty());
+            assert_eq!(
+                manager.status(),
+                &ConnectionStatus::WaitingToRetry {
+                    attempt: attempt + 1,
+                    retry_at_ms: now + backoff_ms(attempt),
+                }
+            );
+            now += backoff_ms(attempt);
+            manager.update(now);
+        }
+
+        manager.transport_mut().push(TransportEvent::Closed);
+        assert_eq!(
+            manager.update(now),
+            vec![NetworkError::GaveUp {
+                attempts: MAX_CONNECT_ATTEMPTS
+            }]
+        );
+        assert_eq!(manager.status(), &ConnectionStatus::Failed);
+        assert_eq!(
+            manager.transport().opened.len(),
+            MAX_CONNECT_ATTEMPTS as usize
+        );
+    }
+
+    #[test]
+    fn test_rejection_stops_reconnecting() {
+        let mut manager = connected();
+        receive(
+            &mut manager,
+            ServerMessage::Disconnect(DisconnectReason::Kicked("spam".to_string())),
+        );
+
+        let errors = manager.update(30.0);
+        assert_eq!(errors[0].code(), "network_rejected");
+        assert!(!errors[0].is_recoverable());
+        assert_eq!(manager.status(), &ConnectionStatus::Failed);
+
+        let mut manager = connected();
+        receive(
+            &mut manager,
+            ServerMessage::Disconnect(DisconnectReason::ServerShutdown),
+        );
+        manager.update(30.0);
+        assert!(manager.status().is_pending());
+    }
+
+    #[test]
+    fn test_game_messages_are_queued_once_connected() {
+        let mut manager = connected();
+        receive(
+            &mut manager,
+            ServerMessage::Chat {
+                from: 1,
+                name: "Other".to_string(),
+                channel: ChatChannel::Global,
+                text: "hi".to_string(),
+            },
+        );
+        manager.update(30.0);
+        assert_eq!(manager.drain_messages().len(), 1);
+        assert!(manager.drain_messages().is_empty());
+
+        manager.disconnect();
+        assert_eq!(sent(&manager).last(), Some(&ClientMessage::Leave));
+        assert_eq!(manager.status(), &ConnectionStatus::Disconnected);
+        assert!(manager.send(&ClientMessage::Leave).is_err());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::protocol::PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Most players in one party, leader included
+pub const MAX_PARTY_SIZE: usize = 5;
+
+/// How long an invite can be accepted for
+pub const PARTY_INVITE_TIMEOUT_MS: f64 = 60_000.0;
+
+/// Extra XP per additional member sharing a kill, as a percentage, so
+/// grouping up isn't slower than playing alone
+pub const PARTY_XP_BONUS_PERCENT: u32 = 10;
+
+pub type PartyId = u32;
+
+/// Something a player asks the server to do with parties
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PartyAction {
+    Invite { name: String },
+    Accept,
+    Decline,
+    Leave,
+    Kick { id: PlayerId },
+    Promote { id: PlayerId },
+}
+
+/// One member as shown in the HUD's party frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct PartyMember {
+    pub id: PlayerId,
+    pub name: String,
+    pub level: u32,
+    pub hp: f64,
+    pub max_hp: f64,
+}
+
+/// A party as its members see it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct PartyView {
+    pub leader: PlayerId,
+    /// Members in the order they joined, leader included
+    pub members: Vec<PartyMember>,
+}
+
+/// Reasons a party action is refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartyError {
+    AlreadyInParty,
+    TargetInParty(String),
+    NotInParty,
+    NotLeader,
+    PartyFull,
+    NoInvite,
+    UnknownPlayer(String),
+    NotAMember,
+    CannotTargetSelf,
+    NotConnected,
+    /// The server refused the action, with its explanation
+    Rejected(String),
+}
+
+impl PartyError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            PartyError::AlreadyInParty => "party_already_in_party",
+            PartyError::TargetInParty(_) => "party_target_in_party",
+            PartyError::NotInParty => "party_not_in_party",
+            PartyError::NotLeader => "party_not_leader",
+            PartyError::PartyFull => "party_full",
+            PartyError::NoInvite => "party_no_invite",
+            PartyError::UnknownPlayer(_) => "party_unknown_player",
+            PartyError::NotAMember => "party_not_a_member",
+            PartyError::CannotTargetSelf => "party_cannot_target_self",
+            PartyError::NotConnected => "party_not_connected",
+            PartyError::Rejected(_) => "party_rejected",
+        }
+    }
+}
+
+impl fmt::Display for PartyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartyError::AlreadyInParty => write!(f, "You're already in a party"),
+            PartyError::TargetInParty(name) => write!(f, "{name} is already in a party"),
+            PartyError::NotInParty => write!(f, "You're not in a party"),
+            PartyError::NotLeader => write!(f, "Only the party leader can do that"),
+            PartyError::PartyFull => write!(f, "The party is full ({MAX_PARTY_SIZE} players)"),
+            PartyError::NoInvite => write!(f, "You have no pending party invite"),
+            PartyError::UnknownPlayer(name) => write!(f, "No player named '{name}' is online"),
+            PartyError::NotAMember => write!(f, "That player isn't in your party"),
+            PartyError::CannotTargetSelf => write!(f, "You can't do that to yourself"),
+            PartyError::NotConnected => write!(f, "Connect to a server to join a party"),
+            PartyError::Rejected(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for PartyError {}
+
+/// A group of players, by id
+#[derive(Debug, Clone, PartialEq)]
+pub struct Party {
+    pub id: PartyId,
+    pub leader: PlayerId,
+    /// In the order they joined, leader included
+    pub members: Vec<PlayerId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Invite {
+    from: PlayerId,
+    expires_at_ms: f64,
+}
+
+/// Server-side party bookkeeping. Knows nothing about names or the network,
+/// so it can be tested on its own.
+#[derive(Debug, Clone)]
+pub struct PartyManager {
+    parties: BTreeMap<PartyId, Party>,
+    membership: HashMap<PlayerId, PartyId>,
+    /// Pending invites by invitee; a newer invite replaces an older one
+    invites: HashMap<PlayerId, Invite>,
+    next_party_id: PartyId,
+}
+
+impl Default for PartyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartyManager {
+    pub fn new() -> Self {
+        Self {
+            parties: BTreeMap::new(),
+            membership: HashMap::new(),
+            invites: HashMap::new(),
+            next_party_id: 1,
+        }
+    }
+
+    pub fn party_of(&self, id: PlayerId) -> Option<&Party> {
+        self.parties.get(self.membership.get(&id)?)
+    }
+
+    pub fn parties(&self) -> impl Iterator<Item = &Party> {
+        self.parties.values()
+    }
+
+    /// Who invited `id`, if the invite is still open at `now_ms`
+    pub fn pending_invite(&self, id: PlayerId, now_ms: f64) -> Option<PlayerId> {
+        self.invites
+            .get(&id)
+            .filter(|invite| invite.expires_at_ms > now_ms)
+            .map(|invite| invite.from)
+    }
+
+    /// Invite `to` into `from`'s party, or into a new one once accepted.
+    /// `to_name` is only used to explain refusals.
+    pub fn invite(
+        &mut self,
+        from: PlayerId,
+        to: PlayerId,
+        to_name: &str,
+        now_ms: f64,
+    ) -> Result<(), PartyError> {
+        if from == to {
+            return Err(PartyError::CannotTargetSelf);
+        }
+        if let Some(party) = self.party_of(from) {
+            if party.leader != from {
+                return Err(PartyError::NotLeader);
+            }
+            if party.members.len() >= MAX_PARTY_SIZE {
+                return Err(PartyError::PartyFull);
+            }
+        }
+        if self.membership.contains_key(&to) {
+            return Err(PartyError::TargetInParty(to_name.to_string()));
+        }
+        self.invites.insert(
+            to,
+            Invite {
+                from,
+                expires_at_ms: now_ms + PARTY_INVITE_TIMEOUT_MS,
+            },
+        );
+        Ok(())
+    }
+
+    /// Accept the pending invite, joining the inviter's party or forming one
+    pub fn accept(&mut self, id: PlayerId, now_ms: f64) -> Result<PartyId, PartyError> {
+        let inviter = self
+            .pending_invite(id, now_ms)
+            .ok_or(PartyError::NoInvite)?;
+        if self.membership.contains_key(&id) {
+            self.invites.remove(&id);
+            return Err(PartyError::AlreadyInParty);
+        }
+
+        let party_id = match self.membership.get(&inviter) {
+            Some(&party_id) => {
+                let party = self
+                    .parties
+                    .get_mut(&party_id)
+                    .expect("members have a party");
+                if party.members.len() >= MAX_PARTY_SIZE {
+                    return Err(PartyError::PartyFull);
+                }
+                party.members.push(id);
+                party_id
+            }
+            None => {
+                let party_id = self.next_party_id;
+                self.next_party_id += 1;
+                self.parties.insert(
+                    party_id,
+                    Party {
+                        id: party_id,
+                        leader: inviter,
+                        members: vec![inviter, id],
+                    },
+                );
+                self.membership.insert(inviter, party_id);
+                party_id
+            }
+        };
+        self.membership.insert(id, party_id);
+        self.invites.remove(&id);
+        Ok(party_id)
+    }
+
+    /// Turn down the pending invite, returning who sent it
+    pub fn decline(&mut self, id: PlayerId) -> Result<PlayerId, PartyError> {
+        self.invites
+            .remove(&id)
+            .map(|invite| invite.from)
+            .ok_or(PartyError::NoInvite)
+    }
+
+    /// Leave the party. The next member to have joined takes over if the
+    /// leader leaves, and a party of one disbands.
+    pub fn leave(&mut self, id: PlayerId) -> Result<(), PartyError> {
+        let party_id = self.membership.remove(&id).ok_or(PartyError::NotInParty)?;
+        let party = self
+            .parties
+            .get_mut(&party_id)
+            .expect("members have a party");
+        party.members.retain(|&member| member != id);
+
+        if party.members.len() < 2 {
+            for member in &party.members {
+                self.membership.remove(member);
+            }
+            self.parties.remove(&party_id);
+        } else if party.leader == id {
+            party.leader = party.members[0];
+        }
+        Ok(())
+    }
+
+    /// Remove `target` from the leader's party
+    pub fn kick(&mut self, leader: PlayerId, target: PlayerId) -> Result<(), PartyError> {
+        self.check_leader_target(leader, target)?;
+        self.leave(target)
+    }
+
+    /// Hand leadership to another member
+    pub fn promote(&mut self, leader: PlayerId, target: PlayerId) -> Result<(), PartyError> {
+        let party_id = self.check_leader_target(leader, target)?;
+        self.parties
+            .get_mut(&party_id)
+            .expect("members have a party")
+            .leader = target;
+        Ok(())
+    }
+
+    fn check_leader_target(
+        &self,
+        leader: PlayerId,
+        target: PlayerId,
+    ) -> Result<PartyId, PartyError> {
+        let party = self.party_of(leader).ok_or(PartyError::NotInParty)?;
+        if party.leader != leader {
+            return Err(PartyError::NotLeader);
+        }
+        if target == leader {
+            return Err(PartyError::CannotTargetSelf);
+        }
+        if !party.members.contains(&target) {
+            return Err(PartyError::NotAMember);
+        }
+        Ok(party.id)
+    }
+
+    /// Clean up after a player who left the server
+    pub fn forget(&mut self, id: PlayerId) {
+        let _ = self.leave(id);
+        self.invites
+            .retain(|&invitee, invite| invitee != id && invite.from != id);
+    }
+
+    /// Split XP `earner` gained between the party members for whom
+    /// `eligible` holds (e.g. those nearby), with a bonus per extra member.
+    /// Any remainder goes to the earner.
+    pub fn share_xp(
+        &self,
+        earner: PlayerId,
+        amount: u32,
+        eligible: impl Fn(PlayerId) -> bool,
+    ) -> Vec<(PlayerId, u32)> {
+        let mut sharing: Vec<PlayerId> = self
+            .party_of(earner)
+            .map(|party| {
+                party
+                    .members
+                    .iter()
+                    .copied()
+                    .filter(|&member| member == earner || eligible(member))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if sharing.len() < 2 {
+            return vec![(earner, amount)];
+        }
+
+        let extra_members = sharing.len() as u64 - 1;
+        let total =
+            u64::from(amount) * (100 + u64::from(PARTY_XP_BONUS_PERCENT) * extra_members) / 100;
+        let share = total / sharing.len() as u64;
+        let remainder = total % sharing.len() as u64;
+
+        // Earner first so they're the one who gets the remainder
+        sharing.sort_by_key(|&member| member != earner);
+        sharing
+            .into_iter()
+            .map(|member| {
+                let bonus = if member == earner { remainder } else { 0 };
+                (member, u32::try_from(share + bonus).unwrap_or(u32::MAX))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Party of `1` (leader) and `2`
+    fn pair() -> PartyManager {
+        let mut parties = PartyManager::new();
+        parties.invite(1, 2, "Two", 0.0).unwrap();
+        parties.accept(2, 0.0).unwrap();
+        parties
+    }
+
+    #[test]
+    fn test_invite_and_accept_forms_party() {
+        let mut parties = pair();
+        let party = parties.party_of(2).unwrap();
+        assert_eq!(party.leader, 1);
+        assert_eq!(party.members, [1, 2]);
+
+        parties.invite(1, 3, "Three", 0.0).unwrap();
+        assert_eq!(parties.pending_invite(3, 10.0), Some(1));
+        let joined = parties.accept(3, 10.0).unwrap();
+        assert_eq!(parties.party_of(1).unwrap().id, joined);
+        assert_eq!(parties.party_of(3).unwrap().members, [1, 2, 3]);
+        assert_eq!(parties.parties().count(), 1);
+    }
+
+    #[test]
+    fn test_invite_rules() {
+        let mut parties = pair();
+        assert_eq!(
+            parties.invite(2, 3, "Three", 0.0),
+            Err(PartyError::NotLeader)
+        );
+        assert_eq!(
+            parties.invite(3, 2, "Two", 0.0),
+            Err(PartyError::TargetInParty("Two".to_string()))
+        );
+        assert_eq!(
+            parties.invite(3, 3, "Three", 0.0),
+            Err(PartyError::CannotTargetSelf)
+        );
+
+        for id in 3..=MAX_PARTY_SIZE as PlayerId {
+            parties.invite(1, id, "", 0.0).unwrap();
+            parties.accept(id, 0.0).unwrap();
+        }
+        assert_eq!(parties.invite(1, 99, "", 0.0), Err(PartyError::PartyFull));
+    }
+
+    #[test]
+    fn test_invites_expire_and_can_be_declined() {
+        let mut parties = PartyManager::new();
+        parties.invite(1, 2, "Two", 0.0).unwrap();
+        assert_eq!(
+            parties.accept(2, PARTY_INVITE_TIMEOUT_MS),
+            Err(PartyError::NoInvite)
+        );
+
+        parties.invite(1, 2, "Two", 0.0).unwrap();
+        assert_eq!(parties.decline(2), Ok(1));
+        assert_eq!(parties.accept(2, 0.0), Err(PartyError::NoInvite));
+        assert_eq!(parties.decline(2), Err(PartyError::NoInvite));
+    }
+
+    #[test]
+    fn test_leader_leaving_promotes_next_member() {
+        let mut parties = pair();
+        parties.invite(1, 3, "Three", 0.0).unwrap();
+        parties.accept(3, 0.0).unwrap();
+
+        parties.leave(1).unwrap();
+        let party = parties.party_of(2).unwrap();
+        assert_eq!(party.leader, 2);
+        assert_eq!(party.members, [2, 3]);
+        assert!(parties.party_of(1).is_none());
+
+        // Down to one member, the party disbands
+        parties.leave(3).unwrap();
+        assert!(parties.party_of(2).is_none());
+        assert_eq!(parties.parties().count(), 0);
+        assert_eq!(parties.leave(2), Err(PartyError::NotInParty));
+    }
+
+    #[test]
+    fn test_kick_and_promote_need_leader() {
+        let mut parties = pair();
+        parties.invite(1, 3, "Three", 0.0).unwrap();
+        parties.accept(3, 0.0).unwrap();
+
+        assert_eq!(parties.kick(2, 3), Err(PartyError::NotLeader));
+        assert_eq!(parties.kick(1, 1), Err(PartyError::CannotTargetSelf));
+        assert_eq!(parties.kick(1, 9), Err(PartyError::NotAMember));
+        parties.kick(1, 3).unwrap();
+        assert!(parties.party_of(3).is_none());
+
+        parties.promote(1, 2).unwrap();
+        assert_eq!(parties.party_of(1).unwrap().leader, 2);
+        assert_eq!(parties.promote(1, 2), Err(PartyError::NotLeader));
+    }
+
+    #[test]
+    fn test_forget_clears_membership_and_invites() {
+        let mut parties = pair();
+        parties.invite(1, 3, "Three", 0.0).unwrap();
+
+        parties.forget(1);
+        assert!(parties.party_of(2).is_none());
+        assert_eq!(parties.pending_invite(3, 0.0), None);
+    }
+
+    #[test]
+    fn test_xp_is_shared_with_a_bonus() {
+        let mut parties = pair();
+        parties.invite(1, 3, "Three", 0.0).unwrap();
+        parties.accept(3, 0.0).unwrap();
+
+        // 100 XP with two extra members is 120, split three ways
+        assert_eq!(
+            parties.share_xp(2, 100, |_| true),
+            [(2, 40), (1, 40), (3, 40)]
+        );
+        // Members out of range don't share; the remainder goes to the earner
+        assert_eq!(parties.share_xp(3, 101, |id| id != 2), [(3, 56), (1, 55)]);
+        assert_eq!(parties.share_xp(1, 100, |_| false), [(1, 100)]);
+        assert_eq!(parties.share_xp(9, 100, |_| true), [(9, 100)]);
+    }
+}
@@ -0,0 +1,416 @@
+use crate::game_state::GameState;
+use crate::protocol::{PlayerId, PlayerInput, WorldSnapshot};
+use std::collections::VecDeque;
+
+/// Furthest a single input may move a player on either axis, matching the
+/// client's `InputHandler` speed
+pub const MAX_INPUT_STEP: f64 = 5.0;
+
+/// Inputs a moving client sends per second, whatever its frame rate
+pub const INPUT_RATE: f64 = 60.0;
+
+/// Most inputs one frame may catch up on after a stall
+pub const MAX_CATCH_UP_INPUTS: u32 = 4;
+
+/// Fastest a player may move, in units per second: one full step per
+/// input at `INPUT_RATE`. The server spends inputs against this per tick
+/// so flooding it with inputs doesn't make anyone faster.
+pub const MAX_MOVE_SPEED: f64 = MAX_INPUT_STEP * INPUT_RATE;
+
+/// Unacknowledged inputs kept for replay before the oldest are dropped
+pub const MAX_INPUT_HISTORY: usize = 128;
+
+/// Apply one movement input. The server and the predicting client both
+/// go through here so their results match exactly.
+pub fn apply_input(state: &mut GameState, input: &PlayerInput) {
+    state.move_player(clamp_step(input.dx), clamp_step(input.dy));
+}
+
+/// How much movement an input uses up: its longest axis after clamping
+pub fn input_distance(input: &PlayerInput) -> f64 {
+    clamp_step(input.dx).abs().max(clamp_step(input.dy).abs())
+}
+
+fn clamp_step(delta: f64) -> f64 {
+    if delta.is_finite() {
+        delta.clamp(-MAX_INPUT_STEP, MAX_INPUT_STEP)
+    } else {
+        0.0
+    }
+}
+
+/// Client-side prediction: movement is applied locally straight away and
+/// kept until the server acknowledges it, then corrected against each
+/// authoritative snapshot by rewinding and replaying what's still in flight
+#[derive(Debug, Clone)]
+pub struct Predictor {
+    next_seq: u32,
+    history: VecDeque<PlayerInput>,
+    player_id: Option<PlayerId>,
+    last_tick: Option<u64>,
+    /// Time owed towards the next input, so inputs go out at `INPUT_RATE`
+    /// on any display
+    input_clock: f64,
+}
+
+impl Default for Predictor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Predictor {
+    pub fn new() -> Self {
+        Self {
+            next_seq: 1,
+            history: VecDeque::new(),
+            player_id: None,
+            last_tick: None,
+            input_clock: 1.0 / INPUT_RATE,
+        }
+    }
+
+    /// Forget everything, e.g. when joining a new session
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Inputs sent but not yet acknowledged by the server
+    pub fn pending(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Advance one rendered frame of `elapsed_secs`, moving by (dx, dy) per
+    /// input, and return the inputs to send. Inputs are paced at
+    /// `INPUT_RATE`, so a fast display sends no more than a 60 Hz one.
+    pub fn predict_frame(
+        &mut self,
+        state: &mut GameState,
+        elapsed_secs: f64,
+        dx: f64,
+        dy: f64,
+    ) -> Vec<PlayerInput> {
+        let interval = 1.0 / INPUT_RATE;
+        if dx == 0.0 && dy == 0.0 {
+            // Ready to step as soon as movement starts
+            self.input_clock = interval;
+            return Vec::new();
+        }
+        self.input_clock =
+            (self.input_clock + elapsed_secs.max(0.0)).min(interval * MAX_CATCH_UP_INPUTS as f64);
+        let mut inputs = Vec::new();
+        while self.input_clock >= interval {
+            self.input_clock -= interval;
+            inputs.push(self.predict(state, dx, dy));
+        }
+        inputs
+    }
+
+    /// Apply a movement locally and return the numbered input to send
+    pub fn predict(&mut self, state: &mut GameState, dx: f64, dy: f64) -> PlayerInput {
+        let input = PlayerInput {
+            seq: self.next_seq,
+            dx,
+            dy,
+        };
+        self.next_seq = self.next_seq.wrapping_add(1).max(1);
+
+        apply_input(state, &input);
+        if self.history.len() >= MAX_INPUT_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(input.clone());
+        input
+    }
+
+    /// Snap to the server's position for `player_id` and replay the inputs
+    /// it hasn't processed yet. Returns how far the prediction was off, or
+    /// `None` if the snapshot was stale or didn't include the player.
+    pub fn reconcile(
+        &mut self,
+        state: &mut GameState,
+        player_id: PlayerId,
+        snapshot: &WorldSnapshot,
+    ) -> Option<f64> {
+        match self.player_id {
+            Some(id) if id == player_id => {}
+            // A new session numbers its inputs from scratch
+            Some(_) => {
+                self.reset();
+                self.player_id = Some(player_id);
+            }
+            None => self.player_id = Some(player_id),
+        }
+        if self.last_tick.is_some_and(|tick| snapshot.tick <= tick) {
+            return None;
+        }
+        let server = snapshot.players.iter().find(|p| p.id == player_id)?;
+        self.last_tick = Some(snapshot.tick);
+
+        let acknowledged = snapshot.last_processed_input;
+        self.history.retain(|input| input.seq > acknowledged);
+
+        let predicted = (state.player_x, state.player_y);
+        state.set_player_position(server.x, server.y);
+        for input in &self.history {
+            apply_input(state, input);
+        }
+        Some((state.player_x - predicted.0).hypot(state.player_y - predicted.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Region;
+    use crate::profile::{PlayerClass, PlayerProfile};
+    use crate::protocol::{ClientMessage, PlayerSnapshot};
+    use crate::server::{ServerConfig, ServerWorld};
+
+    /// One direction of a network link: delivers after a fixed number of
+    /// ticks and drops a share of messages, deterministically
+    struct SimulatedLink<T> {
+        delay_ticks: u64,
+        loss_percent: u64,
+        rng: u64,
+        in_flight: VecDeque<(u64, T)>,
+    }
+
+    impl<T> SimulatedLink<T> {
+        fn new(delay_ticks: u64, loss_percent: u64, seed: u64) -> Self {
+            Self {
+                delay_ticks,
+                loss_percent,
+                rng: seed,
+                in_flight: VecDeque::new(),
+            }
+        }
+
+        fn send(&mut self, now: u64, message: T) {
+            // 64-bit LCG, good enough to spread drops around
+            self.rng = self
+                .rng
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            if (self.rng >> 33) % 100 < self.loss_percent {
+                return;
+            }
+            self.in_flight.push_back((now + self.delay_ticks, message));
+        }
+
+        fn receive(&mut self, now: u64) -> Vec<T> {
+            let mut delivered = Vec::new();
+            while self.in_flight.front().is_some_and(|(at, _)| *at <= now) {
+                delivered.push(self.in_flight.pop_front().unwrap().1);
+            }
+            delivered
+        }
+    }
+
+    /// A client and server joined by simulated links
+    struct Harness {
+        server: ServerWorld,
+        client: GameState,
+        predictor: Predictor,
+        player_id: PlayerId,
+        uplink: SimulatedLink<PlayerInput>,
+        downlink: SimulatedLink<WorldSnapshot>,
+        corrections: Vec<f64>,
+        tick: u64,
+    }
+
+    impl Harness {
+        fn new(delay_ticks: u64, loss_percent: u64) -> Self {
+            let config = ServerConfig::default();
+            let mut server = ServerWorld::new(config.clone());
+            let profile = PlayerProfile::new("Hero", PlayerClass::Ranger, 0).unwrap();
+            let player_id = server.join(profile, Region::EU).unwrap();
+            Self {
+                server,
+                client: GameState::new_in_world(config.world_width, config.world_height),
+                predictor: Predictor::new(),
+                player_id,
+                uplink: SimulatedLink::new(delay_ticks, loss_percent, 7),
+                downlink: SimulatedLink::new(delay_ticks, loss_percent, 11),
+                corrections: Vec::new(),
+                tick: 0,
+            }
+        }
+
+        fn run(&mut self, ticks: u64, dx: f64, dy: f64) {
+            for _ in 0..ticks {
+                self.receive_snapshots();
+                if dx != 0.0 || dy != 0.0 {
+                    let input = self.predictor.predict(&mut self.client, dx, dy);
+                    self.uplink.send(self.tick, input);
+                }
+                self.step_server();
+            }
+        }
+
+        /// Run with the client rendering at `refresh_hz` and pacing its
+        /// inputs the way the game loop does
+        fn run_display(&mut self, ticks: u64, refresh_hz: f64, dx: f64, dy: f64) {
+            let frame_secs = 1.0 / refresh_hz;
+            let tick_secs = self.server.tick_duration_secs();
+            let mut frame_clock = 0.0;
+            for _ in 0..ticks {
+                self.receive_snapshots();
+                frame_clock += tick_secs;
+                while frame_clock >= frame_secs {
+                    frame_clock -= frame_secs;
+                    for input in self
+                        .predictor
+                        .predict_frame(&mut self.client, frame_secs, dx, dy)
+                    {
+                        self.uplink.send(self.tick, input);
+                    }
+                }
+                self.step_server();
+            }
+        }
+
+        fn receive_snapshots(&mut self) {
+            self.tick += 1;
+            for snapshot in self.downlink.receive(self.tick) {
+                if let Some(correction) =
+                    self.predictor
+                        .reconcile(&mut self.client, self.player_id, &snapshot)
+                {
+                    self.corrections.push(correction);
+                }
+            }
+        }
+
+        fn step_server(&mut self) {
+            for input in self.uplink.receive(self.tick) {
+                self.server
+                    .handle(self.player_id, ClientMessage::Input(input));
+            }
+            self.server.step();
+            let snapshot = self.server.snapshot_for(self.player_id).unwrap();
+            self.downlink.send(self.tick, snapshot);
+        }
+
+        fn server_position(&self) -> (f64, f64) {
+            let snapshot = self.server.snapshot_for(self.player_id).unwrap();
+            let me = &snapshot.players[0];
+            (me.x, me.y)
+        }
+
+        fn client_position(&self) -> (f64, f64) {
+            (self.client.player_x, self.client.player_y)
+        }
+
+        /// Walk a route that ends against the world's edge
+        fn walk_route(&mut self) {
+            self.run(40, 5.0, 0.0);
+            self.run(30, 0.0, 5.0);
+            self.run(100, -5.0, -3.0);
+        }
+    }
+
+    #[test]
+    fn test_prediction_moves_immediately() {
+        let mut harness = Harness::new(5, 0);
+        let start = harness.client_position();
+
+        harness.run(1, 5.0, 0.0);
+        assert_eq!(harness.client_position(), (start.0 + 5.0, start.1));
+        assert_eq!(harness.server_position(), start);
+        assert_eq!(harness.predictor.pending(), 1);
+    }
+
+    #[test]
+    fn test_latency_without_loss_never_corrects() {
+        let mut harness = Harness::new(6, 0);
+        harness.walk_route();
+        harness.run(20, 0.0, 0.0);
+
+        assert!(!harness.corrections.is_empty());
+        assert!(harness.corrections.iter().all(|&c| c < 1e-9));
+        assert_eq!(harness.client_position(), harness.server_position());
+        assert_eq!(harness.predictor.pending(), 0);
+    }
+
+    #[test]
+    fn test_fast_displays_send_inputs_at_the_same_rate() {
+        for refresh_hz in [60.0, 144.0, 240.0] {
+            let mut harness = Harness::new(6, 0);
+            let start = harness.client_position();
+            harness.run_display(20, refresh_hz, 5.0, 0.0);
+            harness.run_display(20, refresh_hz, 0.0, 0.0);
+
+            // A second of walking covers a second's worth of ground, and the
+            // server keeps up without a single correction
+            let walked = harness.client_position().0 - start.0;
+            assert!((walked - MAX_MOVE_SPEED).abs() <= MAX_INPUT_STEP);
+            assert!(harness.corrections.iter().all(|&c| c < 1e-9));
+            assert_eq!(harness.client_position(), harness.server_position());
+            assert_eq!(harness.predictor.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn test_packet_loss_converges_to_server() {
+        let mut harness = Harness::new(4, 25);
+        harness.walk_route();
+
+        // Lost inputs make the client run ahead of the server for a while
+        assert!(harness.corrections.iter().any(|&c| c > 0.0));
+
+        // Once movement stops, the next snapshots that get through settle it
+        harness.run(40, 0.0, 0.0);
+        assert_eq!(harness.client_position(), harness.server_position());
+    }
+
+    #[test]
+    fn test_stale_and_foreign_snapshots_are_ignored() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        let mut predictor = Predictor::new();
+        predictor.predict(&mut state, 5.0, 0.0);
+
+        let snapshot = |tick, x| WorldSnapshot {
+            tick,
+            last_processed_input: 0,
+            players: vec![PlayerSnapshot {
+                id: 1,
+                name: "Hero".to_string(),
+                x,
+                y: 300.0,
+            }],
+        };
+
+        assert_eq!(
+            predictor.reconcile(&mut state, 1, &snapshot(5, 400.0)),
+            Some(0.0)
+        );
+        assert_eq!(
+            predictor.reconcile(&mut state, 1, &snapshot(4, 100.0)),
+            None
+        );
+        assert_eq!(state.player_x, 405.0);
+        assert_eq!(
+            predictor.reconcile(&mut state, 2, &snapshot(6, 100.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_inputs_are_clamped_like_the_server() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        let start = (state.player_x, state.player_y);
+        apply_input(
+            &mut state,
+            &PlayerInput {
+                seq: 1,
+                dx: -90.0,
+                dy: f64::INFINITY,
+            },
+        );
+        assert_eq!(
+            (state.player_x, state.player_y),
+            (start.0 - MAX_INPUT_STEP, start.1)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Shortest allowed player name, in characters
+pub const MIN_NAME_LENGTH: usize = 3;
+
+/// Longest allowed player name, in characters
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// Number of appearance presets offered by the character creator
+pub const APPEARANCE_PRESETS: u8 = 8;
+
+/// Names players can't take because they'd impersonate staff or the game
+const RESERVED_NAMES: &[&str] = &["admin", "gm", "moderator", "server", "system"];
+
+/// Character class picked at creation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub enum PlayerClass {
+    #[default]
+    Warrior,
+    Mage,
+    Ranger,
+}
+
+/// Reasons a player name is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum NameError {
+    Empty,
+    TooShort { min: usize },
+    TooLong { max: usize },
+    InvalidCharacter(char),
+    Reserved(String),
+}
+
+impl NameError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            NameError::Empty => "name_empty",
+            NameError::TooShort { .. } => "name_too_short",
+            NameError::TooLong { .. } => "name_too_long",
+            NameError::InvalidCharacter(_) => "name_invalid_character",
+            NameError::Reserved(_) => "name_reserved",
+        }
+    }
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "Enter a name"),
+            NameError::TooShort { min } => write!(f, "Names need at least {min} characters"),
+            NameError::TooLong { max } => write!(f, "Names can have at most {max} characters"),
+            NameError::InvalidCharacter(c) => write!(f, "Names can't contain {c:?}"),
+            NameError::Reserved(name) => write!(f, "The name {name} is reserved"),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// Reasons a profile is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileError {
+    Name(NameError),
+    UnknownAppearance(u8),
+}
+
+impl ProfileError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProfileError::Name(e) => e.code(),
+            ProfileError::UnknownAppearance(_) => "unknown_appearance",
+        }
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Name(e) => e.fmt(f),
+            ProfileError::UnknownAppearance(preset) => {
+                write!(f, "Appearance {preset} doesn't exist")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<NameError> for ProfileError {
+    fn from(e: NameError) -> Self {
+        ProfileError::Name(e)
+    }
+}
+
+/// Trim a raw name and check it against the naming rules, returning the
+/// name as it should be stored
+pub fn validate_name(raw: &str) -> Result<String, NameError> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+
+    // Check characters first so a 10KB paste of garbage reports the real problem.
+    // ASCII only, so look-alikes such as a Cyrillic "а" can't pass for
+    // someone else's name.
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, ' ' | '_' | '-')))
+    {
+        return Err(NameError::InvalidCharacter(c));
+    }
+
+    let length = name.chars().count();
+    if length < MIN_NAME_LENGTH {
+        return Err(NameError::TooShort {
+            min: MIN_NAME_LENGTH,
+        });
+    }
+    if length > MAX_NAME_LENGTH {
+        return Err(NameError::TooLong {
+            max: MAX_NAME_LENGTH,
+        });
+    }
+
+    let folded = name.to_lowercase();
+    if RESERVED_NAMES.contains(&folded.as_str()) {
+        return Err(NameError::Reserved(name.to_string()));
+    }
+    Ok(name.to_string())
+}
+
+/// Who the player is, as chosen during character creation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct PlayerProfile {
+    pub name: String,
+    pub class: PlayerClass,
+    /// Index of the character creator's appearance preset
+    pub appearance: u8,
+}
+
+impl PlayerProfile {
+    /// Build a profile, validating and normalizing every field
+    pub fn new(name: &str, class: PlayerClass, appearance: u8) -> Result<Self, ProfileError> {
+        let profile = Self {
+            name: name.to_string(),
+            class,
+            appearance,
+        };
+        profile.validated()
+    }
+
+    /// Check a profile that came from outside, e.g. the frontend
+    pub fn validated(self) -> Result<Self, ProfileError> {
+        let name = validate_name(&self.name)?;
+        if self.appearance >= APPEARANCE_PRESETS {
+            return Err(ProfileError::UnknownAppearance(self.appearance));
+        }
+        Ok(Self { name, ..self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_trimmed() {
+        assert_eq!(validate_name("  Hero  "), Ok("Hero".to_string()));
+        assert_eq!(validate_name("Nguyen Van"), Ok("Nguyen Van".to_string()));
+        assert_eq!(validate_name("dark_elf-2"), Ok("dark_elf-2".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        assert_eq!(validate_name(""), Err(NameError::Empty));
+        assert_eq!(validate_name(" \t "), Err(NameError::Empty));
+        assert_eq!(validate_name("Al"), Err(NameError::TooShort { min: 3 }));
+        assert_eq!(
+            validate_name(&"a".repeat(10_000)),
+            Err(NameError::TooLong { max: 16 })
+        );
+        assert_eq!(
+            validate_name("Bad\u{7}Bell"),
+            Err(NameError::InvalidCharacter('\u{7}'))
+        );
+        assert_eq!(
+            validate_name("<script>"),
+            Err(NameError::InvalidCharacter('<'))
+        );
+        assert_eq!(
+            validate_name(" ADMIN "),
+            Err(NameError::Reserved("ADMIN".to_string()))
+        );
+        // Look-alike letters from other scripts
+        assert_eq!(
+            validate_name("\u{430}dmin"),
+            Err(NameError::InvalidCharacter('\u{430}'))
+        );
+        assert_eq!(
+            validate_name("Nguyễn"),
+            Err(NameError::InvalidCharacter('ễ'))
+        );
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let profile = PlayerProfile::new(" Hero ", PlayerClass::Mage, 3).unwrap();
+        assert_eq!(profile.name, "Hero");
+        assert_eq!(profile.class, PlayerClass::Mage);
+
+        assert_eq!(
+            PlayerProfile::new("Hero", PlayerClass::Mage, APPEARANCE_PRESETS),
+            Err(ProfileError::UnknownAppearance(APPEARANCE_PRESETS))
+        );
+        assert_eq!(
+            PlayerProfile::new("", PlayerClass::Mage, 0)
+                .unwrap_err()
+                .code(),
+            "name_empty"
+        );
+    }
+}
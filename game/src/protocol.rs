@@ -0,0 +1,616 @@
+use crate::chat::ChatChannel;
+use crate::crafting::CraftingStation;
+use crate::game_state::{GameState, Region};
+use crate::inventory::Inventory;
+use crate::party::{PartyAction, PartyView};
+use crate::profile::PlayerProfile;
+use crate::trade::{TradeAction, TradeOffer, TradeView};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Version of the wire format, bump whenever a message changes shape.
+/// Client and server refuse frames from other versions.
+pub const PROTOCOL_VERSION: u16 = 6;
+
+/// Largest frame either side accepts
+pub const MAX_FRAME_BYTES: usize = 64 * 1024;
+
+/// Identifies a connected player for the lifetime of their session
+pub type PlayerId = u32;
+
+/// Movement input for one client frame, numbered so the server can
+/// acknowledge it in snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInput {
+    pub seq: u32,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+/// Gold and items a player holds online. The server keeps the only copy
+/// that counts and sends it whenever it changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Belongings {
+    pub gold: u32,
+    pub inventory: Inventory,
+}
+
+impl Belongings {
+    pub fn of(state: &GameState) -> Self {
+        Self {
+            gold: state.gold,
+            inventory: state.inventory.clone(),
+        }
+    }
+}
+
+/// Messages sent by the client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Join {
+        profile: PlayerProfile,
+        region: Region,
+    },
+    Input(PlayerInput),
+    Chat {
+        channel: ChatChannel,
+        text: String,
+    },
+    Party(PartyAction),
+    Trade(TradeAction),
+    /// Buy from the shop; the server answers with `Belongings` or
+    /// `CommandRejected`
+    Buy {
+        item_id: String,
+        quantity: u32,
+    },
+    /// Craft a recipe; the server answers like `Buy`
+    Craft {
+        recipe_id: String,
+        station: Option<CraftingStation>,
+    },
+    /// Keep-alive; the server answers with `Pong`
+    Ping {
+        sent_at_ms: f64,
+    },
+    Leave,
+}
+
+/// One player as seen in a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub id: PlayerId,
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The authoritative world at one server tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub tick: u64,
+    /// Highest input `seq` from the receiving client the server has applied
+    pub last_processed_input: u32,
+    pub players: Vec<PlayerSnapshot>,
+}
+
+/// Why the server closed a connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DisconnectReason {
+    ServerFull,
+    VersionMismatch {
+        server: u16,
+        client: u16,
+    },
+    InvalidProfile(String),
+    /// A player with the same name is already online
+    NameTaken,
+    Kicked(String),
+    Timeout,
+    ServerShutdown,
+}
+
+impl DisconnectReason {
+    /// Whether reconnecting later might succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            DisconnectReason::ServerFull
+                | DisconnectReason::NameTaken
+                | DisconnectReason::Timeout
+                | DisconnectReason::ServerShutdown
+        )
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::ServerFull => write!(f, "The server is full"),
+            DisconnectReason::VersionMismatch { server, client } => write!(
+                f,
+                "Protocol version {client} doesn't match the server's version {server}"
+            ),
+            DisconnectReason::InvalidProfile(reason) => write!(f, "Profile rejected: {reason}"),
+            DisconnectReason::NameTaken => write!(f, "That name is already playing"),
+            DisconnectReason::Kicked(reason) => write!(f, "Kicked: {reason}"),
+            DisconnectReason::Timeout => write!(f, "Connection timed out"),
+            DisconnectReason::ServerShutdown => write!(f, "The server is shutting down"),
+        }
+    }
+}
+
+/// Messages sent by the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome {
+        player_id: PlayerId,
+        tick_rate: u16,
+    },
+    Snapshot(WorldSnapshot),
+    /// Another player came within the client's area of interest
+    EntityEntered(PlayerSnapshot),
+    /// Another player left the client's area of interest or the server
+    EntityLeft {
+        id: PlayerId,
+    },
+    Chat {
+        from: PlayerId,
+        name: String,
+        channel: ChatChannel,
+        text: String,
+    },
+    /// The client's last chat message wasn't delivered
+    ChatRejected {
+        code: String,
+        message: String,
+    },
+    /// Another player invited the client to a party
+    PartyInvite {
+        from: PlayerId,
+        name: String,
+    },
+    /// The client's party changed, or `None` if they're no longer in one
+    PartyUpdate(Option<PartyView>),
+    /// The client's last party action was refused
+    PartyRejected {
+        code: String,
+        message: String,
+    },
+    XpGained {
+        amount: u32,
+    },
+    /// Another player asked the client to trade
+    TradeRequest {
+        from: PlayerId,
+        name: String,
+    },
+    /// The client's trade changed, or `None` if it ended
+    TradeUpdate(Option<TradeView>),
+    /// The client's last trade action was refused
+    TradeRejected {
+        code: String,
+        message: String,
+    },
+    /// A trade went through; `Belongings` follows with the result
+    TradeCompleted {
+        gave: TradeOffer,
+        received: TradeOffer,
+    },
+    /// The client's gold and items, sent on joining and after every change
+    Belongings(Belongings),
+    /// The client's last purchase or craft was refused
+    CommandRejected {
+        code: String,
+        message: String,
+    },
+    Pong {
+        sent_at_ms: f64,
+    },
+    Disconnect(DisconnectReason),
+}
+
+/// How frames are encoded on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Human-readable, for debugging and browser dev tools
+    Json,
+    /// Compact postcard encoding for normal play
+    Binary,
+}
+
+/// Reasons a frame can't be encoded or decoded
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolError {
+    Malformed(String),
+    VersionMismatch { expected: u16, found: u16 },
+    TooLarge(usize),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Malformed(reason) => write!(f, "Malformed frame: {reason}"),
+            ProtocolError::VersionMismatch { expected, found } => {
+                write!(f, "Frame has protocol version {found}, expected {expected}")
+            }
+            ProtocolError::TooLarge(size) => {
+                write!(f, "Frame is {size} bytes, the limit is {MAX_FRAME_BYTES}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// JSON frames carry the version next to the message
+#[derive(Serialize)]
+struct JsonFrame<'a, T> {
+    version: u16,
+    message: &'a T,
+}
+
+/// Encode a message with the protocol version in front of it
+pub fn encode<T: Serialize>(message: &T, encoding: Encoding) -> Result<Vec<u8>, ProtocolError> {
+    let bytes = match encoding {
+        Encoding::Json => serde_json::to_vec(&JsonFrame {
+            version: PROTOCOL_VERSION,
+            message,
+        })
+        .map_err(|e| ProtocolError::Malformed(e.to_string()))?,
+        Encoding::Binary => {
+            let mut bytes = PROTOCOL_VERSION.to_le_bytes().to_vec();
+            postcard::to_io(message, &mut bytes)
+                .map_err(|e| ProtocolError::Malformed(e.to_string()))?;
+            bytes
+        }
+    };
+
+    if bytes.len() > MAX_FRAME_BYTES {
+        return Err(ProtocolError::TooLarge(bytes.len()));
+    }
+    Ok(bytes)
+}
+
+/// Decode a frame, checking its version before looking at the message
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], encoding: Encoding) -> Result<T, ProtocolError> {
+    if bytes.len() > MAX_FRAME_BYTES {
+        return Err(ProtocolError::TooLarge(bytes.len()));
+    }
+
+    match encoding {
+        Encoding::Json => {
+            let mut frame: serde_json::Value = serde_json::from_slice(bytes)
+                .map_err(|e| ProtocolError::Malformed(e.to_string()))?;
+            let version = frame
+                .get("version")
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| ProtocolError::Malformed("missing `version`".to_string()))?;
+            check_version(u16::try_from(version).unwrap_or(u16::MAX))?;
+            serde_json::from_value(frame["message"].take())
+                .map_err(|e| ProtocolError::Malformed(e.to_string()))
+        }
+        Encoding::Binary => {
+            let (header, payload) = bytes
+                .split_first_chunk::<2>()
+                .ok_or_else(|| ProtocolError::Malformed("missing version header".to_string()))?;
+            check_version(u16::from_le_bytes(*header))?;
+            postcard::from_bytes(payload).map_err(|e| ProtocolError::Malformed(e.to_string()))
+        }
+    }
+}
+
+fn check_version(found: u16) -> Result<(), ProtocolError> {
+    if found != PROTOCOL_VERSION {
+        return Err(ProtocolError::VersionMismatch {
+            expected: PROTOCOL_VERSION,
+            found,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemStack;
+    use crate::party::PartyMember;
+    use crate::profile::PlayerClass;
+    use crate::trade::TradeSideView;
+    use proptest::prelude::*;
+
+    fn region() -> impl Strategy<Value = Region> {
+        prop_oneof![Just(Region::EU), Just(Region::Asia), Just(Region::Vietnam)]
+    }
+
+    fn profile() -> impl Strategy<Value = PlayerProfile> {
+        (
+            "[a-zA-Z0-9]{3,16}",
+            prop_oneof![
+                Just(PlayerClass::Warrior),
+                Just(PlayerClass::Mage),
+                Just(PlayerClass::Ranger)
+            ],
+            any::<u8>(),
+        )
+            .prop_map(|(name, class, appearance)| PlayerProfile {
+                name,
+                class,
+                appearance,
+            })
+    }
+
+    fn chat_channel() -> impl Strategy<Value = ChatChannel> {
+        prop_oneof![
+            Just(ChatChannel::Local),
+            Just(ChatChannel::Zone),
+            Just(ChatChannel::Global),
+            Just(ChatChannel::Party),
+            "[a-zA-Z0-9]{3,16}".prop_map(|to| ChatChannel::Whisper { to }),
+        ]
+    }
+
+    fn party_action() -> impl Strategy<Value = PartyAction> {
+        prop_oneof![
+            "[a-zA-Z0-9]{3,16}".prop_map(|name| PartyAction::Invite { name }),
+            Just(PartyAction::Accept),
+            Just(PartyAction::Decline),
+            Just(PartyAction::Leave),
+            any::<u32>().prop_map(|id| PartyAction::Kick { id }),
+            any::<u32>().prop_map(|id| PartyAction::Promote { id }),
+        ]
+    }
+
+    fn crafting_station() -> impl Strategy<Value = CraftingStation> {
+        prop_oneof![
+            Just(CraftingStation::Workbench),
+            Just(CraftingStation::Forge),
+            Just(CraftingStation::Anvil),
+        ]
+    }
+
+    fn trade_offer() -> impl Strategy<Value = TradeOffer> {
+        (
+            prop::collection::vec(("[a-z_]{1,20}", any::<u32>()), 0..8),
+            any::<u32>(),
+        )
+            .prop_map(|(items, gold)| TradeOffer {
+                items: items
+                    .into_iter()
+                    .map(|(item_id, quantity)| ItemStack { item_id, quantity })
+                    .collect(),
+                gold,
+            })
+    }
+
+    fn trade_action() -> impl Strategy<Value = TradeAction> {
+        prop_oneof![
+            "[a-zA-Z0-9]{3,16}".prop_map(|name| TradeAction::Request { name }),
+            Just(TradeAction::Accept),
+            Just(TradeAction::Decline),
+            trade_offer().prop_map(TradeAction::Offer),
+            Just(TradeAction::Lock),
+            Just(TradeAction::Unlock),
+            Just(TradeAction::Confirm),
+            Just(TradeAction::Cancel),
+        ]
+    }
+
+    fn trade_side_view() -> impl Strategy<Value = TradeSideView> {
+        (trade_offer(), any::<bool>(), any::<bool>()).prop_map(|(offer, locked, confirmed)| {
+            TradeSideView {
+                offer,
+                locked,
+                confirmed,
+            }
+        })
+    }
+
+    fn trade_view() -> impl Strategy<Value = TradeView> {
+        (
+            any::<u32>(),
+            "[a-zA-Z]{3,16}",
+            trade_side_view(),
+            trade_side_view(),
+        )
+            .prop_map(|(partner_id, partner_name, mine, theirs)| TradeView {
+                partner_id,
+                partner_name,
+                mine,
+                theirs,
+            })
+    }
+
+    fn party_view() -> impl Strategy<Value = PartyView> {
+        let member = (
+            any::<u32>(),
+            "[a-zA-Z]{3,16}",
+            1..100u32,
+            0.0..1e4f64,
+            0.0..1e4f64,
+        )
+            .prop_map(|(id, name, level, hp, max_hp)| PartyMember {
+                id,
+                name,
+                level,
+                hp,
+                max_hp,
+            });
+        (any::<u32>(), prop::collection::vec(member, 2..6))
+            .prop_map(|(leader, members)| PartyView { leader, members })
+    }
+
+    fn client_message() -> impl Strategy<Value = ClientMessage> {
+        prop_oneof![
+            (profile(), region())
+                .prop_map(|(profile, region)| ClientMessage::Join { profile, region }),
+            (any::<u32>(), -10.0..10.0f64, -10.0..10.0f64)
+                .prop_map(|(seq, dx, dy)| ClientMessage::Input(PlayerInput { seq, dx, dy })),
+            (chat_channel(), ".{0,200}")
+                .prop_map(|(channel, text)| ClientMessage::Chat { channel, text }),
+            party_action().prop_map(ClientMessage::Party),
+            trade_action().prop_map(ClientMessage::Trade),
+            ("[a-z_]{1,20}", any::<u32>())
+                .prop_map(|(item_id, quantity)| ClientMessage::Buy { item_id, quantity }),
+            ("[a-z_]{1,20}", prop::option::of(crafting_station()))
+                .prop_map(|(recipe_id, station)| ClientMessage::Craft { recipe_id, station }),
+            (0.0..1e12f64).prop_map(|sent_at_ms| ClientMessage::Ping { sent_at_ms }),
+            Just(ClientMessage::Leave),
+        ]
+    }
+
+    fn disconnect_reason() -> impl Strategy<Value = DisconnectReason> {
+        prop_oneof![
+            Just(DisconnectReason::ServerFull),
+            (any::<u16>(), any::<u16>())
+                .prop_map(|(server, client)| DisconnectReason::VersionMismatch { server, client }),
+            ".{0,50}".prop_map(DisconnectReason::InvalidProfile),
+            ".{0,50}".prop_map(DisconnectReason::Kicked),
+            Just(DisconnectReason::NameTaken),
+            Just(DisconnectReason::Timeout),
+            Just(DisconnectReason::ServerShutdown),
+        ]
+    }
+
+    fn server_message() -> impl Strategy<Value = ServerMessage> {
+        let player = (any::<u32>(), "[a-zA-Z]{3,16}", -1e4..1e4f64, -1e4..1e4f64)
+            .prop_map(|(id, name, x, y)| PlayerSnapshot { id, name, x, y });
+        prop_oneof![
+            (any::<u32>(), any::<u16>()).prop_map(|(player_id, tick_rate)| {
+                ServerMessage::Welcome {
+                    player_id,
+                    tick_rate,
+                }
+            }),
+            (
+                any::<u64>(),
+                any::<u32>(),
+                prop::collection::vec(player.clone(), 0..20)
+            )
+                .prop_map(|(tick, last_processed_input, players)| {
+                    ServerMessage::Snapshot(WorldSnapshot {
+                        tick,
+                        last_processed_input,
+                        players,
+                    })
+                }),
+            (any::<u32>(), "[a-zA-Z]{3,16}", chat_channel(), ".{0,200}").prop_map(
+                |(from, name, channel, text)| ServerMessage::Chat {
+                    from,
+                    name,
+                    channel,
+                    text,
+                }
+            ),
+            ("[a-z_]{1,30}", ".{0,100}")
+                .prop_map(|(code, message)| ServerMessage::ChatRejected { code, message }),
+            (any::<u32>(), "[a-zA-Z]{3,16}")
+                .prop_map(|(from, name)| ServerMessage::PartyInvite { from, name }),
+            prop::option::of(party_view()).prop_map(ServerMessage::PartyUpdate),
+            ("[a-z_]{1,30}", ".{0,100}")
+                .prop_map(|(code, message)| ServerMessage::PartyRejected { code, message }),
+            any::<u32>().prop_map(|amount| ServerMessage::XpGained { amount }),
+            (any::<u32>(), "[a-zA-Z]{3,16}")
+                .prop_map(|(from, name)| ServerMessage::TradeRequest { from, name }),
+            prop::option::of(trade_view()).prop_map(ServerMessage::TradeUpdate),
+            ("[a-z_]{1,30}", ".{0,100}")
+                .prop_map(|(code, message)| ServerMessage::TradeRejected { code, message }),
+            (trade_offer(), trade_offer())
+                .prop_map(|(gave, received)| ServerMessage::TradeCompleted { gave, received }),
+            (any::<u32>(), trade_offer()).prop_map(|(gold, bag)| {
+                ServerMessage::Belongings(Belongings {
+                    gold,
+                    inventory: Inventory {
+                        capacity: 20,
+                        slots: bag.items,
+                    },
+                })
+            }),
+            ("[a-z_]{1,30}", ".{0,100}")
+                .prop_map(|(code, message)| ServerMessage::CommandRejected { code, message }),
+            player.clone().prop_map(ServerMessage::EntityEntered),
+            any::<u32>().prop_map(|id| ServerMessage::EntityLeft { id }),
+            (0.0..1e12f64).prop_map(|sent_at_ms| ServerMessage::Pong { sent_at_ms }),
+            disconnect_reason().prop_map(ServerMessage::Disconnect),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_client_messages_round_trip(message in client_message()) {
+            for encoding in [Encoding::Json, Encoding::Binary] {
+                let bytes = encode(&message, encoding).unwrap();
+                prop_assert_eq!(&decode::<ClientMessage>(&bytes, encoding).unwrap(), &message);
+            }
+        }
+
+        #[test]
+        fn test_server_messages_round_trip(message in server_message()) {
+            for encoding in [Encoding::Json, Encoding::Binary] {
+                let bytes = encode(&message, encoding).unwrap();
+                prop_assert_eq!(&decode::<ServerMessage>(&bytes, encoding).unwrap(), &message);
+            }
+        }
+
+        #[test]
+        fn test_garbage_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = decode::<ServerMessage>(&bytes, Encoding::Binary);
+            let _ = decode::<ServerMessage>(&bytes, Encoding::Json);
+        }
+    }
+
+    #[test]
+    fn test_binary_is_smaller_than_json() {
+        let message = ServerMessage::Snapshot(WorldSnapshot {
+            tick: 1200,
+            last_processed_input: 58,
+            players: vec![PlayerSnapshot {
+                id: 7,
+                name: "Hero".to_string(),
+                x: 120.0,
+                y: 80.5,
+            }],
+        });
+
+        let json = encode(&message, Encoding::Json).unwrap();
+        let binary = encode(&message, Encoding::Binary).unwrap();
+        assert!(
+            binary.len() * 2 < json.len(),
+            "{} vs {}",
+            binary.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_version_mismatch_is_detected() {
+        let mut bytes = encode(&ClientMessage::Leave, Encoding::Binary).unwrap();
+        bytes[..2].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            decode::<ClientMessage>(&bytes, Encoding::Binary),
+            Err(ProtocolError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: PROTOCOL_VERSION + 1,
+            })
+        );
+
+        let json = br#"{"version": 0, "message": "Leave"}"#;
+        assert!(matches!(
+            decode::<ClientMessage>(json, Encoding::Json),
+            Err(ProtocolError::VersionMismatch { found: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_oversized_frames_are_rejected() {
+        let message = ClientMessage::Chat {
+            channel: ChatChannel::Global,
+            text: "a".repeat(MAX_FRAME_BYTES),
+        };
+        assert!(matches!(
+            encode(&message, Encoding::Binary),
+            Err(ProtocolError::TooLarge(_))
+        ));
+    }
+}
@@ -0,0 +1,515 @@
+use crate::game_state::GameState;
+use crate::interpolation::RemoteEntity;
+use crate::sprites::{FrameRect, SpriteAnimator, SpriteAtlas, SpriteKey};
+use crate::BALL_RADIUS;
+use std::fmt;
+
+/// Radius of the circle drawn for a player while the sprite sheet loads
+pub const PLAYER_RADIUS: f64 = 15.0;
+
+/// How far above a remote player their name is drawn
+const NAME_OFFSET: f64 = 22.0;
+
+const NAME_FONT_SIZE: f64 = 12.0;
+
+pub const BACKGROUND_COLOR: Color = Color::rgb(0x1e, 0x1e, 0x1e);
+pub const BALL_COLOR: Color = Color::rgb(0x4f, 0xc3, 0xf7);
+pub const PLAYER_COLOR: Color = Color::rgb(0xff, 0x6b, 0x6b);
+pub const REMOTE_PLAYER_COLOR: Color = Color::rgb(0x81, 0xc7, 0x84);
+
+/// An sRGB colour with 8 bits per channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// The colour as a CSS string, e.g. `#1e1e1e`
+    pub fn css(&self) -> String {
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            let alpha = self.a as f64 / 255.0;
+            format!("rgba({}, {}, {}, {alpha:.3})", self.r, self.g, self.b)
+        }
+    }
+}
+
+/// One backend-agnostic drawing operation, in canvas pixels
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    /// Wipe the whole target to transparent
+    Clear,
+    FillRect {
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        color: Color,
+    },
+    FillCircle {
+        x: f64,
+        y: f64,
+        radius: f64,
+        color: Color,
+    },
+    /// Text centred on `x` with its baseline at `y`
+    Text {
+        text: String,
+        x: f64,
+        y: f64,
+        size: f64,
+        color: Color,
+    },
+    /// A frame of the sprite sheet, centred on (x, y) at its natural size
+    Sprite { frame: FrameRect, x: f64, y: f64 },
+}
+
+/// Everything to draw for one frame, in painter's order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrawList {
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: DrawCommand) {
+        self.commands.push(command);
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Reasons a backend couldn't draw a frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// No GPU adapter is available to the wgpu backend
+    NoAdapter,
+    /// The backend refused a draw call
+    Backend(String),
+}
+
+impl RenderError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            RenderError::NoAdapter => "render_no_adapter",
+            RenderError::Backend(_) => "render_failed",
+        }
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::NoAdapter => f.write_str("No graphics adapter is available"),
+            RenderError::Backend(reason) => write!(f, "Rendering failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Backends a game can draw with in the browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// The 2D canvas API, available everywhere
+    Canvas2d,
+    /// wgpu on WebGPU; needs the `wgpu` feature and a browser with WebGPU
+    WebGpu,
+}
+
+impl RenderBackend {
+    /// Parse a backend name coming from the frontend
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Canvas2d" => Some(RenderBackend::Canvas2d),
+            "WebGpu" => Some(RenderBackend::WebGpu),
+            _ => None,
+        }
+    }
+}
+
+/// Something that can put a draw list on screen (or somewhere else)
+pub trait Renderer {
+    /// Whether sprite frames can be drawn yet; until then scenes fall back
+    /// to plain shapes
+    fn sprites_ready(&self) -> bool;
+
+    /// Start loading the sprite sheet image at `url`, replacing the current one
+    fn load_sprite_sheet(&mut self, url: &str) -> Result<(), RenderError>;
+
+    fn resize(&mut self, width: f64, height: f64);
+
+    fn render(&mut self, list: &DrawList) -> Result<(), RenderError>;
+}
+
+/// Renderer that keeps every draw list it's given, for tests and tools
+/// that need to know what would have been drawn
+#[derive(Debug, Clone, Default)]
+pub struct HeadlessRenderer {
+    frames: Vec<DrawList>,
+    sprites_ready: bool,
+    width: f64,
+    height: f64,
+}
+
+impl HeadlessRenderer {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            ..Self::default()
+        }
+    }
+
+    /// Pretend the sprite sheet has (or hasn't) finished loading
+    pub fn set_sprites_ready(&mut self, ready: bool) {
+        self.sprites_ready = ready;
+    }
+
+    pub fn size(&self) -> (f64, f64) {
+        (self.width, self.height)
+    }
+
+    pub fn frames(&self) -> &[DrawList] {
+        &self.frames
+    }
+
+    pub fn last_frame(&self) -> Option<&DrawList> {
+        self.frames.last()
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn sprites_ready(&self) -> bool {
+        self.sprites_ready
+    }
+
+    /// Nothing is fetched; tests say when the sheet is ready
+    fn load_sprite_sheet(&mut self, _url: &str) -> Result<(), RenderError> {
+        Ok(())
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn render(&mut self, list: &DrawList) -> Result<(), RenderError> {
+        self.frames.push(list.clone());
+        Ok(())
+    }
+}
+
+/// What the game world looks like this frame
+pub struct WorldScene<'a> {
+    pub state: &'a GameState,
+    /// Other players where the interpolation buffer puts them
+    pub remote_players: &'a [RemoteEntity],
+    pub sprites: &'a SpriteAnimator,
+    pub atlas: &'a SpriteAtlas,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl WorldScene<'_> {
+    /// Build the frame's draw list. Only game world elements are drawn;
+    /// the front-end flow screens are drawn entirely by the frontend.
+    pub fn draw_list(&self, sprites_ready: bool) -> DrawList {
+        let mut list = DrawList::new();
+        list.push(DrawCommand::Clear);
+        if !self.state.in_world() {
+            return list;
+        }
+
+        list.push(DrawCommand::FillRect {
+            x: 0.0,
+            y: 0.0,
+            w: self.width,
+            h: self.height,
+            color: BACKGROUND_COLOR,
+        });
+
+        // Bouncing ball (legacy compatibility)
+        list.push(DrawCommand::FillCircle {
+            x: self.state.ball_x,
+            y: self.state.ball_y,
+            radius: BALL_RADIUS,
+            color: BALL_COLOR,
+        });
+
+        for player in self.remote_players {
+            self.push_sprite(
+                &mut list,
+                SpriteKey::Remote(player.id),
+                (player.x, player.y),
+                REMOTE_PLAYER_COLOR,
+                sprites_ready,
+            );
+            list.push(DrawCommand::Text {
+                text: player.name.clone(),
+                x: player.x,
+                y: player.y - NAME_OFFSET,
+                size: NAME_FONT_SIZE,
+                color: REMOTE_PLAYER_COLOR,
+            });
+        }
+
+        self.push_sprite(
+            &mut list,
+            SpriteKey::LocalPlayer,
+            (self.state.player_x, self.state.player_y),
+            PLAYER_COLOR,
+            sprites_ready,
+        );
+        list
+    }
+
+    /// Draw a sprite's current frame, or a plain circle while there's
+    /// nothing to draw yet, e.g. while the sheet is loading
+    fn push_sprite(
+        &self,
+        list: &mut DrawList,
+        key: SpriteKey,
+        (x, y): (f64, f64),
+        fallback: Color,
+        sprites_ready: bool,
+    ) {
+        let frame = sprites_ready
+            .then(|| self.sprites.frame(key, self.atlas))
+            .flatten();
+        list.push(match frame {
+            Some(&frame) => DrawCommand::Sprite { frame, x, y },
+            None => DrawCommand::FillCircle {
+                x,
+                y,
+                radius: PLAYER_RADIUS,
+                color: fallback,
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Region;
+    use crate::sprites::{PLAYER_SKIN, REMOTE_PLAYER_SKIN};
+
+    fn in_world() -> GameState {
+        let mut state = GameState::new(800.0, 600.0);
+        state.set_player_name("Hero").unwrap();
+        state.set_region(Region::Vietnam);
+        state.enter_world().unwrap();
+        state
+    }
+
+    fn ally(id: u32, name: &str, x: f64, y: f64) -> RemoteEntity {
+        RemoteEntity {
+            id,
+            name: name.to_string(),
+            x,
+            y,
+        }
+    }
+
+    fn scene<'a>(
+        state: &'a GameState,
+        remote_players: &'a [RemoteEntity],
+        sprites: &'a SpriteAnimator,
+        atlas: &'a SpriteAtlas,
+    ) -> WorldScene<'a> {
+        WorldScene {
+            state,
+            remote_players,
+            sprites,
+            atlas,
+            width: 800.0,
+            height: 600.0,
+        }
+    }
+
+    #[test]
+    fn test_front_end_screens_only_clear() {
+        let state = GameState::new(800.0, 600.0);
+        let atlas = SpriteAtlas::with_default_atlas();
+        let sprites = SpriteAnimator::new();
+        let list = scene(&state, &[], &sprites, &atlas).draw_list(true);
+        assert_eq!(list.commands(), &[DrawCommand::Clear]);
+    }
+
+    #[test]
+    fn test_world_falls_back_to_circles_until_sprites_load() {
+        let state = in_world();
+        let atlas = SpriteAtlas::with_default_atlas();
+        let mut sprites = SpriteAnimator::new();
+        let position = (state.player_x, state.player_y);
+        sprites.observe(SpriteKey::LocalPlayer, PLAYER_SKIN, position, 16.0, &atlas);
+
+        let list = scene(&state, &[], &sprites, &atlas).draw_list(false);
+        assert_eq!(
+            list.commands(),
+            &[
+                DrawCommand::Clear,
+                DrawCommand::FillRect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 800.0,
+                    h: 600.0,
+                    color: BACKGROUND_COLOR,
+                },
+                DrawCommand::FillCircle {
+                    x: state.ball_x,
+                    y: state.ball_y,
+                    radius: BALL_RADIUS,
+                    color: BALL_COLOR,
+                },
+                DrawCommand::FillCircle {
+                    x: state.player_x,
+                    y: state.player_y,
+                    radius: PLAYER_RADIUS,
+                    color: PLAYER_COLOR,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_player_drawn_from_current_frame() {
+        let state = in_world();
+        let atlas = SpriteAtlas::with_default_atlas();
+        let mut sprites = SpriteAnimator::new();
+        let position = (state.player_x, state.player_y);
+        sprites.observe(SpriteKey::LocalPlayer, PLAYER_SKIN, position, 16.0, &atlas);
+        let frame = *sprites.frame(SpriteKey::LocalPlayer, &atlas).unwrap();
+
+        let list = scene(&state, &[], &sprites, &atlas).draw_list(true);
+        assert_eq!(
+            list.commands().last(),
+            Some(&DrawCommand::Sprite {
+                frame,
+                x: state.player_x,
+                y: state.player_y,
+            })
+        );
+    }
+
+    #[test]
+    fn test_remote_players_labelled_and_drawn_under_local_player() {
+        let state = in_world();
+        let atlas = SpriteAtlas::with_default_atlas();
+        let mut sprites = SpriteAnimator::new();
+        let remote = [ally(2, "Bob", 100.0, 120.0), ally(3, "Cy", 300.0, 40.0)];
+        sprites.observe(
+            SpriteKey::Remote(2),
+            REMOTE_PLAYER_SKIN,
+            (100.0, 120.0),
+            16.0,
+            &atlas,
+        );
+
+        let list = scene(&state, &remote, &sprites, &atlas).draw_list(true);
+        let commands = &list.commands()[3..];
+        assert!(matches!(
+            commands[0],
+            DrawCommand::Sprite {
+                x: 100.0,
+                y: 120.0,
+                ..
+            }
+        ));
+        assert_eq!(
+            commands[1],
+            DrawCommand::Text {
+                text: "Bob".to_string(),
+                x: 100.0,
+                y: 98.0,
+                size: NAME_FONT_SIZE,
+                color: REMOTE_PLAYER_COLOR,
+            }
+        );
+        // Cy has no animation state yet, so stays a circle
+        assert_eq!(
+            commands[2],
+            DrawCommand::FillCircle {
+                x: 300.0,
+                y: 40.0,
+                radius: PLAYER_RADIUS,
+                color: REMOTE_PLAYER_COLOR,
+            }
+        );
+        assert!(matches!(&commands[3], DrawCommand::Text { text, .. } if text == "Cy"));
+        // The local player has no animation state either and goes last
+        assert!(matches!(
+            commands[4],
+            DrawCommand::FillCircle {
+                color: PLAYER_COLOR,
+                ..
+            }
+        ));
+        assert_eq!(commands.len(), 5);
+    }
+
+    #[test]
+    fn test_headless_renderer_records_frames() {
+        let state = in_world();
+        let atlas = SpriteAtlas::with_default_atlas();
+        let sprites = SpriteAnimator::new();
+        let mut renderer = HeadlessRenderer::new(800.0, 600.0);
+        assert!(!renderer.sprites_ready());
+
+        for _ in 0..2 {
+            let list = scene(&state, &[], &sprites, &atlas).draw_list(renderer.sprites_ready());
+            renderer.render(&list).unwrap();
+        }
+        renderer.resize(1024.0, 768.0);
+
+        assert_eq!(renderer.frames().len(), 2);
+        assert_eq!(renderer.last_frame().unwrap().len(), 4);
+        assert_eq!(renderer.size(), (1024.0, 768.0));
+    }
+
+    #[test]
+    fn test_color_css() {
+        assert_eq!(BACKGROUND_COLOR.css(), "#1e1e1e");
+        let translucent = Color {
+            a: 128,
+            ..PLAYER_COLOR
+        };
+        assert_eq!(translucent.css(), "rgba(255, 107, 107, 0.502)");
+    }
+
+    #[test]
+    fn test_backend_names() {
+        assert_eq!(
+            RenderBackend::from_name("WebGpu"),
+            Some(RenderBackend::WebGpu)
+        );
+        assert_eq!(
+            RenderBackend::from_name("Canvas2d"),
+            Some(RenderBackend::Canvas2d)
+        );
+        assert_eq!(RenderBackend::from_name("WebGL"), None);
+    }
+}
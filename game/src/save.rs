@@ -0,0 +1,203 @@
+use crate::game_state::GameState;
+use crate::migrations;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Current save schema version, bump whenever `GameState`'s shape changes
+pub const SAVE_VERSION: u32 = 5;
+
+/// Versioned envelope written to storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSnapshot {
+    pub version: u32,
+    pub state: GameState,
+}
+
+/// Reasons a snapshot can't be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    /// Not valid JSON or doesn't match the expected shape
+    Malformed(String),
+    /// JSON is missing the `version` field
+    MissingVersion,
+    /// Snapshot was written by a version this build can't read
+    UnsupportedVersion { found: u32, current: u32 },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Malformed(reason) => write!(f, "Save data is corrupted: {reason}"),
+            SaveError::MissingVersion => write!(f, "Save data has no version"),
+            SaveError::UnsupportedVersion { found, current } => write!(
+                f,
+                "Save version {found} is not supported (current version is {current})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SaveSnapshot {
+    /// Snapshot `state` as a local save. Gold and items shown while
+    /// connected are the server's, so the local ones are saved instead.
+    pub fn new(state: &GameState) -> Self {
+        let mut state = state.clone();
+        if let Some((gold, inventory)) = state.offline_belongings.take() {
+            state.gold = gold;
+            state.inventory = inventory;
+        }
+        Self {
+            version: SAVE_VERSION,
+            state,
+        }
+    }
+
+    /// Serialize the snapshot to JSON
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        serde_json::to_string(self).map_err(|e| SaveError::Malformed(e.to_string()))
+    }
+
+    /// Parse a snapshot, upgrading older versions before touching the state
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| SaveError::Malformed(e.to_string()))?;
+        Self::from_value(value)
+    }
+
+    /// Same as `from_json` for snapshots embedded in a larger JSON document
+    pub fn from_value(value: serde_json::Value) -> Result<Self, SaveError> {
+        let version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or(SaveError::MissingVersion)?;
+        let version = u32::try_from(version).unwrap_or(u32::MAX);
+        if version > SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion {
+                found: version,
+                current: SAVE_VERSION,
+            });
+        }
+
+        let value = migrations::migrate(value, version)?;
+        let mut snapshot: Self =
+            serde_json::from_value(value).map_err(|e| SaveError::Malformed(e.to_string()))?;
+        snapshot.state.restore_screen_stack();
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{GameScreen, Region};
+
+    #[test]
+    fn test_round_trip() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.set_player_name("Hero").unwrap();
+        state.set_region(Region::Vietnam);
+        state.transition_to(GameScreen::Inventory).unwrap();
+        state.move_player(25.0, -10.0);
+        state.inventory.add("iron_ore", 7).unwrap();
+
+        let json = SaveSnapshot::new(&state).to_json().unwrap();
+        let loaded = SaveSnapshot::from_json(&json).unwrap();
+
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.state.player_name, Some("Hero".to_string()));
+        assert_eq!(loaded.state.selected_region, Some(Region::Vietnam));
+        assert_eq!(loaded.state.current_screen, GameScreen::Inventory);
+        assert_eq!(loaded.state.player_x, 425.0);
+        assert_eq!(loaded.state.player_y, 290.0);
+        assert_eq!(loaded.state.inventory.count("iron_ore"), 7);
+    }
+
+    #[test]
+    fn test_server_belongings_stay_out_of_saves() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.inventory.add("iron_ore", 7).unwrap();
+        let local_gold = state.gold;
+
+        let mut online = crate::inventory::Inventory::default();
+        online.add("health_potion", 3).unwrap();
+        state.set_belongings(9999, online);
+        assert_eq!(state.gold, 9999);
+
+        let saved = SaveSnapshot::new(&state).state;
+        assert_eq!(saved.gold, local_gold);
+        assert_eq!(saved.inventory.count("iron_ore"), 7);
+        assert_eq!(saved.inventory.count("health_potion"), 0);
+
+        state.restore_offline_belongings();
+        assert_eq!(state.gold, local_gold);
+        assert_eq!(state.inventory.count("iron_ore"), 7);
+    }
+
+    #[test]
+    fn test_modal_saved_before_entering_the_world_stays_out_of_it() {
+        let mut state = GameState::new(800.0, 600.0);
+        state.set_player_name("Hero").unwrap();
+        state.current_screen = GameScreen::HelpModal;
+
+        let json = SaveSnapshot::new(&state).to_json().unwrap();
+        let loaded = SaveSnapshot::from_json(&json).unwrap().state;
+        assert_eq!(
+            loaded.screens(),
+            vec![GameScreen::LoginScreen, GameScreen::HelpModal]
+        );
+        assert!(!loaded.in_world());
+
+        // Screens that only open in the world fall back to the login screen
+        state.current_screen = GameScreen::Inventory;
+        let json = SaveSnapshot::new(&state).to_json().unwrap();
+        let loaded = SaveSnapshot::from_json(&json).unwrap().state;
+        assert_eq!(loaded.screens(), vec![GameScreen::LoginScreen]);
+    }
+
+    #[test]
+    fn test_rejects_malformed_json() {
+        assert!(matches!(
+            SaveSnapshot::from_json("not json"),
+            Err(SaveError::Malformed(_))
+        ));
+        let wrong_shape = format!(r#"{{"version": {SAVE_VERSION}, "state": {{}}}}"#);
+        assert!(matches!(
+            SaveSnapshot::from_json(&wrong_shape),
+            Err(SaveError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_missing_version() {
+        assert_eq!(
+            SaveSnapshot::from_json(r#"{"state": {}}"#).unwrap_err(),
+            SaveError::MissingVersion
+        );
+    }
+
+    #[test]
+    fn test_rejects_unsupported_versions() {
+        let state = GameState::new(800.0, 600.0);
+        let mut value = serde_json::to_value(SaveSnapshot::new(&state)).unwrap();
+
+        value["version"] = serde_json::json!(SAVE_VERSION + 1);
+        assert_eq!(
+            SaveSnapshot::from_json(&value.to_string()).unwrap_err(),
+            SaveError::UnsupportedVersion {
+                found: SAVE_VERSION + 1,
+                current: SAVE_VERSION,
+            }
+        );
+
+        value["version"] = serde_json::json!(0);
+        assert_eq!(
+            SaveSnapshot::from_json(&value.to_string()).unwrap_err(),
+            SaveError::UnsupportedVersion {
+                found: 0,
+                current: SAVE_VERSION,
+            }
+        );
+    }
+}
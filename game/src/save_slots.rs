@@ -0,0 +1,315 @@
+use crate::game_state::GameState;
+use crate::save::{SaveError, SaveSnapshot};
+use crate::storage::{SaveStorage, StorageError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Prefix for every save slot key in storage
+pub const SLOT_KEY_PREFIX: &str = "copilot_game.save.";
+
+/// Slot written automatically when the player changes zone
+pub const AUTOSAVE_SLOT: &str = "autosave";
+
+/// Maximum number of manual save slots (the autosave slot is extra)
+pub const MAX_SAVE_SLOTS: usize = 10;
+
+/// Maximum length of a slot name
+pub const MAX_SLOT_NAME_LEN: usize = 32;
+
+/// Summary shown in the load game menu
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotMetadata {
+    pub slot: String,
+    pub player_name: String,
+    pub level: u32,
+    pub zone: String,
+    /// Milliseconds since the Unix epoch
+    pub saved_at_ms: f64,
+    pub playtime_secs: f64,
+}
+
+/// What actually gets written under a slot key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SlotRecord {
+    metadata: SlotMetadata,
+    snapshot: serde_json::Value,
+}
+
+/// Errors returned by the slot manager
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotError {
+    InvalidSlotName(String),
+    SlotNotFound(String),
+    TooManySlots,
+    Save(SaveError),
+    Storage(StorageError),
+}
+
+impl fmt::Display for SlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotError::InvalidSlotName(name) => write!(f, "Invalid save slot name: {name:?}"),
+            SlotError::SlotNotFound(name) => write!(f, "No save in slot {name:?}"),
+            SlotError::TooManySlots => {
+                write!(f, "All {MAX_SAVE_SLOTS} save slots are in use")
+            }
+            SlotError::Save(e) => e.fmt(f),
+            SlotError::Storage(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SlotError {}
+
+impl From<SaveError> for SlotError {
+    fn from(e: SaveError) -> Self {
+        SlotError::Save(e)
+    }
+}
+
+impl From<StorageError> for SlotError {
+    fn from(e: StorageError) -> Self {
+        SlotError::Storage(e)
+    }
+}
+
+/// Named save slots on top of a storage backend
+#[derive(Debug, Clone, Default)]
+pub struct SaveSlotManager<S: SaveStorage> {
+    storage: S,
+}
+
+impl<S: SaveStorage> SaveSlotManager<S> {
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Write the state into a slot, overwriting any previous save there
+    pub fn save(
+        &mut self,
+        slot: &str,
+        state: &GameState,
+        now_ms: f64,
+    ) -> Result<SlotMetadata, SlotError> {
+        validate_slot_name(slot)?;
+        let key = slot_key(slot);
+
+        if slot != AUTOSAVE_SLOT && self.storage.get_item(&key)?.is_none() {
+            let manual_slots = self
+                .list_slots()?
+                .iter()
+                .filter(|m| m.slot != AUTOSAVE_SLOT)
+                .count();
+            if manual_slots >= MAX_SAVE_SLOTS {
+                return Err(SlotError::TooManySlots);
+            }
+        }
+
+        let metadata = SlotMetadata {
+            slot: slot.to_string(),
+            player_name: state.player_name.clone().unwrap_or_default(),
+            level: state.player_level,
+            zone: state.current_zone.clone(),
+            saved_at_ms: now_ms,
+            playtime_secs: state.playtime_secs,
+        };
+        let record = SlotRecord {
+            metadata: metadata.clone(),
+            snapshot: serde_json::to_value(SaveSnapshot::new(state))
+                .map_err(|e| SaveError::Malformed(e.to_string()))?,
+        };
+        let json =
+            serde_json::to_string(&record).map_err(|e| SaveError::Malformed(e.to_string()))?;
+        self.storage.set_item(&key, &json)?;
+        Ok(metadata)
+    }
+
+    /// Load the state stored in a slot
+    pub fn load(&self, slot: &str) -> Result<GameState, SlotError> {
+        let record = self.read_record(slot)?;
+        Ok(SaveSnapshot::from_value(record.snapshot)?.state)
+    }
+
+    /// Delete a slot, returning an error if it doesn't exist
+    pub fn delete(&mut self, slot: &str) -> Result<(), SlotError> {
+        self.read_record(slot)?;
+        self.storage.remove_item(&slot_key(slot))?;
+        Ok(())
+    }
+
+    /// Metadata for every slot, most recently saved first.
+    /// Unreadable slots are skipped so one bad save can't hide the rest.
+    pub fn list_slots(&self) -> Result<Vec<SlotMetadata>, SlotError> {
+        let mut slots: Vec<SlotMetadata> = self
+            .storage
+            .keys()?
+            .iter()
+            .filter_map(|key| key.strip_prefix(SLOT_KEY_PREFIX))
+            .filter_map(|slot| self.read_record(slot).ok())
+            .map(|record| record.metadata)
+            .collect();
+        slots.sort_by(|a, b| b.saved_at_ms.total_cmp(&a.saved_at_ms));
+        Ok(slots)
+    }
+
+    /// Write the autosave slot
+    pub fn autosave(&mut self, state: &GameState, now_ms: f64) -> Result<SlotMetadata, SlotError> {
+        self.save(AUTOSAVE_SLOT, state, now_ms)
+    }
+
+    fn read_record(&self, slot: &str) -> Result<SlotRecord, SlotError> {
+        validate_slot_name(slot)?;
+        let json = self
+            .storage
+            .get_item(&slot_key(slot))?
+            .ok_or_else(|| SlotError::SlotNotFound(slot.to_string()))?;
+        serde_json::from_str(&json).map_err(|e| SaveError::Malformed(e.to_string()).into())
+    }
+}
+
+fn slot_key(slot: &str) -> String {
+    format!("{SLOT_KEY_PREFIX}{slot}")
+}
+
+fn validate_slot_name(slot: &str) -> Result<(), SlotError> {
+    let valid = !slot.is_empty()
+        && slot.len() <= MAX_SLOT_NAME_LEN
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ' '));
+    if valid {
+        Ok(())
+    } else {
+        Err(SlotError::InvalidSlotName(slot.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn manager() -> SaveSlotManager<MemoryStorage> {
+        SaveSlotManager::new(MemoryStorage::new())
+    }
+
+    #[test]
+    fn test_save_and_load_slot() {
+        let mut slots = manager();
+        let mut state = GameState::new(800.0, 600.0);
+        state.set_player_name("Hero").unwrap();
+        state.enter_zone("Dark Forest");
+        state.add_playtime(90.0);
+        state.inventory.add("coal", 3).unwrap();
+
+        let metadata = slots.save("slot 1", &state, 1_000.0).unwrap();
+        assert_eq!(
+            metadata,
+            SlotMetadata {
+                slot: "slot 1".to_string(),
+                player_name: "Hero".to_string(),
+                level: 1,
+                zone: "Dark Forest".to_string(),
+                saved_at_ms: 1_000.0,
+                playtime_secs: 90.0,
+            }
+        );
+
+        let loaded = slots.load("slot 1").unwrap();
+        assert_eq!(loaded.player_name, Some("Hero".to_string()));
+        assert_eq!(loaded.current_zone, "Dark Forest");
+        assert_eq!(loaded.inventory.count("coal"), 3);
+    }
+
+    #[test]
+    fn test_list_slots_newest_first() {
+        let mut slots = manager();
+        let state = GameState::new(800.0, 600.0);
+
+        slots.save("older", &state, 1_000.0).unwrap();
+        slots.save("newer", &state, 2_000.0).unwrap();
+        slots.autosave(&state, 1_500.0).unwrap();
+
+        let names: Vec<String> = slots
+            .list_slots()
+            .unwrap()
+            .into_iter()
+            .map(|m| m.slot)
+            .collect();
+        assert_eq!(names, vec!["newer", AUTOSAVE_SLOT, "older"]);
+    }
+
+    #[test]
+    fn test_missing_and_deleted_slots() {
+        let mut slots = manager();
+        let state = GameState::new(800.0, 600.0);
+
+        assert_eq!(
+            slots.load("nope").unwrap_err(),
+            SlotError::SlotNotFound("nope".to_string())
+        );
+
+        slots.save("temp", &state, 1_000.0).unwrap();
+        slots.delete("temp").unwrap();
+        assert!(slots.list_slots().unwrap().is_empty());
+        assert_eq!(
+            slots.delete("temp").unwrap_err(),
+            SlotError::SlotNotFound("temp".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_slot_names() {
+        let mut slots = manager();
+        let state = GameState::new(800.0, 600.0);
+
+        for name in ["", "../etc", "slot\n", &"x".repeat(MAX_SLOT_NAME_LEN + 1)] {
+            assert_eq!(
+                slots.save(name, &state, 0.0).unwrap_err(),
+                SlotError::InvalidSlotName(name.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_slot_limit_excludes_autosave_and_overwrites() {
+        let mut slots = manager();
+        let state = GameState::new(800.0, 600.0);
+
+        for i in 0..MAX_SAVE_SLOTS {
+            slots.save(&format!("slot{i}"), &state, i as f64).unwrap();
+        }
+        assert_eq!(
+            slots.save("one too many", &state, 0.0).unwrap_err(),
+            SlotError::TooManySlots
+        );
+
+        // Overwriting an existing slot and autosaving are still allowed
+        slots.save("slot0", &state, 100.0).unwrap();
+        slots.autosave(&state, 200.0).unwrap();
+        assert_eq!(slots.list_slots().unwrap().len(), MAX_SAVE_SLOTS + 1);
+    }
+
+    #[test]
+    fn test_corrupted_slot_is_skipped_in_listing() {
+        let mut storage = MemoryStorage::new();
+        storage
+            .set_item(&format!("{SLOT_KEY_PREFIX}broken"), "{not json")
+            .unwrap();
+        storage.set_item("unrelated.key", "value").unwrap();
+        let mut slots = SaveSlotManager::new(storage);
+        slots
+            .save("good", &GameState::new(800.0, 600.0), 0.0)
+            .unwrap();
+
+        assert_eq!(slots.list_slots().unwrap().len(), 1);
+        assert!(matches!(
+            slots.load("broken"),
+            Err(SlotError::Save(SaveError::Malformed(_)))
+        ));
+    }
+}
@@ -0,0 +1,1632 @@
+use crate::chat::{ChatChannel, ChatError, ChatFilter, ChatModerator};
+use crate::commands::CommandError;
+use crate::crafting::RecipeRegistry;
+use crate::game_state::{GameState, Region};
+use crate::interest::{InterestConfig, SpatialGrid};
+use crate::inventory::Inventory;
+use crate::party::{PartyAction, PartyError, PartyManager, PartyMember, PartyView};
+use crate::prediction::{apply_input, input_distance, MAX_INPUT_STEP, MAX_MOVE_SPEED};
+use crate::profile::PlayerProfile;
+use crate::protocol::{
+    Belongings, ClientMessage, DisconnectReason, PlayerId, PlayerInput, PlayerSnapshot,
+    ServerMessage, WorldSnapshot,
+};
+use crate::shop::ShopCatalog;
+use crate::trade::{self, TradeAction, TradeError, TradeManager, TradeView};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Inputs buffered per player between ticks. A client pacing its inputs at
+/// `INPUT_RATE` never gets near this; past it, new inputs are refused so the
+/// queued ones are still applied in order.
+pub const MAX_PENDING_INPUTS: usize = 32;
+
+/// Settings for an authoritative server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Simulation ticks per second
+    pub tick_rate: u16,
+    pub max_players: usize,
+    pub world_width: f64,
+    pub world_height: f64,
+    pub interest: InterestConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: 20,
+            max_players: 64,
+            world_width: 800.0,
+            world_height: 600.0,
+            interest: InterestConfig::default(),
+        }
+    }
+}
+
+/// Who a server message is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Player(PlayerId),
+    Everyone,
+}
+
+/// A message the transport should deliver
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub to: Recipient,
+    pub message: ServerMessage,
+}
+
+/// One joined player, simulated with the same `GameState` as the client
+#[derive(Debug)]
+struct ConnectedPlayer {
+    state: GameState,
+    pending_inputs: VecDeque<PlayerInput>,
+    last_processed_input: u32,
+    /// Movement this player may still spend on inputs; refilled each tick
+    move_budget: f64,
+    /// Other players this client currently knows about
+    visible: BTreeSet<PlayerId>,
+    /// Party frame last sent to this client
+    party_view: Option<PartyView>,
+    /// Trade window last sent to this client
+    trade_view: Option<TradeView>,
+}
+
+/// The authoritative world, independent of the network transport
+#[derive(Debug)]
+pub struct ServerWorld {
+    config: ServerConfig,
+    players: BTreeMap<PlayerId, ConnectedPlayer>,
+    next_player_id: PlayerId,
+    tick: u64,
+    grid: SpatialGrid,
+    /// Messages produced outside `handle`, by stepping or awarding XP
+    outgoing: Vec<Outgoing>,
+    chat: ChatModerator,
+    parties: PartyManager,
+    trades: TradeManager,
+    shop: ShopCatalog,
+    recipes: RecipeRegistry,
+}
+
+impl ServerWorld {
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            grid: SpatialGrid::new(config.interest.cell_size),
+            config,
+            players: BTreeMap::new(),
+            next_player_id: 1,
+            tick: 0,
+            outgoing: Vec::new(),
+            chat: ChatModerator::default(),
+            parties: PartyManager::new(),
+            trades: TradeManager::new(),
+            shop: ShopCatalog::with_default_items(),
+            recipes: RecipeRegistry::with_default_recipes(),
+        }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Plug in a profanity filter for chat
+    pub fn set_chat_filter(&mut self, filter: Box<dyn ChatFilter>) {
+        self.chat.set_filter(filter);
+    }
+
+    /// Mute or unmute a player by name; mutes last across reconnects
+    pub fn set_muted(&mut self, name: &str, muted: bool) {
+        self.chat.set_muted(name, muted);
+    }
+
+    /// Seconds simulated by each tick
+    pub fn tick_duration_secs(&self) -> f64 {
+        1.0 / f64::from(self.config.tick_rate.max(1))
+    }
+
+    /// Most movement one player's inputs may add up to in a single tick
+    pub fn max_move_per_tick(&self) -> f64 {
+        MAX_MOVE_SPEED * self.tick_duration_secs()
+    }
+
+    /// Server time, for rate limits and expiry
+    fn now_ms(&self) -> f64 {
+        self.tick as f64 * self.tick_duration_secs() * 1000.0
+    }
+
+    /// Admit a player, returning the reason if they're turned away
+    pub fn join(
+        &mut self,
+        profile: PlayerProfile,
+        region: Region,
+    ) -> Result<PlayerId, DisconnectReason> {
+        if self.players.len() >= self.config.max_players {
+            return Err(DisconnectReason::ServerFull);
+        }
+        // One session per name, so whispers and mutes reach the right player
+        if self.player_named(&profile.name).is_some() {
+            return Err(DisconnectReason::NameTaken);
+        }
+
+        let mut state = GameState::new(self.config.world_width, self.config.world_height);
+        state
+            .set_profile(profile)
+            .map_err(|e| DisconnectReason::InvalidProfile(e.to_string()))?;
+        state.set_region(region);
+        state
+            .enter_world()
+            .expect("a named player with a region can enter the world");
+
+        let id = self.next_player_id;
+        self.next_player_id += 1;
+        self.grid.update(id, state.player_x, state.player_y);
+        self.players.insert(
+            id,
+            ConnectedPlayer {
+                state,
+                pending_inputs: VecDeque::new(),
+                last_processed_input: 0,
+                move_budget: 0.0,
+                visible: BTreeSet::new(),
+                party_view: None,
+                trade_view: None,
+            },
+        );
+        let belongings = self.belongings_update(id);
+        self.outgoing.extend(belongings);
+        Ok(id)
+    }
+
+    /// Replace the gold and items the server holds for `id`, e.g. when an
+    /// operator grants them. Returns false if they aren't connected or the
+    /// stacks don't fit in the inventory.
+    pub fn set_belongings(&mut self, id: PlayerId, belongings: Belongings) -> bool {
+        let Some(player) = self.players.get_mut(&id) else {
+            return false;
+        };
+        let mut inventory = Inventory::new(belongings.inventory.capacity);
+        for stack in &belongings.inventory.slots {
+            if inventory.add(&stack.item_id, stack.quantity).is_err() {
+                return false;
+            }
+        }
+        player.state.gold = belongings.gold;
+        player.state.inventory = inventory;
+        let update = self.belongings_update(id);
+        self.outgoing.extend(update);
+        true
+    }
+
+    /// The current gold and items of `id`, addressed to them
+    fn belongings_update(&self, id: PlayerId) -> Option<Outgoing> {
+        let player = self.players.get(&id)?;
+        Some(Outgoing {
+            to: Recipient::Player(id),
+            message: ServerMessage::Belongings(Belongings::of(&player.state)),
+        })
+    }
+
+    /// Remove a player, returning whether they were connected. Clients that
+    /// could see them hear about it on the next step. Their belongings go
+    /// with them: names aren't authenticated, so nothing is kept for a
+    /// later session until there are accounts to tie it to.
+    pub fn leave(&mut self, id: PlayerId) -> bool {
+        self.grid.remove(id);
+        self.chat.forget(id);
+        self.parties.forget(id);
+        self.trades.forget(id);
+        self.players.remove(&id).is_some()
+    }
+
+    /// Handle a message from a joined player
+    pub fn handle(&mut self, id: PlayerId, message: ClientMessage) -> Vec<Outgoing> {
+        let Some(player) = self.players.get_mut(&id) else {
+            return Vec::new();
+        };
+
+        match message {
+            // Already joined; a second join is ignored
+            ClientMessage::Join { .. } => Vec::new(),
+            ClientMessage::Input(input) => {
+                // Inputs the server has already applied arrive late or twice
+                if input.seq > player.last_processed_input
+                    && player.pending_inputs.len() < MAX_PENDING_INPUTS
+                {
+                    player.pending_inputs.push_back(input);
+                }
+                Vec::new()
+            }
+            ClientMessage::Chat { channel, text } => match self.route_chat(id, channel, &text) {
+                Ok(outgoing) => outgoing,
+                Err(e) => vec![Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::ChatRejected {
+                        code: e.code().to_string(),
+                        message: e.to_string(),
+                    },
+                }],
+            },
+            ClientMessage::Party(action) => match self.party_action(id, action) {
+                Ok(outgoing) => outgoing,
+                Err(e) => vec![Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::PartyRejected {
+                        code: e.code().to_string(),
+                        message: e.to_string(),
+                    },
+                }],
+            },
+            ClientMessage::Trade(action) => match self.trade_action(id, action) {
+                Ok(outgoing) => outgoing,
+                Err(e) => vec![Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::TradeRejected {
+                        code: e.code().to_string(),
+                        message: e.to_string(),
+                    },
+                }],
+            },
+            ClientMessage::Buy { item_id, quantity } => {
+                let result = self.shop.buy(&mut player.state, &item_id, quantity);
+                self.belongings_or_rejection(id, result.map_err(CommandError::from))
+            }
+            ClientMessage::Craft { recipe_id, station } => {
+                // A failed craft leaves the inventory as it was
+                let result = self
+                    .recipes
+                    .craft(&recipe_id, &mut player.state.inventory, station);
+                self.belongings_or_rejection(id, result.map_err(CommandError::from))
+            }
+            ClientMessage::Ping { sent_at_ms } => vec![Outgoing {
+                to: Recipient::Player(id),
+                message: ServerMessage::Pong { sent_at_ms },
+            }],
+            ClientMessage::Leave => {
+                self.leave(id);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Answer a purchase or craft with the new belongings, or why it failed
+    fn belongings_or_rejection<T>(
+        &self,
+        id: PlayerId,
+        result: Result<T, CommandError>,
+    ) -> Vec<Outgoing> {
+        match result {
+            Ok(_) => self.belongings_update(id).into_iter().collect(),
+            Err(e) => vec![Outgoing {
+                to: Recipient::Player(id),
+                message: ServerMessage::CommandRejected {
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                },
+            }],
+        }
+    }
+
+    /// Check a chat message and address it to everyone on its channel
+    fn route_chat(
+        &mut self,
+        id: PlayerId,
+        channel: ChatChannel,
+        raw: &str,
+    ) -> Result<Vec<Outgoing>, ChatError> {
+        let sender = &self.players[&id];
+        let recipients: Vec<Recipient> = match &channel {
+            ChatChannel::Local => std::iter::once(id)
+                .chain(sender.visible.iter().copied())
+                .map(Recipient::Player)
+                .collect(),
+            ChatChannel::Zone => self
+                .players
+                .iter()
+                .filter(|(_, player)| player.state.current_zone == sender.state.current_zone)
+                .map(|(&other, _)| Recipient::Player(other))
+                .collect(),
+            ChatChannel::Global => vec![Recipient::Everyone],
+            ChatChannel::Party => self
+                .parties
+                .party_of(id)
+                .ok_or(ChatError::NotInParty)?
+                .members
+                .iter()
+                .copied()
+                .map(Recipient::Player)
+                .collect(),
+            ChatChannel::Whisper { to } => {
+                let target = self
+                    .player_named(to)
+                    .ok_or_else(|| ChatError::UnknownRecipient(to.clone()))?;
+                // The sender sees their own whisper too
+                let mut recipients = vec![Recipient::Player(target)];
+                if target != id {
+                    recipients.push(Recipient::Player(id));
+                }
+                recipients
+            }
+        };
+        let name = sender.state.player_name.clone().unwrap_or_default();
+
+        let text = self.chat.review(id, &name, raw, self.now_ms())?;
+        Ok(recipients
+            .into_iter()
+            .map(|to| Outgoing {
+                to,
+                message: ServerMessage::Chat {
+                    from: id,
+                    name: name.clone(),
+                    channel: channel.clone(),
+                    text: text.clone(),
+                },
+            })
+            .collect())
+    }
+
+    /// Apply a party action, answering with invites and the party frames
+    /// it changed
+    fn party_action(
+        &mut self,
+        id: PlayerId,
+        action: PartyAction,
+    ) -> Result<Vec<Outgoing>, PartyError> {
+        let now_ms = self.now_ms();
+        let mut outgoing = Vec::new();
+        match action {
+            PartyAction::Invite { name } => {
+                let target = self
+                    .player_named(&name)
+                    .ok_or_else(|| PartyError::UnknownPlayer(name.clone()))?;
+                self.parties.invite(id, target, &name, now_ms)?;
+                outgoing.push(Outgoing {
+                    to: Recipient::Player(target),
+                    message: ServerMessage::PartyInvite {
+                        from: id,
+                        name: self.name_of(id),
+                    },
+                });
+            }
+            PartyAction::Accept => {
+                self.parties.accept(id, now_ms)?;
+            }
+            PartyAction::Decline => {
+                self.parties.decline(id)?;
+            }
+            PartyAction::Leave => self.parties.leave(id)?,
+            PartyAction::Kick { id: target } => self.parties.kick(id, target)?,
+            PartyAction::Promote { id: target } => self.parties.promote(id, target)?,
+        }
+        outgoing.extend(self.sync_parties());
+        Ok(outgoing)
+    }
+
+    /// Apply a trade action, answering with requests, completed swaps and
+    /// the trade windows it changed
+    fn trade_action(
+        &mut self,
+        id: PlayerId,
+        action: TradeAction,
+    ) -> Result<Vec<Outgoing>, TradeError> {
+        let now_ms = self.now_ms();
+        let mut outgoing = Vec::new();
+        match action {
+            TradeAction::Request { name } => {
+                let target = self
+                    .player_named(&name)
+                    .ok_or_else(|| TradeError::UnknownPlayer(name.clone()))?;
+                self.trades.request(id, target, &name, now_ms)?;
+                outgoing.push(Outgoing {
+                    to: Recipient::Player(target),
+                    message: ServerMessage::TradeRequest {
+                        from: id,
+                        name: self.name_of(id),
+                    },
+                });
+            }
+            TradeAction::Accept => {
+                // The manager only knows ids; name whoever got busy in the meantime
+                let from = self.trades.pending_request(id, now_ms);
+                self.trades.accept(id, now_ms).map_err(|e| match e {
+                    TradeError::TargetBusy(_) => TradeError::TargetBusy(
+                        from.map(|from| self.name_of(from)).unwrap_or_default(),
+                    ),
+                    e => e,
+                })?;
+            }
+            TradeAction::Decline => {
+                self.trades.decline(id)?;
+            }
+            TradeAction::Offer(offer) => {
+                let offer = offer.normalized();
+                offer.check_owned(&self.players[&id].state)?;
+                self.trades.session_mut(id)?.set_offer(id, offer)?;
+            }
+            TradeAction::Lock => self.trades.session_mut(id)?.lock(id)?,
+            TradeAction::Unlock => self.trades.session_mut(id)?.unlock(id)?,
+            TradeAction::Confirm => {
+                if self.trades.session_mut(id)?.confirm(id)? {
+                    outgoing.extend(self.complete_trade(id)?);
+                }
+            }
+            TradeAction::Cancel => {
+                self.trades.cancel(id)?;
+            }
+        }
+        outgoing.extend(self.sync_trades());
+        Ok(outgoing)
+    }
+
+    /// Swap both offers of `id`'s confirmed trade. If the swap can't go
+    /// through, nothing changes hands, both sides are unlocked to fix their
+    /// offers and the partner is told why.
+    fn complete_trade(&mut self, id: PlayerId) -> Result<Vec<Outgoing>, TradeError> {
+        let session = self.trades.session_of(id).ok_or(TradeError::NotTrading)?;
+        let [a, b] = session.players();
+        let a_offer = session.offer_of(a).cloned().unwrap_or_default();
+        let b_offer = session.offer_of(b).cloned().unwrap_or_default();
+
+        // Take one trader out of the map to borrow both states at once
+        let mut first = self.players.remove(&a).ok_or(TradeError::NotTrading)?;
+        let result = match self.players.get_mut(&b) {
+            Some(second) => trade::settle(&mut first.state, &a_offer, &mut second.state, &b_offer),
+            None => Err(TradeError::NotTrading),
+        };
+        self.players.insert(a, first);
+
+        if let Err(e) = result {
+            self.trades.session_mut(id)?.reopen();
+            let partner = if id == a { b } else { a };
+            self.outgoing.push(Outgoing {
+                to: Recipient::Player(partner),
+                message: ServerMessage::TradeRejected {
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                },
+            });
+            let updates = self.sync_trades();
+            self.outgoing.extend(updates);
+            return Err(e);
+        }
+
+        self.trades.cancel(id)?;
+        let mut outgoing = vec![
+            Outgoing {
+                to: Recipient::Player(a),
+                message: ServerMessage::TradeCompleted {
+                    gave: a_offer.clone(),
+                    received: b_offer.clone(),
+                },
+            },
+            Outgoing {
+                to: Recipient::Player(b),
+                message: ServerMessage::TradeCompleted {
+                    gave: b_offer,
+                    received: a_offer,
+                },
+            },
+        ];
+        outgoing.extend(self.belongings_update(a));
+        outgoing.extend(self.belongings_update(b));
+        Ok(outgoing)
+    }
+
+    /// Give `earner` XP, shared with party members in the same zone.
+    /// Nothing in the world grants XP yet; this is the hook kills and
+    /// quests will call.
+    pub fn award_xp(&mut self, earner: PlayerId, amount: u32) {
+        let Some(zone) = self
+            .players
+            .get(&earner)
+            .map(|player| player.state.current_zone.clone())
+        else {
+            return;
+        };
+        let shares = self.parties.share_xp(earner, amount, |member| {
+            self.players
+                .get(&member)
+                .is_some_and(|player| player.state.current_zone == zone)
+        });
+        for (member, amount) in shares {
+            if let Some(player) = self.players.get_mut(&member) {
+                player.state.gain_xp(amount);
+                self.outgoing.push(Outgoing {
+                    to: Recipient::Player(member),
+                    message: ServerMessage::XpGained { amount },
+                });
+            }
+        }
+    }
+
+    /// The party frame `id` should see
+    fn party_view(&self, id: PlayerId) -> Option<PartyView> {
+        let party = self.parties.party_of(id)?;
+        Some(PartyView {
+            leader: party.leader,
+            members: party
+                .members
+                .iter()
+                .filter_map(|&member| {
+                    let state = &self.players.get(&member)?.state;
+                    Some(PartyMember {
+                        id: member,
+                        name: state.player_name.clone().unwrap_or_default(),
+                        level: state.player_level,
+                        hp: state.player_hp,
+                        max_hp: state.player_max_hp,
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// Send each client their party frame if it changed since last time
+    fn sync_parties(&mut self) -> Vec<Outgoing> {
+        let views: Vec<(PlayerId, Option<PartyView>)> = self
+            .players
+            .keys()
+            .map(|&id| (id, self.party_view(id)))
+            .collect();
+
+        let mut updates = Vec::new();
+        for (id, view) in views {
+            let player = self.players.get_mut(&id).expect("listed above");
+            if player.party_view != view {
+                player.party_view.clone_from(&view);
+                updates.push(Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::PartyUpdate(view),
+                });
+            }
+        }
+        updates
+    }
+
+    /// The trade window `id` should see
+    fn trade_view(&self, id: PlayerId) -> Option<TradeView> {
+        let session = self.trades.session_of(id)?;
+        let partner = session.partner_of(id)?;
+        session.view_for(id, &self.name_of(partner))
+    }
+
+    /// Send each client their trade window if it changed since last time
+    fn sync_trades(&mut self) -> Vec<Outgoing> {
+        let views: Vec<(PlayerId, Option<TradeView>)> = self
+            .players
+            .keys()
+            .map(|&id| (id, self.trade_view(id)))
+            .collect();
+
+        let mut updates = Vec::new();
+        for (id, view) in views {
+            let player = self.players.get_mut(&id).expect("listed above");
+            if player.trade_view != view {
+                player.trade_view.clone_from(&view);
+                updates.push(Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::TradeUpdate(view),
+                });
+            }
+        }
+        updates
+    }
+
+    fn name_of(&self, id: PlayerId) -> String {
+        self.players
+            .get(&id)
+            .and_then(|player| player.state.player_name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Find an online player by name, ignoring case
+    fn player_named(&self, name: &str) -> Option<PlayerId> {
+        self.players.iter().find_map(|(&id, player)| {
+            player
+                .state
+                .player_name
+                .as_deref()
+                .filter(|candidate| candidate.eq_ignore_ascii_case(name.trim()))
+                .map(|_| id)
+        })
+    }
+
+    /// Apply buffered inputs, as far as each player's movement allowance
+    /// goes, and advance the simulation by one tick. Inputs that don't fit
+    /// wait for the next tick, so sending more of them can't speed anyone up.
+    pub fn step(&mut self) -> u64 {
+        let elapsed_secs = self.tick_duration_secs();
+        let allowance = self.max_move_per_tick();
+        for (id, player) in self.players.iter_mut() {
+            // Unused allowance carries over for one tick, so inputs that
+            // arrive bunched up catch up instead of piling up
+            player.move_budget =
+                (player.move_budget + allowance).min(2.0 * allowance.max(MAX_INPUT_STEP));
+            player
+                .pending_inputs
+                .make_contiguous()
+                .sort_by_key(|input| input.seq);
+            while let Some(input) = player.pending_inputs.front() {
+                if input.seq <= player.last_processed_input {
+                    player.pending_inputs.pop_front();
+                    continue;
+                }
+                let distance = input_distance(input);
+                if distance > player.move_budget {
+                    break;
+                }
+                player.move_budget -= distance;
+                // Inputs are applied one at a time, exactly as the client predicts them
+                apply_input(&mut player.state, input);
+                player.last_processed_input = input.seq;
+                player.pending_inputs.pop_front();
+            }
+            player.state.step(elapsed_secs, 0.0, 0.0);
+            self.grid
+                .update(*id, player.state.player_x, player.state.player_y);
+        }
+        self.update_interest();
+        let party_updates = self.sync_parties();
+        self.outgoing.extend(party_updates);
+        let trade_updates = self.sync_trades();
+        self.outgoing.extend(trade_updates);
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Take the messages produced outside `handle` since the last call:
+    /// area-of-interest changes, party frames, trade windows and XP
+    pub fn take_outgoing(&mut self) -> Vec<Outgoing> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Players `id` currently knows about, besides themselves
+    pub fn visible_to(&self, id: PlayerId) -> Option<&BTreeSet<PlayerId>> {
+        self.players.get(&id).map(|player| &player.visible)
+    }
+
+    /// Recompute each client's visible set from the grid. Entities enter at
+    /// the camera area plus margin but only leave past twice the margin, so
+    /// one hovering on the edge doesn't flicker in and out.
+    fn update_interest(&mut self) {
+        let interest = &self.config.interest;
+        let mut changes = Vec::new();
+        for (&id, player) in &self.players {
+            let (x, y) = (player.state.player_x, player.state.player_y);
+            let near = self
+                .grid
+                .query(&interest.area_around(x, y, interest.margin));
+            let still_near = self
+                .grid
+                .query(&interest.area_around(x, y, interest.margin * 2.0));
+
+            let mut visible: BTreeSet<PlayerId> = near
+                .union(&player.visible.intersection(&still_near).copied().collect())
+                .copied()
+                .collect();
+            visible.remove(&id);
+            if visible != player.visible {
+                changes.push((id, visible));
+            }
+        }
+
+        for (id, visible) in changes {
+            let before = std::mem::take(&mut self.players.get_mut(&id).unwrap().visible);
+            for &entered in visible.difference(&before) {
+                if let Some(snapshot) = self.player_snapshot(entered) {
+                    self.outgoing.push(Outgoing {
+                        to: Recipient::Player(id),
+                        message: ServerMessage::EntityEntered(snapshot),
+                    });
+                }
+            }
+            for &left in before.difference(&visible) {
+                self.outgoing.push(Outgoing {
+                    to: Recipient::Player(id),
+                    message: ServerMessage::EntityLeft { id: left },
+                });
+            }
+            self.players.get_mut(&id).unwrap().visible = visible;
+        }
+    }
+
+    fn player_snapshot(&self, id: PlayerId) -> Option<PlayerSnapshot> {
+        let player = self.players.get(&id)?;
+        Some(PlayerSnapshot {
+            id,
+            name: player.state.player_name.clone().unwrap_or_default(),
+            x: player.state.player_x,
+            y: player.state.player_y,
+        })
+    }
+
+    /// The world as `id` should see it this tick: themselves and the
+    /// players in their area of interest
+    pub fn snapshot_for(&self, id: PlayerId) -> Option<WorldSnapshot> {
+        let player = self.players.get(&id)?;
+        let mut ids: BTreeSet<PlayerId> = player.visible.clone();
+        ids.insert(id);
+        Some(WorldSnapshot {
+            tick: self.tick,
+            last_processed_input: player.last_processed_input,
+            players: ids
+                .into_iter()
+                .filter_map(|id| self.player_snapshot(id))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{WordListFilter, CHAT_RATE_LIMIT};
+    use crate::game_state::STARTING_GOLD;
+    use crate::inventory::ItemStack;
+    use crate::prediction::MAX_INPUT_STEP;
+    use crate::profile::PlayerClass;
+    use crate::trade::TradeOffer;
+
+    fn profile(name: &str) -> PlayerProfile {
+        PlayerProfile::new(name, PlayerClass::Warrior, 0).unwrap()
+    }
+
+    fn input(seq: u32, dx: f64, dy: f64) -> ClientMessage {
+        ClientMessage::Input(PlayerInput { seq, dx, dy })
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut world = ServerWorld::new(ServerConfig {
+            max_players: 1,
+            ..ServerConfig::default()
+        });
+
+        let id = world.join(profile("Hero"), Region::EU).unwrap();
+        assert_eq!(
+            world.join(profile("Other"), Region::EU),
+            Err(DisconnectReason::ServerFull)
+        );
+
+        assert!(world.leave(id));
+        assert!(!world.leave(id));
+        assert!(matches!(
+            world.join(
+                PlayerProfile {
+                    name: "admin".to_string(),
+                    class: PlayerClass::Mage,
+                    appearance: 0,
+                },
+                Region::EU
+            ),
+            Err(DisconnectReason::InvalidProfile(_))
+        ));
+        assert_eq!(world.player_count(), 0);
+    }
+
+    #[test]
+    fn test_inputs_are_applied_once_per_tick() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let id = world.join(profile("Hero"), Region::EU).unwrap();
+        let start = world.snapshot_for(id).unwrap().players[0].clone();
+
+        world.handle(id, input(2, 5.0, 0.0));
+        world.handle(id, input(1, 5.0, 0.0));
+        assert_eq!(world.step(), 1);
+
+        let snapshot = world.snapshot_for(id).unwrap();
+        assert_eq!(snapshot.tick, 1);
+        assert_eq!(snapshot.last_processed_input, 2);
+        assert_eq!(snapshot.players[0].x, start.x + 10.0);
+
+        // Replayed and stale inputs don't move the player again
+        world.handle(id, input(2, 5.0, 0.0));
+        world.step();
+        assert_eq!(world.snapshot_for(id).unwrap().players[0].x, start.x + 10.0);
+    }
+
+    #[test]
+    fn test_inputs_are_clamped() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let id = world.join(profile("Hero"), Region::EU).unwrap();
+        let start = world.snapshot_for(id).unwrap().players[0].clone();
+
+        world.handle(id, input(1, 500.0, f64::NAN));
+        world.step();
+
+        let moved = &world.snapshot_for(id).unwrap().players[0];
+        assert_eq!((moved.x, moved.y), (start.x + MAX_INPUT_STEP, start.y));
+    }
+
+    #[test]
+    fn test_flooding_inputs_does_not_speed_up() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let id = world.join(profile("Hero"), Region::EU).unwrap();
+        let start = world.snapshot_for(id).unwrap().players[0].clone();
+
+        for seq in 1..=MAX_PENDING_INPUTS as u32 + 1 {
+            world.handle(id, input(seq, MAX_INPUT_STEP, 0.0));
+        }
+        world.step();
+
+        let snapshot = world.snapshot_for(id).unwrap();
+        let moved = snapshot.players[0].x - start.x;
+        assert_eq!(moved, world.max_move_per_tick());
+        assert_eq!(snapshot.last_processed_input, 3);
+
+        // The rest wait their turn rather than being lost
+        world.step();
+        let snapshot = world.snapshot_for(id).unwrap();
+        assert_eq!(
+            snapshot.players[0].x - start.x,
+            2.0 * world.max_move_per_tick()
+        );
+        assert_eq!(snapshot.last_processed_input, 6);
+    }
+
+    #[test]
+    fn test_snapshots_include_every_player() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+        let other = world.join(profile("Other"), Region::Asia).unwrap();
+
+        world.handle(other, input(7, 0.0, -5.0));
+        world.step();
+
+        let snapshot = world.snapshot_for(hero).unwrap();
+        assert_eq!(snapshot.last_processed_input, 0);
+        let names: Vec<&str> = snapshot.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Hero", "Other"]);
+        assert_eq!(world.snapshot_for(other).unwrap().last_processed_input, 7);
+        assert_eq!(world.snapshot_for(99), None);
+    }
+
+    #[test]
+    fn test_area_of_interest_enter_and_leave() {
+        let mut world = ServerWorld::new(ServerConfig {
+            world_width: 10_000.0,
+            world_height: 10_000.0,
+            interest: InterestConfig {
+                cell_size: 50.0,
+                view_width: 100.0,
+                view_height: 100.0,
+                margin: 10.0,
+            },
+            ..ServerConfig::default()
+        });
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+        let other = world.join(profile("Other"), Region::EU).unwrap();
+        // Both were sent their belongings on joining
+        assert_eq!(world.take_outgoing().len(), 2);
+        let mut seq = 0;
+        let mut walk = |world: &mut ServerWorld, distance: f64| {
+            for _ in 0..(distance.abs() / MAX_INPUT_STEP) as u32 {
+                seq += 1;
+                world.handle(other, input(seq, MAX_INPUT_STEP.copysign(distance), 0.0));
+            }
+            let ticks = (distance.abs() / world.max_move_per_tick()).ceil().max(1.0);
+            for _ in 0..ticks as u32 {
+                world.step();
+            }
+            world.take_outgoing()
+        };
+
+        let entered = walk(&mut world, 0.0);
+        assert_eq!(entered.len(), 2);
+        assert!(entered.iter().any(|out| out.to == Recipient::Player(hero)
+            && matches!(&out.message, ServerMessage::EntityEntered(p) if p.id == other)));
+
+        // Past the margin but not twice the margin: still visible
+        assert!(walk(&mut world, 65.0).is_empty());
+        assert_eq!(world.visible_to(hero), Some(&BTreeSet::from([other])));
+
+        let left = walk(&mut world, 10.0);
+        assert!(left.contains(&Outgoing {
+            to: Recipient::Player(hero),
+            message: ServerMessage::EntityLeft { id: other },
+        }));
+        let snapshot = world.snapshot_for(hero).unwrap();
+        assert_eq!(snapshot.players.len(), 1);
+
+        assert_eq!(walk(&mut world, -20.0).len(), 2);
+
+        // Leaving the server counts as leaving the area
+        world.leave(other);
+        world.step();
+        assert_eq!(
+            world.take_outgoing(),
+            vec![Outgoing {
+                to: Recipient::Player(hero),
+                message: ServerMessage::EntityLeft { id: other },
+            }]
+        );
+    }
+
+    fn chat(
+        world: &mut ServerWorld,
+        id: PlayerId,
+        channel: ChatChannel,
+        text: &str,
+    ) -> Vec<Outgoing> {
+        world.handle(
+            id,
+            ClientMessage::Chat {
+                channel,
+                text: text.to_string(),
+            },
+        )
+    }
+
+    fn recipients(outgoing: &[Outgoing]) -> Vec<Recipient> {
+        outgoing.iter().map(|out| out.to).collect()
+    }
+
+    fn rejection(outgoing: &[Outgoing]) -> Option<&str> {
+        match &outgoing.first()?.message {
+            ServerMessage::ChatRejected { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_chat_channels_reach_the_right_players() {
+        let mut world = ServerWorld::new(ServerConfig {
+            world_width: 10_000.0,
+            world_height: 10_000.0,
+            ..ServerConfig::default()
+        });
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+        let near = world.join(profile("Near"), Region::EU).unwrap();
+        let far = world.join(profile("Far"), Region::EU).unwrap();
+        world
+            .players
+            .get_mut(&far)
+            .unwrap()
+            .state
+            .set_player_position(9_000.0, 9_000.0);
+        world.step();
+
+        let local = chat(&mut world, hero, ChatChannel::Local, "hello");
+        assert_eq!(
+            recipients(&local),
+            [Recipient::Player(hero), Recipient::Player(near)]
+        );
+        assert!(matches!(
+            &local[0].message,
+            ServerMessage::Chat { name, text, .. } if name == "Hero" && text == "hello"
+        ));
+
+        world
+            .players
+            .get_mut(&near)
+            .unwrap()
+            .state
+            .enter_zone("Dark Forest");
+        let zone = chat(&mut world, hero, ChatChannel::Zone, "zone");
+        assert_eq!(
+            recipients(&zone),
+            [Recipient::Player(hero), Recipient::Player(far)]
+        );
+
+        let global = chat(&mut world, hero, ChatChannel::Global, "all");
+        assert_eq!(recipients(&global), [Recipient::Everyone]);
+
+        let whisper = chat(
+            &mut world,
+            hero,
+            ChatChannel::Whisper {
+                to: "far".to_string(),
+            },
+            "psst",
+        );
+        assert_eq!(
+            recipients(&whisper),
+            [Recipient::Player(far), Recipient::Player(hero)]
+        );
+    }
+
+    #[test]
+    fn test_chat_rejections_go_back_to_the_sender() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+
+        let unknown = ChatChannel::Whisper {
+            to: "Nobody".to_string(),
+        };
+        let rejected = chat(&mut world, hero, unknown, "hi");
+        assert_eq!(recipients(&rejected), [Recipient::Player(hero)]);
+        assert_eq!(rejection(&rejected), Some("chat_unknown_recipient"));
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Global, "  ")),
+            Some("chat_empty")
+        );
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Party, "hi")),
+            Some("chat_not_in_party")
+        );
+
+        // Rejected messages don't use up the rate limit
+        for _ in 0..CHAT_RATE_LIMIT {
+            assert_eq!(
+                rejection(&chat(&mut world, hero, ChatChannel::Global, "hi")),
+                None
+            );
+        }
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Global, "hi")),
+            Some("chat_rate_limited")
+        );
+
+        // The window is measured in server time
+        for _ in 0..world.config().tick_rate * 5 {
+            world.step();
+        }
+        world.set_chat_filter(Box::new(WordListFilter::new(["darn"])));
+        let filtered = chat(&mut world, hero, ChatChannel::Global, "darn");
+        assert!(matches!(&filtered[0].message, ServerMessage::Chat { text, .. } if text == "****"));
+
+        world.set_muted("Hero", true);
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Global, "hi")),
+            Some("chat_muted")
+        );
+    }
+
+    #[test]
+    fn test_mute_survives_reconnect() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+        world.set_muted("hero", true);
+
+        world.leave(hero);
+        let hero = world.join(profile("Hero"), Region::EU).unwrap();
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Global, "hi")),
+            Some("chat_muted")
+        );
+
+        world.set_muted("Hero", false);
+        assert_eq!(
+            rejection(&chat(&mut world, hero, ChatChannel::Global, "hi")),
+            None
+        );
+    }
+
+    /// Several clients joined to one world, each keeping what it was sent
+    struct SimulatedClients {
+        world: ServerWorld,
+        inboxes: BTreeMap<PlayerId, Vec<ServerMessage>>,
+    }
+
+    impl SimulatedClients {
+        fn new() -> Self {
+            Self {
+                world: ServerWorld::new(ServerConfig::default()),
+                inboxes: BTreeMap::new(),
+            }
+        }
+
+        fn join(&mut self, name: &str) -> PlayerId {
+            let id = self.world.join(profile(name), Region::EU).unwrap();
+            self.inboxes.insert(id, Vec::new());
+            id
+        }
+
+        fn deliver(&mut self, outgoing: Vec<Outgoing>) {
+            for out in outgoing {
+                match out.to {
+                    Recipient::Player(id) => {
+                        if let Some(inbox) = self.inboxes.get_mut(&id) {
+                            inbox.push(out.message);
+                        }
+                    }
+                    Recipient::Everyone => {
+                        for inbox in self.inboxes.values_mut() {
+                            inbox.push(out.message.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        fn party(&mut self, id: PlayerId, action: PartyAction) {
+            let outgoing = self.world.handle(id, ClientMessage::Party(action));
+            self.deliver(outgoing);
+        }
+
+        fn step(&mut self) {
+            self.world.step();
+            let outgoing = self.world.take_outgoing();
+            self.deliver(outgoing);
+        }
+
+        fn take(&mut self, id: PlayerId) -> Vec<ServerMessage> {
+            std::mem::take(self.inboxes.get_mut(&id).unwrap())
+        }
+
+        /// The party frame `id` was last sent, `Some(None)` meaning "no party"
+        fn party_frame(&self, id: PlayerId) -> Option<Option<PartyView>> {
+            self.inboxes[&id]
+                .iter()
+                .rev()
+                .find_map(|message| match message {
+                    ServerMessage::PartyUpdate(view) => Some(view.clone()),
+                    _ => None,
+                })
+        }
+
+        fn member_ids(&self, id: PlayerId) -> Vec<PlayerId> {
+            self.party_frame(id)
+                .flatten()
+                .map(|view| view.members.iter().map(|member| member.id).collect())
+                .unwrap_or_default()
+        }
+
+        fn rejection(&mut self, id: PlayerId) -> Option<String> {
+            self.take(id).into_iter().find_map(|message| match message {
+                ServerMessage::PartyRejected { code, .. }
+                | ServerMessage::TradeRejected { code, .. }
+                | ServerMessage::CommandRejected { code, .. } => Some(code),
+                _ => None,
+            })
+        }
+
+        fn send(&mut self, id: PlayerId, message: ClientMessage) {
+            let outgoing = self.world.handle(id, message);
+            self.deliver(outgoing);
+        }
+
+        /// The belongings `id` was last sent
+        fn belongings(&self, id: PlayerId) -> Option<Belongings> {
+            self.inboxes[&id]
+                .iter()
+                .rev()
+                .find_map(|message| match message {
+                    ServerMessage::Belongings(belongings) => Some(belongings.clone()),
+                    _ => None,
+                })
+        }
+
+        fn trade(&mut self, id: PlayerId, action: TradeAction) {
+            let outgoing = self.world.handle(id, ClientMessage::Trade(action));
+            self.deliver(outgoing);
+        }
+
+        /// The trade window `id` was last sent, `Some(None)` meaning "closed"
+        fn trade_frame(&self, id: PlayerId) -> Option<Option<TradeView>> {
+            self.inboxes[&id]
+                .iter()
+                .rev()
+                .find_map(|message| match message {
+                    ServerMessage::TradeUpdate(view) => Some(view.clone()),
+                    _ => None,
+                })
+        }
+
+        fn give(&mut self, id: PlayerId, gold: u32, capacity: usize, items: &[(&str, u32)]) {
+            let mut inventory = Inventory::new(capacity);
+            for &(item_id, quantity) in items {
+                inventory.add(item_id, quantity).unwrap();
+            }
+            assert!(self
+                .world
+                .set_belongings(id, Belongings { gold, inventory }));
+        }
+
+        fn state(&self, id: PlayerId) -> &GameState {
+            &self.world.players[&id].state
+        }
+
+        /// Alice and Bob in a trade, Alice offering ore and Bob gold
+        fn trading() -> (Self, PlayerId, PlayerId) {
+            let mut clients = Self::new();
+            let alice = clients.join("Alice");
+            let bob = clients.join("Bob");
+            clients.give(alice, 0, 4, &[("iron_ore", 10)]);
+            clients.give(bob, 50, 4, &[]);
+
+            clients.trade(
+                alice,
+                TradeAction::Request {
+                    name: "Bob".to_string(),
+                },
+            );
+            clients.trade(bob, TradeAction::Accept);
+            clients.trade(alice, TradeAction::Offer(offer(&[("iron_ore", 4)], 0)));
+            clients.trade(bob, TradeAction::Offer(offer(&[], 30)));
+            (clients, alice, bob)
+        }
+    }
+
+    fn offer(items: &[(&str, u32)], gold: u32) -> TradeOffer {
+        TradeOffer {
+            items: items
+                .iter()
+                .map(|&(item_id, quantity)| ItemStack {
+                    item_id: item_id.to_string(),
+                    quantity,
+                })
+                .collect(),
+            gold,
+        }
+    }
+
+    #[test]
+    fn test_party_invite_and_accept() {
+        let mut clients = SimulatedClients::new();
+        let alice = clients.join("Alice");
+        let bob = clients.join("Bob");
+        let carol = clients.join("Carol");
+        clients.step();
+
+        clients.party(
+            alice,
+            PartyAction::Invite {
+                name: "bob".to_string(),
+            },
+        );
+        assert!(clients.take(bob).contains(&ServerMessage::PartyInvite {
+            from: alice,
+            name: "Alice".to_string(),
+        }));
+
+        clients.party(bob, PartyAction::Accept);
+        for id in [alice, bob] {
+            let frame = clients.party_frame(id).flatten().unwrap();
+            assert_eq!(frame.leader, alice);
+            assert_eq!(clients.member_ids(id), [alice, bob]);
+        }
+        assert_eq!(clients.party_frame(carol), None);
+
+        clients.party(
+            carol,
+            PartyAction::Invite {
+                name: "Alice".to_string(),
+            },
+        );
+        assert_eq!(
+            clients.rejection(carol).as_deref(),
+            Some("party_target_in_party")
+        );
+        clients.party(
+            alice,
+            PartyAction::Invite {
+                name: "Nobody".to_string(),
+            },
+        );
+        assert_eq!(
+            clients.rejection(alice).as_deref(),
+            Some("party_unknown_player")
+        );
+
+        clients.party(
+            alice,
+            PartyAction::Invite {
+                name: "Carol".to_string(),
+            },
+        );
+        clients.party(carol, PartyAction::Decline);
+        clients.party(carol, PartyAction::Accept);
+        assert_eq!(clients.rejection(carol).as_deref(), Some("party_no_invite"));
+    }
+
+    #[test]
+    fn test_party_leader_transfer_and_kick() {
+        let mut clients = SimulatedClients::new();
+        let alice = clients.join("Alice");
+        let bob = clients.join("Bob");
+        let carol = clients.join("Carol");
+        for name in ["Bob", "Carol"] {
+            clients.party(
+                alice,
+                PartyAction::Invite {
+                    name: name.to_string(),
+                },
+            );
+        }
+        clients.party(bob, PartyAction::Accept);
+        clients.party(carol, PartyAction::Accept);
+        assert_eq!(clients.member_ids(carol), [alice, bob, carol]);
+
+        clients.party(bob, PartyAction::Kick { id: carol });
+        assert_eq!(clients.rejection(bob).as_deref(), Some("party_not_leader"));
+
+        clients.party(alice, PartyAction::Promote { id: bob });
+        assert_eq!(clients.party_frame(carol).flatten().unwrap().leader, bob);
+
+        clients.party(bob, PartyAction::Kick { id: carol });
+        assert_eq!(clients.party_frame(carol), Some(None));
+        assert_eq!(clients.member_ids(alice), [alice, bob]);
+
+        // The last member leaving the server disbands the party
+        clients.world.leave(bob);
+        clients.inboxes.remove(&bob);
+        clients.step();
+        assert_eq!(clients.party_frame(alice), Some(None));
+    }
+
+    #[test]
+    fn test_party_frame_follows_member_hp() {
+        let mut clients = SimulatedClients::new();
+        let alice = clients.join("Alice");
+        let bob = clients.join("Bob");
+        clients.party(
+            alice,
+            PartyAction::Invite {
+                name: "Bob".to_string(),
+            },
+        );
+        clients.party(bob, PartyAction::Accept);
+        clients.step();
+        clients.take(alice);
+
+        // Nothing changed, nothing sent
+        clients.step();
+        assert!(clients.party_frame(alice).is_none());
+
+        clients
+            .world
+            .players
+            .get_mut(&bob)
+            .unwrap()
+            .state
+            .set_player_hp(35.0);
+        clients.step();
+        let frame = clients.party_frame(alice).flatten().unwrap();
+        assert_eq!(frame.members[1].hp, 35.0);
+        assert_eq!(frame.members[1].max_hp, 100.0);
+    }
+
+    #[test]
+    fn test_party_xp_is_shared_within_zone() {
+        let mut clients = SimulatedClients::new();
+        let alice = clients.join("Alice");
+        let bob = clients.join("Bob");
+        let carol = clients.join("Carol");
+        for name in ["Bob", "Carol"] {
+            clients.party(
+                alice,
+                PartyAction::Invite {
+                    name: name.to_string(),
+                },
+            );
+        }
+        clients.party(bob, PartyAction::Accept);
+        clients.party(carol, PartyAction::Accept);
+        clients
+            .world
+            .players
+            .get_mut(&carol)
+            .unwrap()
+            .state
+            .enter_zone("Dark Forest");
+        for id in [alice, bob, carol] {
+            clients.take(id);
+        }
+
+        clients.world.award_xp(bob, 200);
+        clients.step();
+        // Two sharing: 200 plus a 10% bonus, split evenly
+        for id in [alice, bob] {
+            assert!(clients
+                .take(id)
+                .contains(&ServerMessage::XpGained { amount: 110 }));
+        }
+        // Carol only hears that her teammates levelled up
+        assert!(!clients
+            .take(carol)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::XpGained { .. })));
+
+        let frame = clients.world.party_view(carol).unwrap();
+        let levels: Vec<u32> = frame.members.iter().map(|member| member.level).collect();
+        assert_eq!(levels, [2, 2, 1]);
+    }
+
+    #[test]
+    fn test_party_chat_reaches_members_only() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let alice = world.join(profile("Alice"), Region::EU).unwrap();
+        let bob = world.join(profile("Bob"), Region::EU).unwrap();
+        world.join(profile("Carol"), Region::EU).unwrap();
+        world.handle(
+            alice,
+            ClientMessage::Party(PartyAction::Invite {
+                name: "Bob".to_string(),
+            }),
+        );
+        world.handle(bob, ClientMessage::Party(PartyAction::Accept));
+
+        let sent = chat(&mut world, bob, ChatChannel::Party, "hi team");
+        assert_eq!(
+            recipients(&sent),
+            [Recipient::Player(alice), Recipient::Player(bob)]
+        );
+    }
+
+    #[test]
+    fn test_trade_swaps_after_both_lock_and_confirm() {
+        let (mut clients, alice, bob) = SimulatedClients::trading();
+        let frame = clients.trade_frame(bob).flatten().unwrap();
+        assert_eq!(frame.partner_name, "Alice");
+        assert_eq!(frame.theirs.offer, offer(&[("iron_ore", 4)], 0));
+        assert_eq!(frame.mine.offer.gold, 30);
+        assert!(clients.take(bob).contains(&ServerMessage::TradeRequest {
+            from: alice,
+            name: "Alice".to_string(),
+        }));
+
+        // Confirming needs both sides locked
+        clients.trade(alice, TradeAction::Confirm);
+        assert_eq!(
+            clients.rejection(alice).as_deref(),
+            Some("trade_not_locked")
+        );
+        for id in [alice, bob] {
+            clients.trade(id, TradeAction::Lock);
+        }
+        clients.trade(alice, TradeAction::Confirm);
+        assert_eq!(clients.state(alice).inventory.count("iron_ore"), 10);
+        clients.take(alice);
+        clients.trade(bob, TradeAction::Confirm);
+
+        assert!(clients
+            .take(alice)
+            .contains(&ServerMessage::TradeCompleted {
+                gave: offer(&[("iron_ore", 4)], 0),
+                received: offer(&[], 30),
+            }));
+        assert_eq!(clients.trade_frame(bob), Some(None));
+        assert_eq!(clients.belongings(bob).unwrap().gold, 20);
+        assert_eq!(clients.state(alice).inventory.count("iron_ore"), 6);
+        assert_eq!(clients.state(alice).gold, 30);
+        assert_eq!(clients.state(bob).inventory.count("iron_ore"), 4);
+        assert_eq!(clients.state(bob).gold, 20);
+    }
+
+    #[test]
+    fn test_trade_changes_reset_confirmation() {
+        let (mut clients, alice, bob) = SimulatedClients::trading();
+        for id in [alice, bob] {
+            clients.trade(id, TradeAction::Lock);
+        }
+        clients.trade(alice, TradeAction::Confirm);
+        assert!(clients.trade_frame(bob).flatten().unwrap().theirs.confirmed);
+
+        // Locked offers can't change until unlocked, which clears Alice's confirmation
+        clients.trade(bob, TradeAction::Offer(offer(&[], 10)));
+        assert_eq!(
+            clients.rejection(bob).as_deref(),
+            Some("trade_offer_locked")
+        );
+        clients.trade(bob, TradeAction::Unlock);
+        clients.trade(bob, TradeAction::Offer(offer(&[], 10)));
+        clients.trade(bob, TradeAction::Lock);
+        clients.trade(bob, TradeAction::Confirm);
+
+        let frame = clients.trade_frame(alice).flatten().unwrap();
+        assert!(!frame.mine.confirmed);
+        assert_eq!(frame.theirs.offer.gold, 10);
+        assert_eq!(clients.state(alice).gold, 0);
+
+        // Offers are checked against what the player owns
+        clients.trade(bob, TradeAction::Unlock);
+        clients.trade(bob, TradeAction::Offer(offer(&[("gem", 1)], 0)));
+        assert_eq!(
+            clients.rejection(bob).as_deref(),
+            Some("trade_missing_items")
+        );
+    }
+
+    #[test]
+    fn test_trade_without_space_changes_nothing() {
+        let (mut clients, alice, bob) = SimulatedClients::trading();
+        // Bob's single slot is full of coal, so the ore has nowhere to go
+        clients.give(bob, 50, 1, &[("coal", 99)]);
+        for id in [alice, bob] {
+            clients.trade(id, TradeAction::Lock);
+        }
+        clients.trade(alice, TradeAction::Confirm);
+        clients.trade(bob, TradeAction::Confirm);
+        clients.step();
+
+        for id in [alice, bob] {
+            assert_eq!(
+                clients
+                    .trade_frame(id)
+                    .flatten()
+                    .map(|frame| frame.mine.locked),
+                Some(false)
+            );
+            assert_eq!(
+                clients.rejection(id).as_deref(),
+                Some("trade_not_enough_space")
+            );
+        }
+        assert_eq!(clients.state(alice).inventory.count("iron_ore"), 10);
+        assert_eq!(clients.state(bob).inventory.count("iron_ore"), 0);
+        assert_eq!(
+            (clients.state(alice).gold, clients.state(bob).gold),
+            (0, 50)
+        );
+    }
+
+    #[test]
+    fn test_belongings_live_on_the_server() {
+        let mut clients = SimulatedClients::new();
+        let hero = clients.join("Hero");
+        clients.step();
+        assert_eq!(clients.belongings(hero).unwrap().gold, STARTING_GOLD);
+
+        clients.send(
+            hero,
+            ClientMessage::Buy {
+                item_id: "health_potion".to_string(),
+                quantity: 2,
+            },
+        );
+        let bought = clients.belongings(hero).unwrap();
+        assert_eq!(bought.gold, STARTING_GOLD - 20);
+        assert_eq!(bought.inventory.count("health_potion"), 2);
+
+        clients.send(
+            hero,
+            ClientMessage::Craft {
+                recipe_id: "iron_sword".to_string(),
+                station: None,
+            },
+        );
+        assert_eq!(clients.rejection(hero).as_deref(), Some("craft_failed"));
+
+        // Only one session per name, and taking a name someone left with
+        // doesn't hand over what they owned
+        assert_eq!(
+            clients.world.join(profile("HERO"), Region::EU),
+            Err(DisconnectReason::NameTaken)
+        );
+        clients.world.leave(hero);
+        let hero = clients.join("hero");
+        clients.step();
+        let fresh = clients.belongings(hero).unwrap();
+        assert_eq!(fresh.gold, STARTING_GOLD);
+        assert_eq!(fresh.inventory.count("health_potion"), 0);
+    }
+
+    #[test]
+    fn test_trade_ends_when_a_player_leaves() {
+        let (mut clients, alice, bob) = SimulatedClients::trading();
+        clients.world.leave(alice);
+        clients.step();
+        assert_eq!(clients.trade_frame(bob), Some(None));
+
+        clients.trade(bob, TradeAction::Lock);
+        assert_eq!(clients.rejection(bob).as_deref(), Some("trade_not_trading"));
+    }
+
+    #[test]
+    fn test_ping_and_leave_messages() {
+        let mut world = ServerWorld::new(ServerConfig::default());
+        let id = world.join(profile("Hero"), Region::EU).unwrap();
+
+        let pong = world.handle(id, ClientMessage::Ping { sent_at_ms: 12.5 });
+        assert_eq!(
+            pong,
+            vec![Outgoing {
+                to: Recipient::Player(id),
+                message: ServerMessage::Pong { sent_at_ms: 12.5 },
+            }]
+        );
+
+        world.handle(id, ClientMessage::Leave);
+        assert_eq!(world.player_count(), 0);
+    }
+}
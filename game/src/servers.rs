@@ -0,0 +1,325 @@
+use crate::game_state::Region;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Server manifest bundled with the client
+const DEFAULT_MANIFEST: &str = include_str!("../manifests/servers.json");
+
+/// Manifest format this build understands
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Latency samples kept per server; the median of these is used
+pub const LATENCY_SAMPLES: usize = 5;
+
+/// A game server players can connect to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub id: String,
+    pub endpoint: String,
+    pub capacity: u32,
+    /// Players currently online, if the manifest reports it
+    #[serde(default)]
+    pub players: u32,
+}
+
+impl ServerInfo {
+    pub fn is_full(&self) -> bool {
+        self.players >= self.capacity
+    }
+}
+
+/// Servers hosted in one region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionServers {
+    pub region: Region,
+    pub servers: Vec<ServerInfo>,
+}
+
+/// Reasons a server manifest is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirectoryError {
+    Malformed(String),
+    UnsupportedVersion(u32),
+    DuplicateServer(String),
+}
+
+impl fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectoryError::Malformed(reason) => write!(f, "Server list is invalid: {reason}"),
+            DirectoryError::UnsupportedVersion(version) => {
+                write!(f, "Server list version {version} is not supported")
+            }
+            DirectoryError::DuplicateServer(id) => write!(f, "Server {id} is listed twice"),
+        }
+    }
+}
+
+impl std::error::Error for DirectoryError {}
+
+/// Measures round-trip time to a server
+pub trait LatencyProbe {
+    /// Latency in milliseconds, or `None` if the server couldn't be reached
+    fn measure(&mut self, server: &ServerInfo) -> Option<f64>;
+}
+
+/// Fixed latencies for native builds and tests
+#[derive(Debug, Clone, Default)]
+pub struct MockLatencyProbe {
+    latencies: HashMap<String, f64>,
+}
+
+impl MockLatencyProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_latency(mut self, server_id: &str, latency_ms: f64) -> Self {
+        self.latencies.insert(server_id.to_string(), latency_ms);
+        self
+    }
+}
+
+impl LatencyProbe for MockLatencyProbe {
+    fn measure(&mut self, server: &ServerInfo) -> Option<f64> {
+        self.latencies.get(&server.id).copied()
+    }
+}
+
+/// Latencies measured by the browser and reported back to the game, since
+/// pinging from the page is asynchronous
+#[derive(Debug, Clone, Default)]
+pub struct RecordedLatencies {
+    samples: HashMap<String, VecDeque<f64>>,
+}
+
+impl RecordedLatencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample, dropping the oldest past `LATENCY_SAMPLES`
+    pub fn record(&mut self, server_id: &str, latency_ms: f64) {
+        if !latency_ms.is_finite() || latency_ms < 0.0 {
+            return;
+        }
+        let samples = self.samples.entry(server_id.to_string()).or_default();
+        if samples.len() >= LATENCY_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(latency_ms);
+    }
+
+    /// Median of the recent samples, so one slow ping doesn't skew it
+    pub fn median(&self, server_id: &str) -> Option<f64> {
+        let mut samples: Vec<f64> = self.samples.get(server_id)?.iter().copied().collect();
+        samples.sort_by(f64::total_cmp);
+        samples.get(samples.len() / 2).copied()
+    }
+}
+
+impl LatencyProbe for RecordedLatencies {
+    fn measure(&mut self, server: &ServerInfo) -> Option<f64> {
+        self.median(&server.id)
+    }
+}
+
+/// A server as shown on the server selection screen
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct ServerStatus {
+    pub id: String,
+    pub region: Region,
+    pub endpoint: String,
+    pub latency_ms: Option<f64>,
+    pub full: bool,
+}
+
+/// Every known server, grouped by region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerDirectory {
+    pub version: u32,
+    pub regions: Vec<RegionServers>,
+}
+
+impl ServerDirectory {
+    /// Parse and check a manifest
+    pub fn from_json(json: &str) -> Result<Self, DirectoryError> {
+        let directory: Self =
+            serde_json::from_str(json).map_err(|e| DirectoryError::Malformed(e.to_string()))?;
+        if directory.version != MANIFEST_VERSION {
+            return Err(DirectoryError::UnsupportedVersion(directory.version));
+        }
+
+        let mut seen = HashSet::new();
+        for (_, server) in directory.servers() {
+            if !seen.insert(server.id.as_str()) {
+                return Err(DirectoryError::DuplicateServer(server.id.clone()));
+            }
+        }
+        Ok(directory)
+    }
+
+    /// Directory from the manifest bundled with the client
+    pub fn with_default_servers() -> Self {
+        Self::from_json(DEFAULT_MANIFEST).expect("bundled server manifest is valid")
+    }
+
+    /// All servers with their region
+    pub fn servers(&self) -> impl Iterator<Item = (&Region, &ServerInfo)> {
+        self.regions.iter().flat_map(|group| {
+            group
+                .servers
+                .iter()
+                .map(move |server| (&group.region, server))
+        })
+    }
+
+    pub fn get(&self, server_id: &str) -> Option<&ServerInfo> {
+        self.servers()
+            .map(|(_, server)| server)
+            .find(|server| server.id == server_id)
+    }
+
+    /// Measure every server with `probe`
+    pub fn statuses(&self, probe: &mut impl LatencyProbe) -> Vec<ServerStatus> {
+        self.servers()
+            .map(|(region, server)| ServerStatus {
+                id: server.id.clone(),
+                region: region.clone(),
+                endpoint: server.endpoint.clone(),
+                latency_ms: probe.measure(server),
+                full: server.is_full(),
+            })
+            .collect()
+    }
+
+    /// The server to join in `region`: the fastest one that isn't full, or
+    /// the first one listed if none has been measured yet
+    pub fn server_for_region(
+        &self,
+        region: &Region,
+        probe: &mut impl LatencyProbe,
+    ) -> Option<ServerStatus> {
+        let candidates: Vec<ServerStatus> = self
+            .statuses(probe)
+            .into_iter()
+            .filter(|status| status.region == *region && !status.full)
+            .collect();
+        candidates
+            .iter()
+            .filter(|status| status.latency_ms.is_some())
+            .min_by(|a, b| a.latency_ms.unwrap().total_cmp(&b.latency_ms.unwrap()))
+            .or(candidates.first())
+            .cloned()
+    }
+
+    /// The reachable, non-full server with the lowest latency; its region is
+    /// the one to auto-select
+    pub fn best_server(&self, probe: &mut impl LatencyProbe) -> Option<ServerStatus> {
+        self.statuses(probe)
+            .into_iter()
+            .filter(|status| !status.full && status.latency_ms.is_some())
+            .min_by(|a, b| a.latency_ms.unwrap().total_cmp(&b.latency_ms.unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_manifest_covers_every_region() {
+        let directory = ServerDirectory::with_default_servers();
+
+        for region in [Region::EU, Region::Asia, Region::Vietnam] {
+            assert!(
+                directory.servers().any(|(r, _)| *r == region),
+                "no servers for {region:?}"
+            );
+        }
+        assert!(directory.get("eu-1").is_some());
+    }
+
+    #[test]
+    fn test_rejects_bad_manifests() {
+        assert!(matches!(
+            ServerDirectory::from_json("{}"),
+            Err(DirectoryError::Malformed(_))
+        ));
+        assert_eq!(
+            ServerDirectory::from_json(r#"{"version": 9, "regions": []}"#),
+            Err(DirectoryError::UnsupportedVersion(9))
+        );
+
+        let duplicate = r#"{"version": 1, "regions": [
+            {"region": "EU", "servers": [{"id": "a", "endpoint": "wss://a", "capacity": 1}]},
+            {"region": "Asia", "servers": [{"id": "a", "endpoint": "wss://b", "capacity": 1}]}
+        ]}"#;
+        assert_eq!(
+            ServerDirectory::from_json(duplicate),
+            Err(DirectoryError::DuplicateServer("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_best_server_skips_unreachable_and_full() {
+        let mut directory = ServerDirectory::with_default_servers();
+        let mut probe = MockLatencyProbe::new()
+            .with_latency("eu-1", 180.0)
+            .with_latency("eu-2", 150.0)
+            .with_latency("vn-1", 20.0);
+
+        assert_eq!(
+            directory.best_server(&mut probe).map(|s| s.region),
+            Some(Region::Vietnam)
+        );
+
+        // The Vietnamese server fills up, so the best EU server wins
+        directory.regions[2].servers[0].players = 300;
+        let best = directory.best_server(&mut probe).unwrap();
+        assert_eq!((best.id.as_str(), best.region), ("eu-2", Region::EU));
+
+        assert_eq!(directory.best_server(&mut MockLatencyProbe::new()), None);
+    }
+
+    #[test]
+    fn test_server_for_region() {
+        let mut directory = ServerDirectory::with_default_servers();
+        let mut probe = MockLatencyProbe::new().with_latency("eu-2", 30.0);
+
+        let pick = |directory: &ServerDirectory, probe: &mut MockLatencyProbe| {
+            directory
+                .server_for_region(&Region::EU, probe)
+                .map(|status| status.id)
+        };
+        assert_eq!(
+            pick(&directory, &mut MockLatencyProbe::new()).as_deref(),
+            Some("eu-1")
+        );
+        assert_eq!(pick(&directory, &mut probe).as_deref(), Some("eu-2"));
+
+        directory.regions[0].servers[1].players = 500;
+        assert_eq!(pick(&directory, &mut probe).as_deref(), Some("eu-1"));
+        directory.regions[0].servers[0].players = 500;
+        assert_eq!(pick(&directory, &mut probe), None);
+    }
+
+    #[test]
+    fn test_recorded_latencies_use_median() {
+        let mut latencies = RecordedLatencies::new();
+        for ms in [40.0, 900.0, 42.0, 41.0] {
+            latencies.record("eu-1", ms);
+        }
+        latencies.record("eu-1", f64::NAN);
+
+        assert_eq!(latencies.median("eu-1"), Some(42.0));
+        assert_eq!(latencies.median("asia-1"), None);
+
+        for _ in 0..LATENCY_SAMPLES {
+            latencies.record("eu-1", 10.0);
+        }
+        assert_eq!(latencies.median("eu-1"), Some(10.0));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::game_state::GameState;
+use crate::inventory::InventoryError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// An item offered by the shop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShopItem {
+    pub item_id: String,
+    pub name: String,
+    pub price: u32,
+}
+
+/// Reasons a purchase can be rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum PurchaseError {
+    UnknownItem(String),
+    InvalidQuantity,
+    NotEnoughGold { cost: u32, available: u32 },
+    InventoryFull { item_id: String },
+}
+
+impl fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PurchaseError::UnknownItem(id) => write!(f, "The shop doesn't sell {id}"),
+            PurchaseError::InvalidQuantity => write!(f, "Quantity must be at least 1"),
+            PurchaseError::NotEnoughGold { cost, available } => {
+                write!(f, "Costs {cost} gold, you have {available}")
+            }
+            PurchaseError::InventoryFull { item_id } => {
+                write!(f, "Not enough inventory space for {item_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PurchaseError {}
+
+/// Items the shop sells, keyed by item id
+#[derive(Debug, Clone, Default)]
+pub struct ShopCatalog {
+    items: HashMap<String, ShopItem>,
+}
+
+impl ShopCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Catalog with the general store's starter stock
+    pub fn with_default_items() -> Self {
+        let mut catalog = Self::new();
+        for (item_id, name, price) in [
+            ("health_potion", "Health Potion", 10),
+            ("iron_ore", "Iron Ore", 5),
+            ("coal", "Coal", 3),
+            ("wood", "Wood", 2),
+            ("cloth", "Cloth", 2),
+        ] {
+            catalog.add_item(ShopItem {
+                item_id: item_id.to_string(),
+                name: name.to_string(),
+                price,
+            });
+        }
+        catalog
+    }
+
+    /// Add or replace an item
+    pub fn add_item(&mut self, item: ShopItem) {
+        self.items.insert(item.item_id.clone(), item);
+    }
+
+    pub fn get(&self, item_id: &str) -> Option<&ShopItem> {
+        self.items.get(item_id)
+    }
+
+    /// All items, cheapest first
+    pub fn items(&self) -> Vec<&ShopItem> {
+        let mut items: Vec<&ShopItem> = self.items.values().collect();
+        items.sort_by(|a, b| a.price.cmp(&b.price).then(a.item_id.cmp(&b.item_id)));
+        items
+    }
+
+    /// Buy items, taking gold and adding to the inventory in one step.
+    /// Returns the total cost; nothing changes if the purchase is rejected.
+    pub fn buy(
+        &self,
+        state: &mut GameState,
+        item_id: &str,
+        quantity: u32,
+    ) -> Result<u32, PurchaseError> {
+        let item = self
+            .get(item_id)
+            .ok_or_else(|| PurchaseError::UnknownItem(item_id.to_string()))?;
+        if quantity == 0 {
+            return Err(PurchaseError::InvalidQuantity);
+        }
+
+        let cost = item.price.saturating_mul(quantity);
+        if cost > state.gold {
+            return Err(PurchaseError::NotEnoughGold {
+                cost,
+                available: state.gold,
+            });
+        }
+        if !state.inventory.can_add(item_id, quantity) {
+            return Err(PurchaseError::InventoryFull {
+                item_id: item_id.to_string(),
+            });
+        }
+
+        state
+            .inventory_mut()
+            .add(item_id, quantity)
+            .map_err(|e| match e {
+                InventoryError::NotEnoughSpace { item_id, .. }
+                | InventoryError::NotEnoughItems { item_id, .. } => {
+                    PurchaseError::InventoryFull { item_id }
+                }
+            })?;
+        state.spend_gold(cost);
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::Inventory;
+
+    #[test]
+    fn test_buy_takes_gold_and_adds_items() {
+        let shop = ShopCatalog::with_default_items();
+        let mut state = GameState::new(800.0, 600.0);
+        state.gold = 50;
+
+        assert_eq!(shop.buy(&mut state, "health_potion", 3), Ok(30));
+        assert_eq!(state.gold, 20);
+        assert_eq!(state.inventory.count("health_potion"), 3);
+    }
+
+    #[test]
+    fn test_rejected_purchases_change_nothing() {
+        let shop = ShopCatalog::with_default_items();
+        let mut state = GameState::new(800.0, 600.0);
+        state.gold = 15;
+
+        assert_eq!(
+            shop.buy(&mut state, "dragon_egg", 1),
+            Err(PurchaseError::UnknownItem("dragon_egg".to_string()))
+        );
+        assert_eq!(
+            shop.buy(&mut state, "coal", 0),
+            Err(PurchaseError::InvalidQuantity)
+        );
+        assert_eq!(
+            shop.buy(&mut state, "health_potion", 2),
+            Err(PurchaseError::NotEnoughGold {
+                cost: 20,
+                available: 15,
+            })
+        );
+
+        state.inventory = Inventory::new(0);
+        assert_eq!(
+            shop.buy(&mut state, "coal", 1),
+            Err(PurchaseError::InventoryFull {
+                item_id: "coal".to_string(),
+            })
+        );
+        assert_eq!(state.gold, 15);
+    }
+
+    #[test]
+    fn test_items_sorted_by_price() {
+        let shop = ShopCatalog::with_default_items();
+        let prices: Vec<u32> = shop.items().iter().map(|item| item.price).collect();
+
+        let mut sorted = prices.clone();
+        sorted.sort_unstable();
+        assert_eq!(prices, sorted);
+    }
+}
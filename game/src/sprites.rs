@@ -0,0 +1,536 @@
+use crate::protocol::PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const DEFAULT_ATLAS: &str = include_str!("../manifests/sprites.json");
+
+/// Skin drawn for the local player
+pub const PLAYER_SKIN: &str = "hero";
+
+/// Skin drawn for other players
+pub const REMOTE_PLAYER_SKIN: &str = "ally";
+
+/// Movement below this many pixels per frame counts as standing still
+const MOTION_EPSILON: f64 = 0.01;
+
+/// Where a frame sits in the atlas image, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrameRect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+/// One frame of a clip and how long it stays on screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipFrame {
+    pub frame: String,
+    pub duration_ms: f64,
+}
+
+/// A sequence of atlas frames
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationClip {
+    pub frames: Vec<ClipFrame>,
+    /// Whether the clip starts over when it ends, rather than holding its
+    /// last frame
+    #[serde(default = "looping_by_default")]
+    pub looping: bool,
+}
+
+fn looping_by_default() -> bool {
+    true
+}
+
+impl AnimationClip {
+    pub fn duration_ms(&self) -> f64 {
+        self.frames.iter().map(|frame| frame.duration_ms).sum()
+    }
+
+    /// Whether a clip that doesn't loop has played through
+    pub fn is_finished(&self, elapsed_ms: f64) -> bool {
+        !self.looping && elapsed_ms >= self.duration_ms()
+    }
+
+    /// The frame showing `elapsed_ms` into the clip
+    pub fn frame_at(&self, elapsed_ms: f64) -> Option<&ClipFrame> {
+        let total = self.duration_ms();
+        let mut t = if self.looping && total > 0.0 {
+            elapsed_ms.rem_euclid(total)
+        } else {
+            elapsed_ms.max(0.0)
+        };
+        for frame in &self.frames {
+            if t < frame.duration_ms {
+                return Some(frame);
+            }
+            t -= frame.duration_ms;
+        }
+        self.frames.last()
+    }
+}
+
+/// Reasons an atlas description is refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtlasError {
+    Malformed(String),
+    /// A frame with a zero, negative or non-finite size
+    InvalidFrame(String),
+    EmptyClip(String),
+    /// A clip refers to a frame the atlas doesn't define
+    UnknownFrame {
+        clip: String,
+        frame: String,
+    },
+    InvalidDuration(String),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Malformed(reason) => write!(f, "Sprite atlas is invalid: {reason}"),
+            AtlasError::InvalidFrame(frame) => write!(f, "Frame {frame} has an invalid size"),
+            AtlasError::EmptyClip(clip) => write!(f, "Clip {clip} has no frames"),
+            AtlasError::UnknownFrame { clip, frame } => {
+                write!(f, "Clip {clip} uses unknown frame {frame}")
+            }
+            AtlasError::InvalidDuration(clip) => {
+                write!(f, "Clip {clip} has a frame with an invalid duration")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// Which way a sprite is looking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Facing {
+    #[default]
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// The facing for a movement, by its dominant axis; `None` when still
+    pub fn from_motion(dx: f64, dy: f64) -> Option<Self> {
+        if dx.abs() < MOTION_EPSILON && dy.abs() < MOTION_EPSILON {
+            return None;
+        }
+        Some(if dx.abs() > dy.abs() {
+            if dx > 0.0 {
+                Facing::Right
+            } else {
+                Facing::Left
+            }
+        } else if dy > 0.0 {
+            Facing::Down
+        } else {
+            Facing::Up
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Facing::Down => "down",
+            Facing::Up => "up",
+            Facing::Left => "left",
+            Facing::Right => "right",
+        }
+    }
+}
+
+/// What a sprite is doing, which picks its clip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Action {
+    #[default]
+    Idle,
+    Walk,
+    /// Played once, then back to idling or walking
+    Attack,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Idle => "idle",
+            Action::Walk => "walk",
+            Action::Attack => "attack",
+        }
+    }
+}
+
+/// Frame rectangles and animation clips for one sprite sheet. Clips are
+/// named `{skin}_{action}_{facing}`, e.g. `hero_walk_left`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpriteAtlas {
+    /// URL of the sheet image, relative to the page
+    pub image: String,
+    pub frames: BTreeMap<String, FrameRect>,
+    pub clips: BTreeMap<String, AnimationClip>,
+}
+
+impl SpriteAtlas {
+    pub fn from_json(json: &str) -> Result<Self, AtlasError> {
+        let atlas: Self =
+            serde_json::from_str(json).map_err(|e| AtlasError::Malformed(e.to_string()))?;
+
+        for (name, rect) in &atlas.frames {
+            let sizes = [rect.x, rect.y, rect.w, rect.h];
+            if sizes.iter().any(|v| !v.is_finite()) || rect.w <= 0.0 || rect.h <= 0.0 {
+                return Err(AtlasError::InvalidFrame(name.clone()));
+            }
+        }
+        for (name, clip) in &atlas.clips {
+            if clip.frames.is_empty() {
+                return Err(AtlasError::EmptyClip(name.clone()));
+            }
+            for frame in &clip.frames {
+                if !atlas.frames.contains_key(&frame.frame) {
+                    return Err(AtlasError::UnknownFrame {
+                        clip: name.clone(),
+                        frame: frame.frame.clone(),
+                    });
+                }
+                if !frame.duration_ms.is_finite() || frame.duration_ms <= 0.0 {
+                    return Err(AtlasError::InvalidDuration(name.clone()));
+                }
+            }
+        }
+        Ok(atlas)
+    }
+
+    /// Atlas for the sheet bundled with the client
+    pub fn with_default_atlas() -> Self {
+        Self::from_json(DEFAULT_ATLAS).expect("bundled sprite atlas is valid")
+    }
+
+    pub fn frame(&self, name: &str) -> Option<&FrameRect> {
+        self.frames.get(name)
+    }
+
+    /// The clip for a skin doing `action` towards `facing`, falling back to
+    /// a direction-less clip and then to idling when the sheet lacks one
+    pub fn clip_for(&self, skin: &str, action: Action, facing: Facing) -> Option<&AnimationClip> {
+        [
+            format!("{skin}_{}_{}", action.name(), facing.name()),
+            format!("{skin}_{}", action.name()),
+            format!("{skin}_idle_{}", facing.name()),
+            format!("{skin}_idle"),
+        ]
+        .iter()
+        .find_map(|name| self.clips.get(name))
+    }
+}
+
+/// Animation playback for one sprite
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationState {
+    pub skin: String,
+    pub facing: Facing,
+    /// Idle or walking, from the sprite's movement
+    locomotion: Action,
+    /// An action playing once over the locomotion, e.g. an attack
+    one_shot: Option<Action>,
+    elapsed_ms: f64,
+}
+
+impl AnimationState {
+    pub fn new(skin: &str) -> Self {
+        Self {
+            skin: skin.to_string(),
+            facing: Facing::default(),
+            locomotion: Action::Idle,
+            one_shot: None,
+            elapsed_ms: 0.0,
+        }
+    }
+
+    /// What the sprite is showing
+    pub fn action(&self) -> Action {
+        self.one_shot.unwrap_or(self.locomotion)
+    }
+
+    /// Walk towards the movement, or idle facing the same way when still
+    pub fn set_motion(&mut self, dx: f64, dy: f64) {
+        let facing = Facing::from_motion(dx, dy);
+        let locomotion = if facing.is_some() {
+            Action::Walk
+        } else {
+            Action::Idle
+        };
+        if let Some(facing) = facing {
+            self.facing = facing;
+        }
+        if locomotion != self.locomotion {
+            self.locomotion = locomotion;
+            if self.one_shot.is_none() {
+                self.elapsed_ms = 0.0;
+            }
+        }
+    }
+
+    /// Play an action once from its first frame
+    pub fn play(&mut self, action: Action) {
+        self.one_shot = Some(action);
+        self.elapsed_ms = 0.0;
+    }
+
+    pub fn advance(&mut self, dt_ms: f64, atlas: &SpriteAtlas) {
+        self.elapsed_ms += dt_ms.max(0.0);
+        if let Some(action) = self.one_shot {
+            let finished = atlas
+                .clip_for(&self.skin, action, self.facing)
+                .is_none_or(|clip| clip.is_finished(self.elapsed_ms));
+            if finished {
+                self.one_shot = None;
+                self.elapsed_ms = 0.0;
+            }
+        }
+    }
+
+    /// The part of the sheet to draw right now
+    pub fn frame<'a>(&self, atlas: &'a SpriteAtlas) -> Option<&'a FrameRect> {
+        let clip = atlas.clip_for(&self.skin, self.action(), self.facing)?;
+        atlas.frame(&clip.frame_at(self.elapsed_ms)?.frame)
+    }
+}
+
+/// Something on screen with its own animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpriteKey {
+    LocalPlayer,
+    Remote(PlayerId),
+}
+
+#[derive(Debug, Clone)]
+struct TrackedSprite {
+    state: AnimationState,
+    last_position: (f64, f64),
+}
+
+/// Animation states for every sprite, driven by how far each one moved
+/// since the previous frame. That covers predicted, reconciled and
+/// interpolated movement alike without knowing where it came from.
+#[derive(Debug, Clone, Default)]
+pub struct SpriteAnimator {
+    sprites: BTreeMap<SpriteKey, TrackedSprite>,
+}
+
+impl SpriteAnimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record where `key` is this frame and advance its animation by `dt_ms`
+    pub fn observe(
+        &mut self,
+        key: SpriteKey,
+        skin: &str,
+        position: (f64, f64),
+        dt_ms: f64,
+        atlas: &SpriteAtlas,
+    ) {
+        let sprite = self.sprites.entry(key).or_insert_with(|| TrackedSprite {
+            state: AnimationState::new(skin),
+            last_position: position,
+        });
+        sprite.state.set_motion(
+            position.0 - sprite.last_position.0,
+            position.1 - sprite.last_position.1,
+        );
+        sprite.state.advance(dt_ms, atlas);
+        sprite.last_position = position;
+    }
+
+    /// Play a one-off action, e.g. an attack swing
+    pub fn play(&mut self, key: SpriteKey, action: Action) {
+        if let Some(sprite) = self.sprites.get_mut(&key) {
+            sprite.state.play(action);
+        }
+    }
+
+    /// Forget sprites that are no longer on screen
+    pub fn retain(&mut self, mut keep: impl FnMut(SpriteKey) -> bool) {
+        self.sprites.retain(|&key, _| keep(key));
+    }
+
+    pub fn state(&self, key: SpriteKey) -> Option<&AnimationState> {
+        self.sprites.get(&key).map(|sprite| &sprite.state)
+    }
+
+    pub fn frame<'a>(&self, key: SpriteKey, atlas: &'a SpriteAtlas) -> Option<&'a FrameRect> {
+        self.state(key)?.frame(atlas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATLAS: &str = r#"{
+        "image": "sheet.png",
+        "frames": {
+            "a": { "x": 0, "y": 0, "w": 16, "h": 16 },
+            "b": { "x": 16, "y": 0, "w": 16, "h": 16 },
+            "c": { "x": 32, "y": 0, "w": 16, "h": 16 }
+        },
+        "clips": {
+            "hero_idle": { "frames": [{ "frame": "a", "duration_ms": 500 }] },
+            "hero_walk_left": {
+                "frames": [
+                    { "frame": "b", "duration_ms": 100 },
+                    { "frame": "c", "duration_ms": 100 }
+                ]
+            },
+            "hero_attack": {
+                "frames": [
+                    { "frame": "c", "duration_ms": 50 },
+                    { "frame": "b", "duration_ms": 50 }
+                ],
+                "looping": false
+            }
+        }
+    }"#;
+
+    fn atlas() -> SpriteAtlas {
+        SpriteAtlas::from_json(ATLAS).unwrap()
+    }
+
+    fn x_of(rect: Option<&FrameRect>) -> Option<f64> {
+        rect.map(|rect| rect.x)
+    }
+
+    #[test]
+    fn test_bundled_atlas_has_every_clip() {
+        let atlas = SpriteAtlas::with_default_atlas();
+        for skin in [PLAYER_SKIN, REMOTE_PLAYER_SKIN] {
+            for action in [Action::Idle, Action::Walk, Action::Attack] {
+                for facing in [Facing::Down, Facing::Up, Facing::Left, Facing::Right] {
+                    let name = format!("{skin}_{}_{}", action.name(), facing.name());
+                    assert!(atlas.clips.contains_key(&name), "missing clip {name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_atlases() {
+        assert!(matches!(
+            SpriteAtlas::from_json("{"),
+            Err(AtlasError::Malformed(_))
+        ));
+
+        let unknown = ATLAS.replace(
+            r#""frame": "c", "duration_ms": 100"#,
+            r#""frame": "z", "duration_ms": 100"#,
+        );
+        assert_eq!(
+            SpriteAtlas::from_json(&unknown),
+            Err(AtlasError::UnknownFrame {
+                clip: "hero_walk_left".to_string(),
+                frame: "z".to_string(),
+            })
+        );
+
+        let zero = ATLAS.replace(r#""duration_ms": 500"#, r#""duration_ms": 0"#);
+        assert_eq!(
+            SpriteAtlas::from_json(&zero),
+            Err(AtlasError::InvalidDuration("hero_idle".to_string()))
+        );
+
+        let flat = ATLAS.replace(r#""x": 0, "y": 0, "w": 16"#, r#""x": 0, "y": 0, "w": 0"#);
+        assert_eq!(
+            SpriteAtlas::from_json(&flat),
+            Err(AtlasError::InvalidFrame("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_clips_loop_or_hold() {
+        let atlas = atlas();
+        let walk = &atlas.clips["hero_walk_left"];
+        let frames: Vec<&str> = [0.0, 99.0, 100.0, 199.0, 200.0, 350.0]
+            .iter()
+            .map(|&t| walk.frame_at(t).unwrap().frame.as_str())
+            .collect();
+        assert_eq!(frames, ["b", "b", "c", "c", "b", "c"]);
+        assert!(!walk.is_finished(10_000.0));
+
+        let attack = &atlas.clips["hero_attack"];
+        assert_eq!(attack.frame_at(500.0).unwrap().frame, "b");
+        assert!(!attack.is_finished(99.0));
+        assert!(attack.is_finished(100.0));
+    }
+
+    #[test]
+    fn test_facing_follows_the_dominant_axis() {
+        assert_eq!(Facing::from_motion(0.0, 0.0), None);
+        assert_eq!(Facing::from_motion(3.0, 1.0), Some(Facing::Right));
+        assert_eq!(Facing::from_motion(-3.0, 1.0), Some(Facing::Left));
+        assert_eq!(Facing::from_motion(1.0, -3.0), Some(Facing::Up));
+        assert_eq!(Facing::from_motion(0.0, 2.0), Some(Facing::Down));
+    }
+
+    #[test]
+    fn test_clips_fall_back_to_idle() {
+        let atlas = atlas();
+        // No right-facing walk in this sheet, nor any direction-less one
+        assert_eq!(
+            atlas.clip_for("hero", Action::Walk, Facing::Right),
+            Some(&atlas.clips["hero_idle"])
+        );
+        assert_eq!(atlas.clip_for("ghost", Action::Idle, Facing::Down), None);
+    }
+
+    #[test]
+    fn test_animator_walks_while_moving() {
+        let atlas = atlas();
+        let mut animator = SpriteAnimator::new();
+        let key = SpriteKey::Remote(7);
+
+        animator.observe(key, "hero", (100.0, 100.0), 16.0, &atlas);
+        assert_eq!(animator.state(key).unwrap().action(), Action::Idle);
+        assert_eq!(x_of(animator.frame(key, &atlas)), Some(0.0));
+
+        // Moving left starts the walk from its first frame
+        animator.observe(key, "hero", (95.0, 100.0), 50.0, &atlas);
+        let state = animator.state(key).unwrap();
+        assert_eq!((state.action(), state.facing), (Action::Walk, Facing::Left));
+        assert_eq!(x_of(animator.frame(key, &atlas)), Some(16.0));
+        animator.observe(key, "hero", (90.0, 100.0), 60.0, &atlas);
+        assert_eq!(x_of(animator.frame(key, &atlas)), Some(32.0));
+
+        // Stopping idles but keeps facing the same way
+        animator.observe(key, "hero", (90.0, 100.0), 16.0, &atlas);
+        let state = animator.state(key).unwrap();
+        assert_eq!((state.action(), state.facing), (Action::Idle, Facing::Left));
+
+        animator.retain(|key| key == SpriteKey::LocalPlayer);
+        assert_eq!(animator.state(key), None);
+    }
+
+    #[test]
+    fn test_one_shot_actions_play_once() {
+        let atlas = atlas();
+        let mut animator = SpriteAnimator::new();
+        let key = SpriteKey::LocalPlayer;
+        animator.observe(key, "hero", (0.0, 0.0), 0.0, &atlas);
+
+        animator.play(key, Action::Attack);
+        animator.observe(key, "hero", (0.0, 0.0), 10.0, &atlas);
+        assert_eq!(animator.state(key).unwrap().action(), Action::Attack);
+        assert_eq!(x_of(animator.frame(key, &atlas)), Some(32.0));
+
+        // Moving during the swing doesn't interrupt it
+        animator.observe(key, "hero", (-5.0, 0.0), 50.0, &atlas);
+        assert_eq!(x_of(animator.frame(key, &atlas)), Some(16.0));
+
+        animator.observe(key, "hero", (-10.0, 0.0), 50.0, &atlas);
+        assert_eq!(animator.state(key).unwrap().action(), Action::Walk);
+    }
+}
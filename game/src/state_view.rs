@@ -0,0 +1,369 @@
+use crate::change_tracking::{DirtySections, StateSection};
+use crate::game_state::{xp_to_next_level, GameScreen, GameState, Region};
+use crate::inventory::Inventory;
+use crate::party::PartyView;
+use crate::profile::PlayerClass;
+use crate::trade::TradeView;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Version of the `get_game_state` JSON contract.
+/// Bump this whenever `GameStateView` (or a type it contains) changes shape,
+/// then regenerate the TypeScript bindings with
+/// `UPDATE_TS_BINDINGS=1 cargo test state_view`.
+pub const STATE_VIEW_VERSION: u32 = 14;
+
+/// Game state as exposed to the frontend, the single source of truth for
+/// the TypeScript `GameStateView` type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct GameStateView {
+    pub version: u32,
+    pub screen: GameScreen,
+    /// Every open screen, bottom first, ending with `screen`
+    pub screen_stack: Vec<GameScreen>,
+    pub region: Option<Region>,
+    pub player_name: Option<String>,
+    pub player_class: PlayerClass,
+    pub appearance: u8,
+    pub is_loading: bool,
+    /// Whether the world is frozen, by a screen or explicitly
+    pub paused: bool,
+    pub error: Option<String>,
+    pub player_position: [f64; 2],
+    pub ball_position: [f64; 2],
+    pub zone: String,
+    pub level: u32,
+    pub xp: u32,
+    /// XP needed for the next level
+    pub xp_to_next_level: u32,
+    pub hp: f64,
+    pub max_hp: f64,
+    pub playtime_secs: f64,
+    pub gold: u32,
+    pub inventory: Inventory,
+    pub party: Option<PartyView>,
+    /// Name of the player whose party invite is waiting for an answer
+    pub party_invite: Option<String>,
+    pub trade: Option<TradeView>,
+    /// Name of the player whose trade request is waiting for an answer
+    pub trade_request: Option<String>,
+}
+
+impl GameStateView {
+    pub fn from_state(state: &GameState) -> Self {
+        Self {
+            version: STATE_VIEW_VERSION,
+            screen: state.current_screen.clone(),
+            screen_stack: state.screens(),
+            region: state.selected_region.clone(),
+            player_name: state.player_name.clone(),
+            player_class: state.player_class,
+            appearance: state.appearance,
+            is_loading: state.is_loading,
+            paused: state.is_simulation_paused(),
+            error: state.error_message.clone(),
+            player_position: [state.player_x, state.player_y],
+            ball_position: [state.ball_x, state.ball_y],
+            zone: state.current_zone.clone(),
+            level: state.player_level,
+            xp: state.player_xp,
+            xp_to_next_level: xp_to_next_level(state.player_level),
+            hp: state.player_hp,
+            max_hp: state.player_max_hp,
+            playtime_secs: state.playtime_secs,
+            gold: state.gold,
+            inventory: state.inventory.clone(),
+            party: state.party.clone(),
+            party_invite: state.party_invite.clone(),
+            trade: state.trade.clone(),
+            trade_request: state.trade_request.clone(),
+        }
+    }
+
+    /// `GameStateView` fields belonging to one section of the state
+    fn section_fields(state: &GameState, section: StateSection) -> Vec<(&'static str, Value)> {
+        match section {
+            StateSection::Screen => vec![
+                ("screen", json!(state.current_screen)),
+                ("screen_stack", json!(state.screens())),
+            ],
+            StateSection::Region => vec![("region", json!(state.selected_region))],
+            StateSection::Profile => vec![
+                ("player_name", json!(state.player_name)),
+                ("player_class", json!(state.player_class)),
+                ("appearance", json!(state.appearance)),
+            ],
+            StateSection::Status => vec![
+                ("is_loading", json!(state.is_loading)),
+                ("paused", json!(state.is_simulation_paused())),
+                ("error", json!(state.error_message)),
+            ],
+            StateSection::PlayerPosition => {
+                vec![("player_position", json!([state.player_x, state.player_y]))]
+            }
+            StateSection::Ball => vec![("ball_position", json!([state.ball_x, state.ball_y]))],
+            StateSection::Zone => vec![("zone", json!(state.current_zone))],
+            StateSection::Progress => vec![
+                ("level", json!(state.player_level)),
+                ("xp", json!(state.player_xp)),
+                (
+                    "xp_to_next_level",
+                    json!(xp_to_next_level(state.player_level)),
+                ),
+                ("hp", json!(state.player_hp)),
+                ("max_hp", json!(state.player_max_hp)),
+                ("playtime_secs", json!(state.playtime_secs)),
+            ],
+            StateSection::Gold => vec![("gold", json!(state.gold))],
+            StateSection::Inventory => vec![("inventory", json!(state.inventory))],
+            StateSection::Party => vec![
+                ("party", json!(state.party)),
+                ("party_invite", json!(state.party_invite)),
+            ],
+            StateSection::Trade => vec![
+                ("trade", json!(state.trade)),
+                ("trade_request", json!(state.trade_request)),
+            ],
+        }
+    }
+}
+
+/// Changes since the previous delta. When `full` is set, `changes` holds the
+/// complete `GameStateView` and the frontend should replace its copy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct StateDelta {
+    pub seq: u32,
+    pub full: bool,
+    #[cfg_attr(test, ts(type = "Partial<GameStateView>"))]
+    pub changes: Map<String, Value>,
+}
+
+/// Hands out sequence-numbered deltas of the game state
+#[derive(Debug, Clone)]
+pub struct StateDeltaTracker {
+    next_seq: u32,
+    needs_full_resync: bool,
+}
+
+impl Default for StateDeltaTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateDeltaTracker {
+    pub fn new() -> Self {
+        Self {
+            next_seq: 0,
+            needs_full_resync: true,
+        }
+    }
+
+    /// Make the next delta a full snapshot (e.g. after a load or a missed delta)
+    pub fn request_full_resync(&mut self) {
+        self.needs_full_resync = true;
+    }
+
+    /// Collect the sections changed since the last call and clear them
+    pub fn take_delta(&mut self, state: &mut GameState) -> StateDelta {
+        let dirty = state.dirty.take();
+        let full = std::mem::take(&mut self.needs_full_resync);
+
+        let changes = if full {
+            match serde_json::to_value(GameStateView::from_state(state)) {
+                Ok(Value::Object(map)) => map,
+                _ => Map::new(),
+            }
+        } else {
+            Self::changed_fields(state, dirty)
+        };
+
+        let delta = StateDelta {
+            seq: self.next_seq,
+            full,
+            changes,
+        };
+        self.next_seq = self.next_seq.wrapping_add(1);
+        delta
+    }
+
+    fn changed_fields(state: &GameState, dirty: DirtySections) -> Map<String, Value> {
+        dirty
+            .iter()
+            .flat_map(|section| GameStateView::section_fields(state, section))
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatChannel, ChatLine};
+    use crate::commands::{CommandResult, UiCommand};
+    use crate::crafting::CraftingStation;
+    use crate::errors::{ErrorReport, ErrorSeverity};
+    use crate::events::GameEvent;
+    use crate::inventory::ItemStack;
+    use crate::party::PartyMember;
+    use crate::profile::PlayerProfile;
+    use crate::servers::ServerStatus;
+    use crate::trade::{TradeOffer, TradeSideView};
+    use std::path::PathBuf;
+    use ts_rs::TS;
+
+    const VERSION_MARKER: &str = "// STATE_VIEW_VERSION: ";
+
+    fn bindings_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../ui/src/types/game-state.generated.ts")
+    }
+
+    fn typescript_bindings() -> String {
+        let decls = [
+            GameScreen::decl(),
+            Region::decl(),
+            PlayerClass::decl(),
+            PlayerProfile::decl(),
+            ServerStatus::decl(),
+            ItemStack::decl(),
+            Inventory::decl(),
+            GameStateView::decl(),
+            StateDelta::decl(),
+            GameEvent::decl(),
+            CraftingStation::decl(),
+            UiCommand::decl(),
+            CommandResult::decl(),
+            ErrorSeverity::decl(),
+            ErrorReport::decl(),
+            ChatChannel::decl(),
+            ChatLine::decl(),
+            PartyMember::decl(),
+            PartyView::decl(),
+            TradeOffer::decl(),
+            TradeSideView::decl(),
+            TradeView::decl(),
+        ];
+
+        let mut out = String::new();
+        out.push_str("// Generated from game/src/state_view.rs by `cargo test`. Do not edit.\n");
+        out.push_str(&format!("{VERSION_MARKER}{STATE_VIEW_VERSION}\n\n"));
+        for decl in decls {
+            out.push_str(&format!("export {decl}\n\n"));
+        }
+        out.push_str(&format!(
+            "export const STATE_VIEW_VERSION = {STATE_VIEW_VERSION};\n"
+        ));
+        out
+    }
+
+    fn checked_in_version(bindings: &str) -> Option<u32> {
+        bindings
+            .lines()
+            .find_map(|line| line.strip_prefix(VERSION_MARKER))
+            .and_then(|v| v.trim().parse().ok())
+    }
+
+    #[test]
+    fn test_view_matches_state() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        state.set_error("Oops".to_string());
+        let view = GameStateView::from_state(&state);
+
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["version"], STATE_VIEW_VERSION);
+        assert_eq!(json["screen"], "GameHUD");
+        assert_eq!(json["screen_stack"], serde_json::json!(["GameHUD"]));
+        assert_eq!(json["region"], "EU");
+        assert_eq!(json["player_name"], "Player");
+        assert_eq!(json["error"], "Oops");
+        assert_eq!(json["player_position"], serde_json::json!([400.0, 300.0]));
+        assert_eq!(json["inventory"]["slots"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_sections_cover_every_view_field() {
+        let state = GameState::new(800.0, 600.0);
+        let full = serde_json::to_value(GameStateView::from_state(&state)).unwrap();
+
+        let mut dirty = DirtySections::default();
+        dirty.mark_all();
+        let mut partial = StateDeltaTracker::changed_fields(&state, dirty);
+        partial.insert("version".to_string(), json!(STATE_VIEW_VERSION));
+
+        assert_eq!(Value::Object(partial), full);
+    }
+
+    #[test]
+    fn test_first_delta_is_full() {
+        let mut state = GameState::new_in_world(800.0, 600.0);
+        let mut tracker = StateDeltaTracker::new();
+
+        let delta = tracker.take_delta(&mut state);
+        assert_eq!(delta.seq, 0);
+        assert!(delta.full);
+        assert_eq!(delta.changes["version"], STATE_VIEW_VERSION);
+        assert_eq!(delta.changes["screen"], "GameHUD");
+    }
+
+    #[test]
+    fn test_delta_only_contains_changes() {
+        let mut state = GameState::new(800.0, 600.0);
+        let mut tracker = StateDeltaTracker::new();
+        tracker.take_delta(&mut state);
+
+        state.move_player(10.0, 0.0);
+        let delta = tracker.take_delta(&mut state);
+        assert_eq!(delta.seq, 1);
+        assert!(!delta.full);
+        assert_eq!(
+            Value::Object(delta.changes),
+            json!({"player_position": [410.0, 300.0]})
+        );
+
+        let delta = tracker.take_delta(&mut state);
+        assert_eq!(delta.seq, 2);
+        assert!(delta.changes.is_empty());
+    }
+
+    #[test]
+    fn test_resync_sends_everything() {
+        let mut state = GameState::new(800.0, 600.0);
+        let mut tracker = StateDeltaTracker::new();
+        tracker.take_delta(&mut state);
+
+        tracker.request_full_resync();
+        let delta = tracker.take_delta(&mut state);
+        assert!(delta.full);
+        assert_eq!(
+            Value::Object(delta.changes),
+            serde_json::to_value(GameStateView::from_state(&state)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_typescript_bindings_up_to_date() {
+        let generated = typescript_bindings();
+        let checked_in = std::fs::read_to_string(bindings_path()).unwrap_or_default();
+        if generated == checked_in {
+            return;
+        }
+
+        assert_ne!(
+            checked_in_version(&checked_in),
+            Some(STATE_VIEW_VERSION),
+            "GameStateView changed shape without a version bump: \
+             increase STATE_VIEW_VERSION, then regenerate the bindings"
+        );
+
+        if std::env::var_os("UPDATE_TS_BINDINGS").is_some() {
+            std::fs::write(bindings_path(), generated).unwrap();
+        } else {
+            panic!(
+                "TypeScript bindings are stale, regenerate them with \
+                 `UPDATE_TS_BINDINGS=1 cargo test state_view`"
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Error raised by a storage backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Storage error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+/// Key-value string storage used for save data
+pub trait SaveStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, StorageError>;
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), StorageError>;
+    fn remove_item(&mut self, key: &str) -> Result<(), StorageError>;
+    fn keys(&self) -> Result<Vec<String>, StorageError>;
+}
+
+/// In-memory storage for native builds and tests
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    items: BTreeMap<String, String>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SaveStorage for MemoryStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, StorageError> {
+        Ok(self.items.get(key).cloned())
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove_item(&mut self, key: &str) -> Result<(), StorageError> {
+        self.items.remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.items.keys().cloned().collect())
+    }
+}
+
+/// Browser `window.localStorage` backend
+#[derive(Debug, Clone, Default)]
+pub struct LocalStorage;
+
+impl LocalStorage {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn storage(&self) -> Result<web_sys::Storage, StorageError> {
+        web_sys::window()
+            .ok_or_else(|| StorageError("no global `window` exists".to_string()))?
+            .local_storage()
+            .map_err(|e| StorageError(format!("{e:?}")))?
+            .ok_or_else(|| StorageError("localStorage is not available".to_string()))
+    }
+}
+
+impl SaveStorage for LocalStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.storage()?
+            .get_item(key)
+            .map_err(|e| StorageError(format!("{e:?}")))
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.storage()?
+            .set_item(key, value)
+            .map_err(|e| StorageError(format!("{e:?}")))
+    }
+
+    fn remove_item(&mut self, key: &str) -> Result<(), StorageError> {
+        self.storage()?
+            .remove_item(key)
+            .map_err(|e| StorageError(format!("{e:?}")))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, StorageError> {
+        let storage = self.storage()?;
+        let length = storage
+            .length()
+            .map_err(|e| StorageError(format!("{e:?}")))?;
+        let mut keys = Vec::new();
+        for index in 0..length {
+            if let Ok(Some(key)) = storage.key(index) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
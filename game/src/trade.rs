@@ -0,0 +1,670 @@
+use crate::game_state::GameState;
+use crate::inventory::ItemStack;
+use crate::protocol::PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Most distinct items one side can put in a trade
+pub const MAX_TRADE_ITEMS: usize = 8;
+
+/// How long a trade request can be accepted for
+pub const TRADE_REQUEST_TIMEOUT_MS: f64 = 30_000.0;
+
+pub type TradeId = u32;
+
+/// What one side puts up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct TradeOffer {
+    pub items: Vec<ItemStack>,
+    pub gold: u32,
+}
+
+impl TradeOffer {
+    /// Merge repeated items and drop empty stacks
+    pub fn normalized(&self) -> TradeOffer {
+        let mut items: Vec<ItemStack> = Vec::new();
+        for stack in self.items.iter().filter(|stack| stack.quantity > 0) {
+            match items.iter_mut().find(|item| item.item_id == stack.item_id) {
+                Some(item) => item.quantity = item.quantity.saturating_add(stack.quantity),
+                None => items.push(stack.clone()),
+            }
+        }
+        TradeOffer {
+            items,
+            gold: self.gold,
+        }
+    }
+
+    /// Whether `state` currently owns everything offered
+    pub fn check_owned(&self, state: &GameState) -> Result<(), TradeError> {
+        if self.gold > state.gold {
+            return Err(TradeError::NotEnoughGold);
+        }
+        match self
+            .items
+            .iter()
+            .find(|stack| state.inventory.count(&stack.item_id) < stack.quantity)
+        {
+            Some(stack) => Err(TradeError::MissingItems(stack.item_id.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Something a player asks the server to do with trades
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TradeAction {
+    Request {
+        name: String,
+    },
+    Accept,
+    Decline,
+    /// Replace this side's offer; only while unlocked
+    Offer(TradeOffer),
+    Lock,
+    Unlock,
+    Confirm,
+    Cancel,
+}
+
+/// One side of a trade as shown in the trade window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct TradeSideView {
+    pub offer: TradeOffer,
+    pub locked: bool,
+    pub confirmed: bool,
+}
+
+/// A trade as one of its players sees it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+pub struct TradeView {
+    pub partner_id: PlayerId,
+    pub partner_name: String,
+    pub mine: TradeSideView,
+    pub theirs: TradeSideView,
+}
+
+/// Reasons a trade action is refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeError {
+    NotTrading,
+    AlreadyTrading,
+    TargetBusy(String),
+    UnknownPlayer(String),
+    CannotTradeSelf,
+    NoRequest,
+    /// Unlock before changing the offer
+    OfferLocked,
+    /// Both sides must lock before confirming
+    NotLocked,
+    TooManyItems {
+        max: usize,
+    },
+    NotEnoughGold,
+    MissingItems(String),
+    /// The named player has no room for what they'd receive
+    NotEnoughSpace(String),
+    NotConnected,
+    /// The server refused the action, with its explanation
+    Rejected(String),
+}
+
+impl TradeError {
+    /// Stable machine-readable code for the UI
+    pub fn code(&self) -> &'static str {
+        match self {
+            TradeError::NotTrading => "trade_not_trading",
+            TradeError::AlreadyTrading => "trade_already_trading",
+            TradeError::TargetBusy(_) => "trade_target_busy",
+            TradeError::UnknownPlayer(_) => "trade_unknown_player",
+            TradeError::CannotTradeSelf => "trade_cannot_trade_self",
+            TradeError::NoRequest => "trade_no_request",
+            TradeError::OfferLocked => "trade_offer_locked",
+            TradeError::NotLocked => "trade_not_locked",
+            TradeError::TooManyItems { .. } => "trade_too_many_items",
+            TradeError::NotEnoughGold => "trade_not_enough_gold",
+            TradeError::MissingItems(_) => "trade_missing_items",
+            TradeError::NotEnoughSpace(_) => "trade_not_enough_space",
+            TradeError::NotConnected => "trade_not_connected",
+            TradeError::Rejected(_) => "trade_rejected",
+        }
+    }
+}
+
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeError::NotTrading => write!(f, "You're not trading with anyone"),
+            TradeError::AlreadyTrading => write!(f, "You're already trading"),
+            TradeError::TargetBusy(name) => write!(f, "{name} is busy trading"),
+            TradeError::UnknownPlayer(name) => write!(f, "No player named '{name}' is online"),
+            TradeError::CannotTradeSelf => write!(f, "You can't trade with yourself"),
+            TradeError::NoRequest => write!(f, "You have no pending trade request"),
+            TradeError::OfferLocked => write!(f, "Unlock your offer before changing it"),
+            TradeError::NotLocked => write!(f, "Both offers must be locked first"),
+            TradeError::TooManyItems { max } => write!(f, "You can offer at most {max} items"),
+            TradeError::NotEnoughGold => write!(f, "Not enough gold for this trade"),
+            TradeError::MissingItems(item_id) => write!(f, "You don't have the {item_id} offered"),
+            TradeError::NotEnoughSpace(name) => {
+                write!(f, "{name} doesn't have room for the traded items")
+            }
+            TradeError::NotConnected => write!(f, "Connect to a server to trade"),
+            TradeError::Rejected(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for TradeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TradeSide {
+    player: PlayerId,
+    offer: TradeOffer,
+    locked: bool,
+    confirmed: bool,
+}
+
+impl TradeSide {
+    fn new(player: PlayerId) -> Self {
+        Self {
+            player,
+            offer: TradeOffer::default(),
+            locked: false,
+            confirmed: false,
+        }
+    }
+
+    fn view(&self) -> TradeSideView {
+        TradeSideView {
+            offer: self.offer.clone(),
+            locked: self.locked,
+            confirmed: self.confirmed,
+        }
+    }
+}
+
+/// Two players swapping items and gold. Each side edits its offer, locks
+/// it, and once both are locked each confirms; the swap happens when both
+/// have confirmed. Unlocking to change an offer clears both confirmations,
+/// so nobody confirms a deal that changed under them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeSession {
+    pub id: TradeId,
+    sides: [TradeSide; 2],
+}
+
+impl TradeSession {
+    fn new(id: TradeId, a: PlayerId, b: PlayerId) -> Self {
+        Self {
+            id,
+            sides: [TradeSide::new(a), TradeSide::new(b)],
+        }
+    }
+
+    pub fn players(&self) -> [PlayerId; 2] {
+        [self.sides[0].player, self.sides[1].player]
+    }
+
+    pub fn partner_of(&self, player: PlayerId) -> Option<PlayerId> {
+        self.players().into_iter().find(|&other| other != player)
+    }
+
+    pub fn offer_of(&self, player: PlayerId) -> Option<&TradeOffer> {
+        self.sides
+            .iter()
+            .find(|side| side.player == player)
+            .map(|side| &side.offer)
+    }
+
+    fn side_index(&self, player: PlayerId) -> Result<usize, TradeError> {
+        self.sides
+            .iter()
+            .position(|side| side.player == player)
+            .ok_or(TradeError::NotTrading)
+    }
+
+    fn reset_confirmations(&mut self) {
+        for side in &mut self.sides {
+            side.confirmed = false;
+        }
+    }
+
+    pub fn set_offer(&mut self, player: PlayerId, offer: TradeOffer) -> Result<(), TradeError> {
+        let index = self.side_index(player)?;
+        let offer = offer.normalized();
+        if offer.items.len() > MAX_TRADE_ITEMS {
+            return Err(TradeError::TooManyItems {
+                max: MAX_TRADE_ITEMS,
+            });
+        }
+        if self.sides[index].locked {
+            return Err(TradeError::OfferLocked);
+        }
+        self.sides[index].offer = offer;
+        self.reset_confirmations();
+        Ok(())
+    }
+
+    pub fn lock(&mut self, player: PlayerId) -> Result<(), TradeError> {
+        let index = self.side_index(player)?;
+        self.sides[index].locked = true;
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, player: PlayerId) -> Result<(), TradeError> {
+        let index = self.side_index(player)?;
+        self.sides[index].locked = false;
+        self.reset_confirmations();
+        Ok(())
+    }
+
+    /// Confirm the deal, returning whether both sides now have
+    pub fn confirm(&mut self, player: PlayerId) -> Result<bool, TradeError> {
+        let index = self.side_index(player)?;
+        if !self.sides.iter().all(|side| side.locked) {
+            return Err(TradeError::NotLocked);
+        }
+        self.sides[index].confirmed = true;
+        Ok(self.sides.iter().all(|side| side.confirmed))
+    }
+
+    /// Unlock both sides after a swap that couldn't go through
+    pub fn reopen(&mut self) {
+        for side in &mut self.sides {
+            side.locked = false;
+            side.confirmed = false;
+        }
+    }
+
+    /// The trade from `player`'s side
+    pub fn view_for(&self, player: PlayerId, partner_name: &str) -> Option<TradeView> {
+        let mine = self.side_index(player).ok()?;
+        let theirs = &self.sides[1 - mine];
+        Some(TradeView {
+            partner_id: theirs.player,
+            partner_name: partner_name.to_string(),
+            mine: self.sides[mine].view(),
+            theirs: theirs.view(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TradeRequest {
+    from: PlayerId,
+    expires_at_ms: f64,
+}
+
+/// Server-side trade bookkeeping, independent of the network
+#[derive(Debug, Clone)]
+pub struct TradeManager {
+    sessions: BTreeMap<TradeId, TradeSession>,
+    by_player: HashMap<PlayerId, TradeId>,
+    /// Pending requests by the player asked
+    requests: HashMap<PlayerId, TradeRequest>,
+    next_trade_id: TradeId,
+}
+
+impl Default for TradeManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: BTreeMap::new(),
+            by_player: HashMap::new(),
+            requests: HashMap::new(),
+            next_trade_id: 1,
+        }
+    }
+
+    pub fn session_of(&self, player: PlayerId) -> Option<&TradeSession> {
+        self.sessions.get(self.by_player.get(&player)?)
+    }
+
+    pub fn session_mut(&mut self, player: PlayerId) -> Result<&mut TradeSession, TradeError> {
+        let id = self.by_player.get(&player).ok_or(TradeError::NotTrading)?;
+        Ok(self.sessions.get_mut(id).expect("traders have a session"))
+    }
+
+    /// Who asked `player` to trade, if the request is still open
+    pub fn pending_request(&self, player: PlayerId, now_ms: f64) -> Option<PlayerId> {
+        self.requests
+            .get(&player)
+            .filter(|request| request.expires_at_ms > now_ms)
+            .map(|request| request.from)
+    }
+
+    /// Ask `to` to trade. `to_name` is only used to explain refusals.
+    pub fn request(
+        &mut self,
+        from: PlayerId,
+        to: PlayerId,
+        to_name: &str,
+        now_ms: f64,
+    ) -> Result<(), TradeError> {
+        if from == to {
+            return Err(TradeError::CannotTradeSelf);
+        }
+        if self.by_player.contains_key(&from) {
+            return Err(TradeError::AlreadyTrading);
+        }
+        if self.by_player.contains_key(&to) {
+            return Err(TradeError::TargetBusy(to_name.to_string()));
+        }
+        self.requests.insert(
+            to,
+            TradeRequest {
+                from,
+                expires_at_ms: now_ms + TRADE_REQUEST_TIMEOUT_MS,
+            },
+        );
+        Ok(())
+    }
+
+    /// Accept the pending request and open a session
+    pub fn accept(&mut self, player: PlayerId, now_ms: f64) -> Result<TradeId, TradeError> {
+        let from = self
+            .pending_request(player, now_ms)
+            .ok_or(TradeError::NoRequest)?;
+        self.requests.remove(&player);
+        if self.by_player.contains_key(&player) {
+            return Err(TradeError::AlreadyTrading);
+        }
+        if self.by_player.contains_key(&from) {
+            return Err(TradeError::TargetBusy(String::new()));
+        }
+
+        let id = self.next_trade_id;
+        self.next_trade_id += 1;
+        self.sessions
+            .insert(id, TradeSession::new(id, from, player));
+        self.by_player.insert(from, id);
+        self.by_player.insert(player, id);
+        Ok(id)
+    }
+
+    /// Turn down the pending request, returning who sent it
+    pub fn decline(&mut self, player: PlayerId) -> Result<PlayerId, TradeError> {
+        self.requests
+            .remove(&player)
+            .map(|request| request.from)
+            .ok_or(TradeError::NoRequest)
+    }
+
+    /// End `player`'s session without swapping anything
+    pub fn cancel(&mut self, player: PlayerId) -> Result<TradeSession, TradeError> {
+        let id = self
+            .by_player
+            .get(&player)
+            .copied()
+            .ok_or(TradeError::NotTrading)?;
+        let session = self.sessions.remove(&id).expect("traders have a session");
+        for trader in session.players() {
+            self.by_player.remove(&trader);
+        }
+        Ok(session)
+    }
+
+    /// Clean up after a player who left the server
+    pub fn forget(&mut self, player: PlayerId) {
+        let _ = self.cancel(player);
+        self.requests
+            .retain(|&asked, request| asked != player && request.from != player);
+    }
+}
+
+/// Swap both offers between `a` and `b`, all or nothing: if either side is
+/// missing something or lacks room, neither state changes
+pub fn settle(
+    a: &mut GameState,
+    a_offer: &TradeOffer,
+    b: &mut GameState,
+    b_offer: &TradeOffer,
+) -> Result<(), TradeError> {
+    a_offer.check_owned(a)?;
+    b_offer.check_owned(b)?;
+
+    let mut a_inventory = a.inventory.clone();
+    let mut b_inventory = b.inventory.clone();
+    for stack in &a_offer.items {
+        a_inventory
+            .remove(&stack.item_id, stack.quantity)
+            .map_err(|_| TradeError::MissingItems(stack.item_id.clone()))?;
+    }
+    for stack in &b_offer.items {
+        b_inventory
+            .remove(&stack.item_id, stack.quantity)
+            .map_err(|_| TradeError::MissingItems(stack.item_id.clone()))?;
+    }
+    let no_room = |state: &GameState| {
+        TradeError::NotEnoughSpace(state.player_name.clone().unwrap_or_default())
+    };
+    for stack in &a_offer.items {
+        b_inventory
+            .add(&stack.item_id, stack.quantity)
+            .map_err(|_| no_room(b))?;
+    }
+    for stack in &b_offer.items {
+        a_inventory
+            .add(&stack.item_id, stack.quantity)
+            .map_err(|_| no_room(a))?;
+    }
+
+    *a.inventory_mut() = a_inventory;
+    *b.inventory_mut() = b_inventory;
+    a.spend_gold(a_offer.gold);
+    b.spend_gold(b_offer.gold);
+    a.add_gold(b_offer.gold);
+    b.add_gold(a_offer.gold);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item_id: &str, quantity: u32) -> ItemStack {
+        ItemStack {
+            item_id: item_id.to_string(),
+            quantity,
+        }
+    }
+
+    fn offer(items: &[(&str, u32)], gold: u32) -> TradeOffer {
+        TradeOffer {
+            items: items.iter().map(|&(id, qty)| stack(id, qty)).collect(),
+            gold,
+        }
+    }
+
+    fn trading() -> (TradeManager, TradeSession) {
+        let mut trades = TradeManager::new();
+        trades.request(1, 2, "Two", 0.0).unwrap();
+        trades.accept(2, 0.0).unwrap();
+        let session = trades.session_of(1).unwrap().clone();
+        (trades, session)
+    }
+
+    #[test]
+    fn test_request_accept_and_cancel() {
+        let (mut trades, session) = trading();
+        assert_eq!(session.players(), [1, 2]);
+        assert_eq!(session.partner_of(2), Some(1));
+
+        assert_eq!(
+            trades.request(3, 1, "One", 0.0),
+            Err(TradeError::TargetBusy("One".to_string()))
+        );
+        assert_eq!(
+            trades.request(1, 3, "Three", 0.0),
+            Err(TradeError::AlreadyTrading)
+        );
+        assert_eq!(
+            trades.request(3, 3, "Three", 0.0),
+            Err(TradeError::CannotTradeSelf)
+        );
+
+        trades.cancel(2).unwrap();
+        assert!(trades.session_of(1).is_none());
+        assert_eq!(trades.cancel(1), Err(TradeError::NotTrading));
+    }
+
+    #[test]
+    fn test_requests_expire_and_can_be_declined() {
+        let mut trades = TradeManager::new();
+        trades.request(1, 2, "Two", 0.0).unwrap();
+        assert_eq!(
+            trades.accept(2, TRADE_REQUEST_TIMEOUT_MS),
+            Err(TradeError::NoRequest)
+        );
+
+        trades.request(1, 2, "Two", 0.0).unwrap();
+        assert_eq!(trades.decline(2), Ok(1));
+        assert_eq!(trades.accept(2, 0.0), Err(TradeError::NoRequest));
+
+        trades.request(1, 2, "Two", 0.0).unwrap();
+        trades.forget(1);
+        assert_eq!(trades.pending_request(2, 0.0), None);
+    }
+
+    #[test]
+    fn test_lock_then_confirm() {
+        let (_, mut session) = trading();
+        session.set_offer(1, offer(&[("iron_ore", 2)], 10)).unwrap();
+
+        assert_eq!(session.confirm(1), Err(TradeError::NotLocked));
+        session.lock(1).unwrap();
+        assert_eq!(
+            session.set_offer(1, TradeOffer::default()),
+            Err(TradeError::OfferLocked)
+        );
+        session.lock(2).unwrap();
+
+        assert_eq!(session.confirm(1), Ok(false));
+        assert_eq!(session.confirm(2), Ok(true));
+        assert_eq!(session.confirm(3), Err(TradeError::NotTrading));
+    }
+
+    #[test]
+    fn test_any_change_resets_confirmation() {
+        let (_, mut session) = trading();
+        session.lock(1).unwrap();
+        session.lock(2).unwrap();
+        session.confirm(1).unwrap();
+
+        // Two unlocks to change their offer; One has to confirm again
+        session.unlock(2).unwrap();
+        session.set_offer(2, offer(&[], 5)).unwrap();
+        session.lock(2).unwrap();
+        let view = session.view_for(1, "Two").unwrap();
+        assert!(!view.mine.confirmed);
+        assert!(view.mine.locked);
+        assert_eq!(view.theirs.offer.gold, 5);
+
+        assert_eq!(session.confirm(2), Ok(false));
+        assert_eq!(session.confirm(1), Ok(true));
+    }
+
+    #[test]
+    fn test_offers_are_normalized_and_bounded() {
+        let (_, mut session) = trading();
+        session
+            .set_offer(
+                1,
+                offer(&[("iron_ore", 2), ("coal", 0), ("iron_ore", 3)], 0),
+            )
+            .unwrap();
+        assert_eq!(
+            session.offer_of(1).unwrap().items,
+            vec![stack("iron_ore", 5)]
+        );
+
+        let many: Vec<(String, u32)> = (0..=MAX_TRADE_ITEMS)
+            .map(|i| (format!("item_{i}"), 1))
+            .collect();
+        let many: Vec<(&str, u32)> = many.iter().map(|(id, qty)| (id.as_str(), *qty)).collect();
+        assert_eq!(
+            session.set_offer(1, offer(&many, 0)),
+            Err(TradeError::TooManyItems {
+                max: MAX_TRADE_ITEMS
+            })
+        );
+    }
+
+    fn trader(name: &str, items: &[(&str, u32)], capacity: usize) -> GameState {
+        let mut state = GameState::new(800.0, 600.0);
+        state.player_name = Some(name.to_string());
+        state.inventory = crate::inventory::Inventory::new(capacity);
+        for &(id, qty) in items {
+            state.inventory.add(id, qty).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_settle_swaps_items_and_gold() {
+        let mut a = trader("A", &[("iron_ore", 10)], 4);
+        let mut b = trader("B", &[("coal", 3)], 4);
+
+        settle(
+            &mut a,
+            &offer(&[("iron_ore", 4)], 30),
+            &mut b,
+            &offer(&[("coal", 3)], 0),
+        )
+        .unwrap();
+
+        assert_eq!(a.inventory.count("iron_ore"), 6);
+        assert_eq!(a.inventory.count("coal"), 3);
+        assert_eq!(b.inventory.count("iron_ore"), 4);
+        assert_eq!(b.inventory.count("coal"), 0);
+        assert_eq!((a.gold, b.gold), (70, 130));
+    }
+
+    #[test]
+    fn test_settle_is_all_or_nothing() {
+        // B's only slot is full, so A's ore has nowhere to go
+        let mut a = trader("A", &[("iron_ore", 10)], 4);
+        let mut b = trader("B", &[("coal", 99)], 1);
+        let (a_before, b_before) = (a.inventory.clone(), b.inventory.clone());
+
+        assert_eq!(
+            settle(
+                &mut a,
+                &offer(&[("iron_ore", 4)], 50),
+                &mut b,
+                &offer(&[], 0)
+            ),
+            Err(TradeError::NotEnoughSpace("B".to_string()))
+        );
+        assert_eq!((&a.inventory, &b.inventory), (&a_before, &b_before));
+        assert_eq!((a.gold, b.gold), (100, 100));
+
+        // Giving the coal away makes room
+        settle(
+            &mut a,
+            &offer(&[("iron_ore", 4)], 0),
+            &mut b,
+            &offer(&[("coal", 99)], 0),
+        )
+        .unwrap();
+        assert_eq!(b.inventory.count("iron_ore"), 4);
+
+        assert_eq!(
+            settle(&mut a, &offer(&[], 500), &mut b, &offer(&[], 0)),
+            Err(TradeError::NotEnoughGold)
+        );
+        assert_eq!(
+            settle(&mut a, &offer(&[("gem", 1)], 0), &mut b, &offer(&[], 0)),
+            Err(TradeError::MissingItems("gem".to_string()))
+        );
+    }
+}
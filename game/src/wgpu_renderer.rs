@@ -0,0 +1,776 @@
+use crate::render::{Color, DrawCommand, DrawList, RenderError, Renderer};
+use wgpu::util::DeviceExt;
+
+/// Format of the offscreen target and the sprite sheet
+const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Segments used to approximate a circle
+const CIRCLE_SEGMENTS: usize = 32;
+
+const SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) textured: f32,
+};
+
+@group(0) @binding(0) var sheet: texture_2d<f32>;
+@group(0) @binding(1) var sheet_sampler: sampler;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) textured: f32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = uv;
+    out.color = color;
+    out.textured = textured;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(sheet, sheet_sampler, in.uv);
+    return select(in.color, texel, in.textured > 0.5);
+}
+"#;
+
+/// Position in clip space, sheet UV, linear RGBA and whether to sample
+/// the sheet
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+    textured: f32,
+}
+
+const VERTEX_FLOATS: usize = 9;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+    0 => Float32x2,
+    1 => Float32x2,
+    2 => Float32x4,
+    3 => Float32,
+];
+
+/// Where finished frames go
+enum Target {
+    /// An offscreen texture, for tests and tools that read frames back
+    Texture(wgpu::Texture),
+    /// A page canvas, presented after every frame, with text drawn on a
+    /// 2D canvas laid over it
+    #[cfg(target_arch = "wasm32")]
+    Canvas {
+        canvas: web_sys::HtmlCanvasElement,
+        surface: wgpu::Surface<'static>,
+        config: wgpu::SurfaceConfiguration,
+        text: TextOverlay,
+    },
+}
+
+/// A 2D canvas laid over the WebGPU one for the text the GPU path doesn't
+/// rasterize. Text always lands above the GPU shapes, which is where the
+/// scenes draw it anyway.
+#[cfg(target_arch = "wasm32")]
+struct TextOverlay {
+    canvas: web_sys::HtmlCanvasElement,
+    ctx: web_sys::CanvasRenderingContext2d,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl TextOverlay {
+    /// Insert an overlay right after `below`; it lets the pointer through,
+    /// so input still reaches the game canvas
+    fn new(below: &web_sys::HtmlCanvasElement) -> Result<Self, wasm_bindgen::JsValue> {
+        use wasm_bindgen::JsCast;
+
+        let document = below
+            .owner_document()
+            .ok_or("the canvas should be in a document")?;
+        let canvas = document
+            .create_element("canvas")?
+            .dyn_into::<web_sys::HtmlCanvasElement>()?;
+        canvas.set_attribute("aria-hidden", "true")?;
+        below.after_with_node_1(&canvas)?;
+        let ctx = canvas
+            .get_context("2d")?
+            .ok_or("should have a 2d context")?
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+        Ok(Self { canvas, ctx })
+    }
+
+    /// Line up with `below`, wherever the page has put it, and draw the
+    /// text that comes after the list's last `Clear`
+    #[allow(deprecated)] // Same fill_style API as the 2D renderer
+    fn draw(
+        &self,
+        below: &web_sys::HtmlCanvasElement,
+        list: &DrawList,
+    ) -> Result<(), wasm_bindgen::JsValue> {
+        let style = format!(
+            "position: absolute; pointer-events: none; left: {}px; top: {}px; width: {}px; height: {}px",
+            below.offset_left(),
+            below.offset_top(),
+            below.offset_width(),
+            below.offset_height()
+        );
+        if self.canvas.get_attribute("style").as_deref() != Some(style.as_str()) {
+            self.canvas.set_attribute("style", &style)?;
+        }
+        if (self.canvas.width(), self.canvas.height()) != (below.width(), below.height()) {
+            self.canvas.set_width(below.width());
+            self.canvas.set_height(below.height());
+        }
+
+        let start = list
+            .commands()
+            .iter()
+            .rposition(|command| *command == DrawCommand::Clear)
+            .map_or(0, |clear| clear + 1);
+        self.ctx.clear_rect(
+            0.0,
+            0.0,
+            self.canvas.width() as f64,
+            self.canvas.height() as f64,
+        );
+        for command in &list.commands()[start..] {
+            if let DrawCommand::Text {
+                text,
+                x,
+                y,
+                size,
+                color,
+            } = command
+            {
+                self.ctx
+                    .set_fill_style(&wasm_bindgen::JsValue::from_str(&color.css()));
+                self.ctx.set_font(&format!("{size}px sans-serif"));
+                self.ctx.set_text_align("center");
+                self.ctx.fill_text(text, *x, *y)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for TextOverlay {
+    fn drop(&mut self) {
+        self.canvas.remove();
+    }
+}
+
+/// Draws on the GPU, into an offscreen texture or, in browsers with
+/// WebGPU, a page canvas. The GPU path doesn't rasterize text: on a canvas
+/// it goes to an overlay, and offscreen `DrawCommand::Text` is skipped.
+pub struct WgpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bind_group: wgpu::BindGroup,
+    target: Target,
+    /// Sheet size in pixels, once one has been uploaded
+    sheet_size: Option<(f32, f32)>,
+    /// Sheet image still decoding, uploaded on the first frame after
+    #[cfg(target_arch = "wasm32")]
+    pending_sheet: Option<web_sys::HtmlImageElement>,
+    width: f64,
+    height: f64,
+}
+
+impl WgpuRenderer {
+    /// Pick an adapter and set up a `width` x `height` offscreen target
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(width: u32, height: u32) -> Result<Self, RenderError> {
+        pollster::block_on(Self::offscreen(width, height))
+    }
+
+    /// Like `new`, for callers that can't block
+    pub async fn offscreen(width: u32, height: u32) -> Result<Self, RenderError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(|_| RenderError::NoAdapter)?;
+        let (device, queue) = request_device(&adapter).await?;
+        let target = Target::Texture(create_target(&device, width, height));
+        Ok(Self::with_target(
+            device,
+            queue,
+            target,
+            TEXTURE_FORMAT,
+            (width, height),
+        ))
+    }
+
+    /// Take over `canvas` with WebGPU and start loading the sheet at
+    /// `sprite_sheet_url`. A canvas used this way can't go back to 2D.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn for_canvas(
+        canvas: web_sys::HtmlCanvasElement,
+        sprite_sheet_url: &str,
+    ) -> Result<Self, RenderError> {
+        let (width, height) = (canvas.width().max(1), canvas.height().max(1));
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let surface = instance
+            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
+            .map_err(|e| RenderError::Backend(e.to_string()))?;
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                compatible_surface: Some(&surface),
+                ..Default::default()
+            })
+            .await
+            .map_err(|_| RenderError::NoAdapter)?;
+        let (device, queue) = request_device(&adapter).await?;
+        let mut config = surface
+            .get_default_config(&adapter, width, height)
+            .ok_or(RenderError::NoAdapter)?;
+        // Browsers offer plain formats; draw through an sRGB view so colours
+        // blend as they do on the 2D canvas
+        let format = config.format.add_srgb_suffix();
+        config.view_formats.push(format);
+        surface.configure(&device, &config);
+        let text = TextOverlay::new(&canvas).map_err(|e| RenderError::Backend(format!("{e:?}")))?;
+
+        let target = Target::Canvas {
+            canvas,
+            surface,
+            config,
+            text,
+        };
+        let mut renderer = Self::with_target(device, queue, target, format, (width, height));
+        renderer.load_sprite_sheet(sprite_sheet_url)?;
+        Ok(renderer)
+    }
+
+    fn with_target(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        target: Target,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("draw list shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sprite sheet"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("draw list layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("draw list pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: (VERTEX_FLOATS * std::mem::size_of::<f32>()) as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview_mask: None,
+            cache: None,
+        });
+        // Nearest filtering keeps pixel art crisp
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        // A blank 1x1 sheet until a real one is uploaded
+        let placeholder = create_texture(&device, 1, 1, wgpu::TextureUsages::TEXTURE_BINDING);
+        let bind_group = create_bind_group(&device, &bind_group_layout, &placeholder, &sampler);
+
+        Self {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            bind_group,
+            target,
+            sheet_size: None,
+            #[cfg(target_arch = "wasm32")]
+            pending_sheet: None,
+            width: width as f64,
+            height: height as f64,
+        }
+    }
+
+    /// Upload the sheet sprites are cut from, as tightly packed RGBA8 pixels
+    pub fn set_sprite_sheet(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+    ) -> Result<(), RenderError> {
+        if width == 0 || height == 0 || rgba.len() != width as usize * height as usize * 4 {
+            return Err(RenderError::Backend(format!(
+                "sprite sheet should be {width}x{height} RGBA pixels"
+            )));
+        }
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        let sheet = create_texture(&self.device, width, height, usage);
+        self.queue.write_texture(
+            sheet.as_image_copy(),
+            rgba,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            sheet.size(),
+        );
+        self.use_sheet(&sheet);
+        Ok(())
+    }
+
+    fn use_sheet(&mut self, sheet: &wgpu::Texture) {
+        self.bind_group =
+            create_bind_group(&self.device, &self.bind_group_layout, sheet, &self.sampler);
+        self.sheet_size = Some((sheet.width() as f32, sheet.height() as f32));
+    }
+
+    /// Copy the pending sheet image to the GPU once the browser has decoded it
+    #[cfg(target_arch = "wasm32")]
+    fn upload_pending_sheet(&mut self) {
+        let Some(image) = self
+            .pending_sheet
+            .take_if(|image| image.complete() && image.natural_width() > 0)
+        else {
+            return;
+        };
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        let sheet = create_texture(
+            &self.device,
+            image.natural_width(),
+            image.natural_height(),
+            usage,
+        );
+        self.queue.copy_external_image_to_texture(
+            &wgpu::CopyExternalImageSourceInfo {
+                source: wgpu::ExternalImageSource::HTMLImageElement(image),
+                origin: wgpu::Origin2d::ZERO,
+                flip_y: false,
+            },
+            wgpu::CopyExternalImageDestInfo {
+                texture: &sheet,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+                color_space: wgpu::PredefinedColorSpace::Srgb,
+                premultiplied_alpha: false,
+            },
+            sheet.size(),
+        );
+        self.use_sheet(&sheet);
+    }
+
+    /// The offscreen texture frames are drawn into, for reading back, or
+    /// `None` when drawing to a canvas
+    pub fn target(&self) -> Option<&wgpu::Texture> {
+        match &self.target {
+            Target::Texture(texture) => Some(texture),
+            #[cfg(target_arch = "wasm32")]
+            Target::Canvas { .. } => None,
+        }
+    }
+
+    /// Triangles for everything after the last `Clear`, and whether the
+    /// target is cleared first
+    fn tessellate(&self, list: &DrawList) -> (Vec<f32>, bool) {
+        let mut vertices = Vec::new();
+        let mut clear = false;
+        let mut mesh = Mesh {
+            vertices: &mut vertices,
+            width: self.width as f32,
+            height: self.height as f32,
+        };
+        for command in list.commands() {
+            match command {
+                DrawCommand::Clear => {
+                    mesh.vertices.clear();
+                    clear = true;
+                }
+                DrawCommand::FillRect { x, y, w, h, color } => {
+                    mesh.quad([*x, *y, *w, *h], [0.0; 4], linear(*color), 0.0);
+                }
+                DrawCommand::FillCircle {
+                    x,
+                    y,
+                    radius,
+                    color,
+                } => mesh.circle(*x, *y, *radius, linear(*color)),
+                DrawCommand::Text { .. } => {}
+                DrawCommand::Sprite { frame, x, y } => {
+                    let Some((sheet_w, sheet_h)) = self.sheet_size else {
+                        continue;
+                    };
+                    let uv = [
+                        frame.x as f32 / sheet_w,
+                        frame.y as f32 / sheet_h,
+                        frame.w as f32 / sheet_w,
+                        frame.h as f32 / sheet_h,
+                    ];
+                    let rect = [x - frame.w / 2.0, y - frame.h / 2.0, frame.w, frame.h];
+                    mesh.quad(rect, uv, [1.0; 4], 1.0);
+                }
+            }
+        }
+        (vertices, clear)
+    }
+}
+
+impl Renderer for WgpuRenderer {
+    fn sprites_ready(&self) -> bool {
+        self.sheet_size.is_some()
+    }
+
+    /// Start loading the sheet image at `url`; only browsers can fetch
+    /// one, elsewhere pixels go through `set_sprite_sheet`
+    fn load_sprite_sheet(&mut self, url: &str) -> Result<(), RenderError> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let image = web_sys::HtmlImageElement::new()
+                .map_err(|e| RenderError::Backend(format!("{e:?}")))?;
+            image.set_src(url);
+            self.pending_sheet = Some(image);
+            Ok(())
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Err(RenderError::Backend(format!(
+            "can't load {url} outside a browser; upload its pixels instead"
+        )))
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        self.width = width.max(1.0);
+        self.height = height.max(1.0);
+        let (width, height) = (self.width as u32, self.height as u32);
+        match &mut self.target {
+            Target::Texture(texture) => *texture = create_target(&self.device, width, height),
+            #[cfg(target_arch = "wasm32")]
+            Target::Canvas {
+                canvas,
+                surface,
+                config,
+                ..
+            } => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+                config.width = width;
+                config.height = height;
+                surface.configure(&self.device, config);
+            }
+        }
+    }
+
+    fn render(&mut self, list: &DrawList) -> Result<(), RenderError> {
+        #[cfg(target_arch = "wasm32")]
+        self.upload_pending_sheet();
+
+        let (view, frame): (_, Option<wgpu::SurfaceTexture>) = match &self.target {
+            Target::Texture(texture) => (
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                None,
+            ),
+            #[cfg(target_arch = "wasm32")]
+            Target::Canvas {
+                surface, config, ..
+            } => {
+                let frame = match surface.get_current_texture() {
+                    wgpu::CurrentSurfaceTexture::Success(frame)
+                    | wgpu::CurrentSurfaceTexture::Suboptimal(frame) => frame,
+                    // Skip this frame; the next one gets a fresh texture
+                    wgpu::CurrentSurfaceTexture::Outdated => {
+                        surface.configure(&self.device, config);
+                        return Ok(());
+                    }
+                    wgpu::CurrentSurfaceTexture::Timeout
+                    | wgpu::CurrentSurfaceTexture::Occluded => return Ok(()),
+                    wgpu::CurrentSurfaceTexture::Lost => {
+                        return Err(RenderError::Backend("the canvas surface was lost".into()))
+                    }
+                    wgpu::CurrentSurfaceTexture::Validation => {
+                        return Err(RenderError::Backend(
+                            "the canvas surface failed validation".into(),
+                        ))
+                    }
+                };
+                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+                    format: Some(config.format.add_srgb_suffix()),
+                    ..Default::default()
+                });
+                (view, Some(frame))
+            }
+        };
+
+        let (vertices, clear) = self.tessellate(list);
+        let bytes: Vec<u8> = vertices.iter().flat_map(|f| f.to_ne_bytes()).collect();
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("draw list vertices"),
+                contents: &bytes,
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let load = if clear {
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+            } else {
+                wgpu::LoadOp::Load
+            };
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("draw list pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            if !vertices.is_empty() {
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &self.bind_group, &[]);
+                pass.set_vertex_buffer(0, buffer.slice(..));
+                pass.draw(0..(vertices.len() / VERTEX_FLOATS) as u32, 0..1);
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+        if let Some(frame) = frame {
+            frame.present();
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Target::Canvas { canvas, text, .. } = &self.target {
+            text.draw(canvas, list)
+                .map_err(|e| RenderError::Backend(format!("{e:?}")))?;
+        }
+        Ok(())
+    }
+}
+
+async fn request_device(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), RenderError> {
+    adapter
+        .request_device(&wgpu::DeviceDescriptor::default())
+        .await
+        .map_err(|e| RenderError::Backend(e.to_string()))
+}
+
+/// Triangles being built from pixel-space shapes
+struct Mesh<'a> {
+    vertices: &'a mut Vec<f32>,
+    width: f32,
+    height: f32,
+}
+
+impl Mesh<'_> {
+    fn vertex(&mut self, x: f64, y: f64, uv: [f32; 2], color: [f32; 4], textured: f32) {
+        let vertex = Vertex {
+            position: [
+                x as f32 / self.width * 2.0 - 1.0,
+                1.0 - y as f32 / self.height * 2.0,
+            ],
+            uv,
+            color,
+            textured,
+        };
+        self.vertices.extend_from_slice(&vertex.position);
+        self.vertices.extend_from_slice(&vertex.uv);
+        self.vertices.extend_from_slice(&vertex.color);
+        self.vertices.push(vertex.textured);
+    }
+
+    /// Two triangles covering `[x, y, w, h]`, mapped to `[u, v, uw, vh]`
+    fn quad(&mut self, rect: [f64; 4], uv: [f32; 4], color: [f32; 4], textured: f32) {
+        let [x, y, w, h] = rect;
+        let [u, v, uw, vh] = uv;
+        let corners = [
+            (x, y, u, v),
+            (x + w, y, u + uw, v),
+            (x, y + h, u, v + vh),
+            (x + w, y, u + uw, v),
+            (x + w, y + h, u + uw, v + vh),
+            (x, y + h, u, v + vh),
+        ];
+        for (cx, cy, cu, cv) in corners {
+            self.vertex(cx, cy, [cu, cv], color, textured);
+        }
+    }
+
+    fn circle(&mut self, x: f64, y: f64, radius: f64, color: [f32; 4]) {
+        let point = |i: usize| {
+            let angle = i as f64 / CIRCLE_SEGMENTS as f64 * std::f64::consts::TAU;
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            let (ax, ay) = point(i);
+            let (bx, by) = point(i + 1);
+            self.vertex(x, y, [0.0; 2], color, 0.0);
+            self.vertex(ax, ay, [0.0; 2], color, 0.0);
+            self.vertex(bx, by, [0.0; 2], color, 0.0);
+        }
+    }
+}
+
+/// An sRGB colour as the linear values the shader blends in
+fn linear(color: Color) -> [f32; 4] {
+    let channel = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        color.a as f32 / 255.0,
+    ]
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    usage: wgpu::TextureUsages,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TEXTURE_FORMAT,
+        usage,
+        view_formats: &[],
+    })
+}
+
+fn create_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC;
+    create_texture(device, width, height, usage)
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sheet: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    let view = sheet.create_view(&wgpu::TextureViewDescriptor::default());
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("sprite sheet"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{BACKGROUND_COLOR, PLAYER_COLOR};
+
+    #[test]
+    fn test_linear_colour_channels() {
+        assert_eq!(linear(Color::rgb(0, 0, 0)), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(linear(Color::rgb(255, 255, 255)), [1.0, 1.0, 1.0, 1.0]);
+        let [r, g, b, _] = linear(BACKGROUND_COLOR);
+        assert!(r < 0.02 && r == g && g == b);
+    }
+
+    #[test]
+    fn test_renders_without_sheet_when_adapter_available() {
+        // CI machines often have no GPU at all
+        let Ok(mut renderer) = WgpuRenderer::new(64, 64) else {
+            return;
+        };
+        assert!(!renderer.sprites_ready());
+
+        let mut list = DrawList::new();
+        list.push(DrawCommand::Clear);
+        list.push(DrawCommand::FillCircle {
+            x: 32.0,
+            y: 32.0,
+            radius: 8.0,
+            color: PLAYER_COLOR,
+        });
+        let (vertices, clear) = renderer.tessellate(&list);
+        assert!(clear);
+        assert_eq!(vertices.len(), CIRCLE_SEGMENTS * 3 * VERTEX_FLOATS);
+        renderer.render(&list).unwrap();
+
+        assert!(renderer.set_sprite_sheet(2, 2, &[0; 4]).is_err());
+        renderer.set_sprite_sheet(2, 2, &[255; 16]).unwrap();
+        assert!(renderer.sprites_ready());
+    }
+}
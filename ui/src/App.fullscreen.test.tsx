@@ -6,10 +6,13 @@ import App from './App';
 Object.defineProperty(window, 'wasmBindings', {
   value: {
     default: vi.fn().mockResolvedValue(undefined),
+    drain_panic_reports: vi.fn().mockReturnValue('[]'),
     start_game: vi.fn().mockReturnValue({
       update: vi.fn(),
       render: vi.fn(),
       resize: vi.fn(),
+      drain_errors: vi.fn().mockReturnValue('[]'),
+      drain_events: vi.fn().mockReturnValue('[]'),
     }),
   },
   writable: true,
@@ -1,6 +1,7 @@
 import { createSignal, onMount, onCleanup } from 'solid-js';
 import { ErrorToastManager } from './components/ErrorToast';
 import { errorLogger } from './utils/error-logger';
+import { publishGameEvents } from './utils/game-events';
 import GameUI from './components/GameUI';
 import {
   EnhancedGameInstance,
@@ -49,9 +50,18 @@ const App = () => {
       setGameStatus('Creating game instance...');
 
       if (canvasRef) {
-        // Create the enhanced game instance
-        const game = window.wasmBindings.start_game(
-          'game-canvas'
+        // Create the enhanced game instance. `?renderer=WebGpu` opts into
+        // the GPU renderer in builds made with `npm run build-game-gpu`.
+        const renderer = new URLSearchParams(window.location.search).get(
+          'renderer'
+        );
+        const game = (
+          renderer
+            ? await window.wasmBindings.start_game_with(
+                'game-canvas',
+                renderer
+              )
+            : window.wasmBindings.start_game('game-canvas')
         ) as EnhancedGameInstance;
         setGameInstance(game);
         setGameStatus('Game ready!');
@@ -80,6 +90,38 @@ const App = () => {
     }
   };
 
+  // Forward errors (including panics) reported by the Rust core to the toasts
+  const forwardGameErrors = (game: EnhancedGameInstance) => {
+    try {
+      errorLogger.logGameReports(JSON.parse(game.drain_errors()));
+    } catch {
+      // After a panic the loop's own error handling takes over
+    }
+  };
+
+  // Hand game events to subscribers once the frame's calls into the game
+  // have returned, so they can call back into it
+  const forwardGameEvents = (game: EnhancedGameInstance) => {
+    try {
+      publishGameEvents(JSON.parse(game.drain_events()));
+    } catch (error) {
+      errorLogger.logGameError('Failed to deliver game events', {
+        error: String(error),
+      });
+    }
+  };
+
+  // Panics leave the game instance unusable, so read them without it
+  const forwardPanicReports = () => {
+    try {
+      errorLogger.logGameReports(
+        JSON.parse(window.wasmBindings.drain_panic_reports())
+      );
+    } catch {
+      // Nothing more can be reported
+    }
+  };
+
   const startGameLoop = () => {
     if (!gameInstance()) return;
 
@@ -91,12 +133,16 @@ const App = () => {
         if (game && isGameRunning()) {
           game.update();
           game.render();
+          forwardGameErrors(game);
+          forwardGameEvents(game);
           animationId = requestAnimationFrame(gameLoop);
         }
       } catch (error) {
         const errorMessage =
           error instanceof Error ? error.message : 'Game loop error';
 
+        forwardPanicReports();
+
         // Log error with game context
         errorLogger.logGameError(`Game loop error: ${errorMessage}`, {
           canvasSize: canvasSize(),
@@ -169,6 +215,11 @@ const App = () => {
     }
   };
 
+  // Freeze the simulation while the tab is hidden
+  const handleVisibilityChange = () => {
+    gameInstance()?.set_paused?.(document.hidden);
+  };
+
   const handleCanvasReady = (canvas: HTMLCanvasElement) => {
     canvasRef = canvas;
 
@@ -214,11 +265,13 @@ const App = () => {
 
     // Add resize listener
     window.addEventListener('resize', resizeCanvas);
+    document.addEventListener('visibilitychange', handleVisibilityChange);
   });
 
   onCleanup(() => {
     stopGame();
     window.removeEventListener('resize', resizeCanvas);
+    document.removeEventListener('visibilitychange', handleVisibilityChange);
 
     // Cleanup input manager
     if (inputManager && canvasRef) {
@@ -1,5 +1,26 @@
-import { Component, Show } from 'solid-js';
-import { GameState, GameScreen, EnhancedGameInstance } from './GameTypes';
+import {
+  Component,
+  For,
+  Show,
+  createSignal,
+  onCleanup,
+  onMount,
+} from 'solid-js';
+import {
+  GameState,
+  GameScreen,
+  EnhancedGameInstance,
+  Region,
+} from './GameTypes';
+import type {
+  ChatChannel,
+  ChatLine,
+  CommandResult,
+  ServerStatus,
+  TradeOffer,
+  TradeSideView,
+} from '../types/game-state.generated';
+import { probeServers } from '../utils/server-ping';
 
 interface GameScreenProps {
   gameState: GameState | null;
@@ -7,6 +28,348 @@ interface GameScreenProps {
   gameInstance: EnhancedGameInstance | null;
 }
 
+// Login Screen Component - first step of the front-end flow
+export const LoginScreen: Component<GameScreenProps> = props => {
+  const [name, setName] = createSignal('');
+  const [nameError, setNameError] = createSignal<string | null>(null);
+
+  const submit = (e: Event) => {
+    e.preventDefault();
+    const response = props.gameInstance?.set_player_name(name());
+    if (!response) return;
+
+    // The game validates the name and explains rejections
+    const result: CommandResult = JSON.parse(response);
+    setNameError(result.status === 'Error' ? result.message : null);
+  };
+
+  return (
+    <div class="game-screen login-screen">
+      <div class="screen-content">
+        <h1 class="game-title">⚔️ Copilot RPG</h1>
+        <p class="subtitle">Enter your name, adventurer</p>
+
+        <form class="login-form" onSubmit={submit}>
+          <input
+            class="player-name-input"
+            type="text"
+            placeholder="Player name"
+            maxLength={16}
+            value={name()}
+            onInput={e => setName(e.currentTarget.value)}
+          />
+          <Show when={nameError()}>
+            <p class="name-error">{nameError()}</p>
+          </Show>
+          <button class="continue-btn" type="submit">
+            Continue
+          </button>
+        </form>
+
+        <p class="instructions">Press Enter to continue</p>
+      </div>
+    </div>
+  );
+};
+
+const REGIONS: { region: Region; label: string; description: string }[] = [
+  { region: 'EU', label: '🇪🇺 Europe', description: 'Servers in Frankfurt' },
+  { region: 'Asia', label: '🌏 Asia', description: 'Servers in Singapore' },
+  { region: 'Vietnam', label: '🇻🇳 Vietnam', description: 'Servers in Hanoi' },
+];
+
+// Server Selection Screen Component
+export const ServerSelectionScreen: Component<GameScreenProps> = props => {
+  const [servers, setServers] = createSignal<ServerStatus[]>([]);
+
+  onMount(async () => {
+    if (props.gameInstance?.get_servers) {
+      setServers(await probeServers(props.gameInstance));
+    }
+  });
+
+  // Lowest latency among the region's servers, if any answered
+  const regionLatency = (region: Region) => {
+    const latencies = servers()
+      .filter(server => server.region === region && server.latency_ms !== null)
+      .map(server => server.latency_ms as number);
+    return latencies.length ? Math.round(Math.min(...latencies)) : null;
+  };
+
+  return (
+    <div class="game-screen server-selection-screen">
+      <div class="screen-content">
+        <h2>🌍 Select Region</h2>
+
+        <div class="region-options">
+          <For each={REGIONS}>
+            {option => (
+              <button
+                class="region-btn"
+                classList={{
+                  selected: props.gameState?.region === option.region,
+                }}
+                onClick={() => props.gameInstance?.set_region(option.region)}
+              >
+                <span>{option.label}</span>
+                <span class="region-desc">
+                  {option.description}
+                  <Show when={regionLatency(option.region) !== null}>
+                    {` · ${regionLatency(option.region)} ms`}
+                  </Show>
+                </span>
+              </button>
+            )}
+          </For>
+        </div>
+
+        <div class="region-actions">
+          <button
+            class="menu-option-btn"
+            disabled={!servers().some(server => server.latency_ms !== null)}
+            onClick={() => props.gameInstance?.auto_select_region()}
+          >
+            ⚡ Lowest ping
+          </button>
+          <button
+            class="back-btn"
+            onClick={() =>
+              props.gameInstance?.transition_to_screen('LoginScreen')
+            }
+          >
+            ← Back
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+// Main Menu Screen Component
+export const MainMenuScreen: Component<GameScreenProps> = props => {
+  return (
+    <div class="game-screen main-menu-screen">
+      <div class="screen-content">
+        <h2>🏰 Main Menu</h2>
+
+        <div class="player-info">
+          <p class="welcome-message">
+            Welcome, {props.gameState?.player_name}!
+          </p>
+          <p class="region-info">
+            Region:{' '}
+            <span class="region-name">{props.gameState?.region}</span>
+          </p>
+        </div>
+
+        <div class="menu-actions">
+          <button
+            class="start-game-btn"
+            onClick={() =>
+              props.gameInstance?.transition_to_screen('GameHUD')
+            }
+          >
+            ▶️ Enter World
+          </button>
+
+          <div class="menu-options">
+            <button
+              class="menu-option-btn"
+              onClick={() =>
+                props.gameInstance?.transition_to_screen('ServerSelection')
+              }
+            >
+              🌍 Change Region
+            </button>
+            <button
+              class="menu-option-btn"
+              onClick={() =>
+                props.gameInstance?.transition_to_screen('HelpModal')
+              }
+            >
+              ❓ Help
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+// Chat lines kept on screen
+const MAX_CHAT_LINES = 50;
+
+const channelLabel = (channel: ChatChannel) =>
+  typeof channel === 'string' ? channel : 'Whisper';
+
+// Chat Box Component - while the input has focus the game leaves keys alone,
+// so typing WASD doesn't move the player
+const ChatBox: Component<{
+  gameInstance: EnhancedGameInstance | null;
+}> = props => {
+  const [lines, setLines] = createSignal<ChatLine[]>([]);
+  const [draft, setDraft] = createSignal('');
+  let input: HTMLInputElement | undefined;
+
+  onMount(() => {
+    const poll = setInterval(() => {
+      const json = props.gameInstance?.drain_chat();
+      if (!json) return;
+      const received = JSON.parse(json) as ChatLine[];
+      if (received.length > 0) {
+        setLines(current => [...current, ...received].slice(-MAX_CHAT_LINES));
+      }
+    }, 250);
+    onCleanup(() => {
+      clearInterval(poll);
+      props.gameInstance?.set_chat_active(false);
+    });
+  });
+
+  const send = (e: Event) => {
+    e.preventDefault();
+    // Party invites and trade requests share the chat box with the chat
+    // channel prefixes. Names with spaces are quoted, as in whispers.
+    const invite = draft().match(/^\/invite\s+(?:"([^"]+)"|(\S+))/i);
+    const trade = draft().match(/^\/trade\s+(?:"([^"]+)"|(\S+))/i);
+    const sent = invite
+      ? props.gameInstance?.party_invite(invite[1] ?? invite[2])
+      : trade
+        ? props.gameInstance?.trade_request(trade[1] ?? trade[2])
+        : props.gameInstance?.send_chat(draft());
+    if (sent) {
+      setDraft('');
+    }
+  };
+
+  return (
+    <div class="chat-box">
+      <div class="chat-lines">
+        <For each={lines()}>
+          {line => (
+            <div class="chat-line">
+              <span class="chat-channel">[{channelLabel(line.channel)}]</span>
+              <span class="chat-from">{line.from}:</span>
+              <span class="chat-text">{line.text}</span>
+            </div>
+          )}
+        </For>
+      </div>
+      <form onSubmit={send}>
+        <input
+          ref={input}
+          class="chat-input"
+          value={draft()}
+          placeholder="Chat... (/g global, /z zone, /p party, /w Name, /invite Name, /trade Name)"
+          onInput={e => setDraft(e.currentTarget.value)}
+          onFocus={() => props.gameInstance?.set_chat_active(true)}
+          onBlur={() => props.gameInstance?.set_chat_active(false)}
+          onKeyDown={e => {
+            if (e.key === 'Escape') input?.blur();
+          }}
+        />
+      </form>
+    </div>
+  );
+};
+
+// Party Frame Component - party members' health, plus any pending invite
+const PartyFrame: Component<GameScreenProps> = props => {
+  const party = () => props.gameState?.party;
+  const isLeader = (id: number) => party()?.leader === id;
+  const leading = () =>
+    party()?.members.some(
+      member =>
+        isLeader(member.id) && member.name === props.gameState?.player_name
+    ) ?? false;
+
+  return (
+    <div class="party-frame">
+      <Show when={props.gameState?.party_invite}>
+        <div class="party-invite">
+          <span>🤝 {props.gameState!.party_invite} invited you to a party</span>
+          <button
+            class="party-btn"
+            onClick={() => props.gameInstance?.party_accept()}
+          >
+            Accept
+          </button>
+          <button
+            class="party-btn"
+            onClick={() => props.gameInstance?.party_decline()}
+          >
+            Decline
+          </button>
+        </div>
+      </Show>
+
+      <Show when={party()}>
+        <For each={party()!.members}>
+          {member => (
+            <div class="party-member">
+              <span class="party-member-name">
+                {isLeader(member.id) ? '👑 ' : ''}
+                {member.name} (Lv {member.level})
+              </span>
+              <div class="party-hp-bar">
+                <div
+                  class="party-hp-fill"
+                  style={{ width: `${(member.hp / member.max_hp) * 100}%` }}
+                ></div>
+              </div>
+              <Show
+                when={leading() && member.name !== props.gameState?.player_name}
+              >
+                <button
+                  class="party-btn"
+                  title="Make leader"
+                  onClick={() => props.gameInstance?.party_promote(member.id)}
+                >
+                  👑
+                </button>
+                <button
+                  class="party-btn"
+                  title="Kick"
+                  onClick={() => props.gameInstance?.party_kick(member.id)}
+                >
+                  ✖
+                </button>
+              </Show>
+            </div>
+          )}
+        </For>
+        <button
+          class="party-btn"
+          onClick={() => props.gameInstance?.party_leave()}
+        >
+          Leave Party
+        </button>
+      </Show>
+    </div>
+  );
+};
+
+// Trade Request Prompt Component - answers another player's trade request
+const TradeRequestPrompt: Component<GameScreenProps> = props => (
+  <Show when={props.gameState?.trade_request}>
+    <div class="trade-request">
+      <span>⚖️ {props.gameState!.trade_request} wants to trade</span>
+      <button
+        class="party-btn"
+        onClick={() => props.gameInstance?.trade_accept()}
+      >
+        Accept
+      </button>
+      <button
+        class="party-btn"
+        onClick={() => props.gameInstance?.trade_decline()}
+      >
+        Decline
+      </button>
+    </div>
+  </Show>
+);
+
 // Game HUD Overlay Component - The main game interface
 export const GameHUDScreen: Component<GameScreenProps> = props => {
   const toggleInventory = () => {
@@ -35,13 +398,28 @@ export const GameHUDScreen: Component<GameScreenProps> = props => {
               {Math.round(props.gameState!.player_position[1])})
             </span>
           </Show>
+          <Show when={props.gameState?.is_loading}>
+            <span class="connection-status">📡 Connecting...</span>
+          </Show>
         </div>
 
         <div class="game-info">
-          <span class="level">Level 1</span>
+          <span class="level">Level {props.gameState?.level ?? 1}</span>
           <div class="health-bar">
-            <div class="health-fill" style={{ width: '80%' }}></div>
-            <span class="health-text">HP: 80/100</span>
+            <div
+              class="health-fill"
+              style={{
+                width: `${
+                  ((props.gameState?.hp ?? 0) /
+                    (props.gameState?.max_hp || 1)) *
+                  100
+                }%`,
+              }}
+            ></div>
+            <span class="health-text">
+              HP: {Math.round(props.gameState?.hp ?? 0)}/
+              {Math.round(props.gameState?.max_hp ?? 0)}
+            </span>
           </div>
         </div>
       </div>
@@ -92,6 +470,10 @@ export const GameHUDScreen: Component<GameScreenProps> = props => {
         </div>
       </div>
 
+      <PartyFrame {...props} />
+      <TradeRequestPrompt {...props} />
+      <ChatBox gameInstance={props.gameInstance} />
+
       {/* Minimap */}
       <div class="minimap">
         <div class="minimap-content">
@@ -106,7 +488,7 @@ export const GameHUDScreen: Component<GameScreenProps> = props => {
 // Inventory Modal Component
 export const InventoryScreen: Component<GameScreenProps> = props => {
   const closeInventory = () => {
-    props.gameInstance?.transition_to_screen('GameHUD');
+    props.gameInstance?.dispatch(JSON.stringify({ type: 'PopScreen' }));
   };
 
   return (
@@ -173,7 +555,7 @@ export const InventoryScreen: Component<GameScreenProps> = props => {
 // Shop Modal Component
 export const ShopScreen: Component<GameScreenProps> = props => {
   const closeShop = () => {
-    props.gameInstance?.transition_to_screen('GameHUD');
+    props.gameInstance?.dispatch(JSON.stringify({ type: 'PopScreen' }));
   };
 
   return (
@@ -267,10 +649,162 @@ export const ShopScreen: Component<GameScreenProps> = props => {
   );
 };
 
+const TradeSide: Component<{ title: string; side: TradeSideView }> = props => (
+  <div
+    class="trade-side"
+    classList={{
+      locked: props.side.locked,
+      confirmed: props.side.confirmed,
+    }}
+  >
+    <h4>
+      {props.title}
+      {props.side.confirmed ? ' ✅' : props.side.locked ? ' 🔒' : ''}
+    </h4>
+    <For each={props.side.offer.items}>
+      {item => (
+        <div class="trade-item">
+          <span class="item-name">{item.item_id}</span>
+          <span class="item-count">x{item.quantity}</span>
+        </div>
+      )}
+    </For>
+    <div class="trade-gold">💰 {props.side.offer.gold}</div>
+  </div>
+);
+
+// Trade Modal Component - both offers side by side. Offers can only change
+// while unlocked, and any change means both players confirm again.
+export const TradeScreen: Component<GameScreenProps> = props => {
+  const trade = () => props.gameState?.trade;
+  const [draft, setDraft] = createSignal<Record<string, number>>({});
+  const [gold, setGold] = createSignal(0);
+
+  const setQuantity = (itemId: string, quantity: number) =>
+    setDraft(current => ({ ...current, [itemId]: Math.max(0, quantity) }));
+
+  const sendOffer = () => {
+    const offer: TradeOffer = {
+      items: Object.entries(draft())
+        .filter(([, quantity]) => quantity > 0)
+        .map(([item_id, quantity]) => ({ item_id, quantity })),
+      gold: Math.max(0, Math.floor(gold())),
+    };
+    props.gameInstance?.trade_offer(JSON.stringify(offer));
+  };
+
+  const cancelTrade = () => {
+    props.gameInstance?.trade_cancel();
+  };
+
+  return (
+    <div class="game-screen trade-screen">
+      <div class="modal-overlay">
+        <div class="modal-panel trade-panel">
+          <div class="modal-header">
+            <h3>⚖️ Trade with {trade()?.partner_name}</h3>
+            <button class="close-btn" onClick={cancelTrade}>
+              ✕
+            </button>
+          </div>
+
+          <Show when={trade()}>
+            <div class="trade-content">
+              <TradeSide title="Your offer" side={trade()!.mine} />
+              <TradeSide
+                title={`${trade()!.partner_name}'s offer`}
+                side={trade()!.theirs}
+              />
+            </div>
+
+            <Show when={!trade()!.mine.locked}>
+              <div class="trade-editor">
+                <For each={props.gameState?.inventory.slots ?? []}>
+                  {slot => (
+                    <label class="trade-item">
+                      <span class="item-name">{slot.item_id}</span>
+                      <input
+                        type="number"
+                        min="0"
+                        max={slot.quantity}
+                        value={draft()[slot.item_id] ?? 0}
+                        onInput={e =>
+                          setQuantity(
+                            slot.item_id,
+                            e.currentTarget.valueAsNumber || 0
+                          )
+                        }
+                      />
+                    </label>
+                  )}
+                </For>
+                <label class="trade-item">
+                  <span class="item-name">💰 Gold</span>
+                  <input
+                    type="number"
+                    min="0"
+                    max={props.gameState?.gold ?? 0}
+                    value={gold()}
+                    onInput={e => setGold(e.currentTarget.valueAsNumber || 0)}
+                  />
+                </label>
+                <button class="trade-btn" onClick={sendOffer}>
+                  Update Offer
+                </button>
+              </div>
+            </Show>
+
+            <div class="trade-actions">
+              <Show
+                when={trade()!.mine.locked}
+                fallback={
+                  <button
+                    class="trade-btn"
+                    onClick={() => props.gameInstance?.trade_lock()}
+                  >
+                    🔒 Lock
+                  </button>
+                }
+              >
+                <button
+                  class="trade-btn"
+                  onClick={() => props.gameInstance?.trade_unlock()}
+                >
+                  🔓 Unlock
+                </button>
+              </Show>
+              <button
+                class="trade-btn confirm"
+                disabled={
+                  !trade()!.mine.locked ||
+                  !trade()!.theirs.locked ||
+                  trade()!.mine.confirmed
+                }
+                onClick={() => props.gameInstance?.trade_confirm()}
+              >
+                ✅ Confirm
+              </button>
+              <button class="trade-btn" onClick={cancelTrade}>
+                Cancel
+              </button>
+            </div>
+          </Show>
+
+          <div class="modal-footer">
+            <p class="help-text">
+              Lock your offer, then confirm once both sides are locked
+            </p>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+};
+
 // Help Modal Component
 export const HelpScreen: Component<GameScreenProps> = props => {
   const closeHelp = () => {
-    props.gameInstance?.transition_to_screen('GameHUD');
+    props.gameInstance?.dispatch(JSON.stringify({ type: 'PopScreen' }));
   };
 
   return (
@@ -292,6 +826,9 @@ export const HelpScreen: Component<GameScreenProps> = props => {
                   <strong>WASD</strong> or <strong>Arrow Keys</strong> - Move
                   your character
                 </li>
+                <li>
+                  <strong>F</strong> - Attack
+                </li>
                 <li>
                   <strong>Mouse</strong> - Look around (planned)
                 </li>
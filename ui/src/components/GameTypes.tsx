@@ -7,6 +7,11 @@ import {
   createEffect,
 } from 'solid-js';
 import { errorLogger } from '../utils/error-logger';
+import { onGameEvent } from '../utils/game-events';
+import type {
+  GameScreen,
+  GameStateView,
+} from '../types/game-state.generated';
 
 // Enhanced interface for the new RPG game backend
 export interface EnhancedGameInstance {
@@ -15,16 +20,44 @@ export interface EnhancedGameInstance {
   render(): void;
   resize(width: number, height: number): void;
   reset(): void;
+  set_paused(paused: boolean): void;
 
   // New RPG methods
   handle_input(eventType: string, data: string): boolean;
   get_current_screen(): string;
   get_game_state(): string;
+  dispatch(commandJson: string): string;
+  drain_errors(): string;
+  drain_events(): string;
 
   // UI control methods (new)
   transition_to_screen(screen: string): void;
-  set_player_name(name: string): void;
+  set_player_name(name: string): string;
   set_region(region: string): void;
+  get_servers(): string;
+  record_server_latency(serverId: string, latencyMs: number): void;
+  auto_select_region(): string | undefined;
+  connect(endpoint?: string): void;
+  disconnect(): void;
+  is_connected(): boolean;
+  set_chat_active(active: boolean): void;
+  is_chat_active(): boolean;
+  send_chat(input: string): boolean;
+  drain_chat(): string;
+  party_invite(name: string): boolean;
+  party_accept(): boolean;
+  party_decline(): boolean;
+  party_leave(): boolean;
+  party_kick(playerId: number): boolean;
+  party_promote(playerId: number): boolean;
+  trade_request(name: string): boolean;
+  trade_accept(): boolean;
+  trade_decline(): boolean;
+  trade_offer(offerJson: string): boolean;
+  trade_lock(): boolean;
+  trade_unlock(): boolean;
+  trade_confirm(): boolean;
+  trade_cancel(): boolean;
   get_player_position(): number[];
   is_player_moving(): boolean;
 
@@ -36,29 +69,18 @@ export interface EnhancedGameInstance {
 export interface WasmBindings {
   default(): Promise<void>;
   start_game(canvasId: string): EnhancedGameInstance;
+  start_game_with(
+    canvasId: string,
+    backend: string
+  ): Promise<EnhancedGameInstance>;
+  drain_panic_reports(): string;
 }
 
-// Game state types (matching Rust backend)
-export type GameScreen =
-  | 'LoginScreen'
-  | 'ServerSelection'
-  | 'MainMenu'
-  | 'GameHUD'
-  | 'Inventory'
-  | 'Shop'
-  | 'HelpModal';
-
-export type Region = 'EU' | 'Asia' | 'Vietnam';
-
-export interface GameState {
-  screen: GameScreen;
-  region?: Region;
-  player_name?: string;
-  is_loading: boolean;
-  error?: string;
-  player_position: [number, number];
-  ball_position: [number, number];
-}
+// Game state types (generated from the Rust `GameStateView`)
+export type { GameScreen };
+export type { Region } from '../types/game-state.generated';
+
+export type GameState = GameStateView;
 
 // Input handling utilities
 export class InputManager {
@@ -291,9 +313,12 @@ export const GameStateManager: Component<{
     // Update immediately and then every 100ms
     updateGameState();
     const interval = setInterval(updateGameState, 100);
+    // Screen changes and pickups show up without waiting for the next poll
+    const unsubscribe = onGameEvent(updateGameState);
 
     onCleanup(() => {
       clearInterval(interval);
+      unsubscribe();
     });
   });
 
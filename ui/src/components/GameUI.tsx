@@ -7,9 +7,13 @@ import {
 } from './GameTypes';
 import MobileControls from './MobileControls';
 import {
+  LoginScreen,
+  ServerSelectionScreen,
+  MainMenuScreen,
   GameHUDScreen,
   InventoryScreen,
   ShopScreen,
+  TradeScreen,
   HelpScreen,
 } from './GameScreens';
 import './MobileControls.css';
@@ -78,6 +82,18 @@ const ScreenSelector: Component<{
 
   return (
     <>
+      <Show when={props.screen === 'LoginScreen'}>
+        <LoginScreen {...screenProps()} />
+      </Show>
+
+      <Show when={props.screen === 'ServerSelection'}>
+        <ServerSelectionScreen {...screenProps()} />
+      </Show>
+
+      <Show when={props.screen === 'MainMenu'}>
+        <MainMenuScreen {...screenProps()} />
+      </Show>
+
       <Show when={props.screen === 'GameHUD'}>
         <GameHUDScreen {...screenProps()} />
       </Show>
@@ -90,6 +106,10 @@ const ScreenSelector: Component<{
         <ShopScreen {...screenProps()} />
       </Show>
 
+      <Show when={props.screen === 'Trade'}>
+        <TradeScreen {...screenProps()} />
+      </Show>
+
       <Show when={props.screen === 'HelpModal'}>
         <HelpScreen {...screenProps()} />
       </Show>
@@ -122,12 +122,14 @@ describe('MobileControls', () => {
       render(() => <MobileControlButtons inputManager={mockInputManager} />);
 
       const buttons = document.querySelectorAll('.mobile-control-btn');
-      expect(buttons).toHaveLength(3);
+      expect(buttons).toHaveLength(4);
 
+      const attackBtn = document.querySelector('.attack-btn');
       const inventoryBtn = document.querySelector('.inventory-btn');
       const shopBtn = document.querySelector('.shop-btn');
       const helpBtn = document.querySelector('.help-btn');
 
+      expect(attackBtn).toBeTruthy();
       expect(inventoryBtn).toBeTruthy();
       expect(shopBtn).toBeTruthy();
       expect(helpBtn).toBeTruthy();
@@ -141,6 +143,13 @@ describe('MobileControls', () => {
       ) as HTMLElement;
       const shopBtn = document.querySelector('.shop-btn') as HTMLElement;
       const helpBtn = document.querySelector('.help-btn') as HTMLElement;
+      const attackBtn = document.querySelector('.attack-btn') as HTMLElement;
+
+      fireEvent.click(attackBtn);
+      expect(mockInputManager.handleVirtualKey).toHaveBeenCalledWith(
+        'KeyF',
+        true
+      );
 
       fireEvent.click(inventoryBtn);
       expect(mockInputManager.handleVirtualKey).toHaveBeenCalledWith(
@@ -199,6 +199,14 @@ export const MobileControlButtons: Component<
 
   return (
     <div class="mobile-control-buttons">
+      <button
+        class="mobile-control-btn attack-btn"
+        onClick={() => handleButtonPress('KeyF')}
+        title="Attack (F)"
+      >
+        ⚔️
+      </button>
+
       <button
         class="mobile-control-btn inventory-btn"
         onClick={() => handleButtonPress('KeyI')}
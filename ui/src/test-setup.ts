@@ -5,6 +5,7 @@ import { vi } from 'vitest';
 Object.defineProperty(window, 'wasmBindings', {
   value: {
     default: () => Promise.resolve(),
+    drain_panic_reports: () => '[]',
     start_game: () => ({
       update: vi.fn(),
       render: vi.fn(),
@@ -0,0 +1,76 @@
+// Generated from game/src/state_view.rs by `cargo test`. Do not edit.
+// STATE_VIEW_VERSION: 14
+
+export type GameScreen = "LoginScreen" | "ServerSelection" | "MainMenu" | "GameHUD" | "Inventory" | "Shop" | "Trade" | "HelpModal";
+
+export type Region = "EU" | "Asia" | "Vietnam";
+
+export type PlayerClass = "Warrior" | "Mage" | "Ranger";
+
+export type PlayerProfile = { name: string, class: PlayerClass, 
+/**
+ * Index of the character creator's appearance preset
+ */
+appearance: number, };
+
+export type ServerStatus = { id: string, region: Region, endpoint: string, latency_ms: number | null, full: boolean, };
+
+export type ItemStack = { item_id: string, quantity: number, };
+
+export type Inventory = { capacity: number, slots: Array<ItemStack>, };
+
+export type GameStateView = { version: number, screen: GameScreen, 
+/**
+ * Every open screen, bottom first, ending with `screen`
+ */
+screen_stack: Array<GameScreen>, region: Region | null, player_name: string | null, player_class: PlayerClass, appearance: number, is_loading: boolean, 
+/**
+ * Whether the world is frozen, by a screen or explicitly
+ */
+paused: boolean, error: string | null, player_position: [number, number], ball_position: [number, number], zone: string, level: number, xp: number, 
+/**
+ * XP needed for the next level
+ */
+xp_to_next_level: number, hp: number, max_hp: number, playtime_secs: number, gold: number, inventory: Inventory, party: PartyView | null, 
+/**
+ * Name of the player whose party invite is waiting for an answer
+ */
+party_invite: string | null, trade: TradeView | null, 
+/**
+ * Name of the player whose trade request is waiting for an answer
+ */
+trade_request: string | null, };
+
+export type StateDelta = { seq: number, full: boolean, changes: Partial<GameStateView>, };
+
+export type GameEvent = { "type": "ScreenChanged", from: GameScreen, to: GameScreen, } | { "type": "ItemPickedUp", item_id: string, quantity: number, } | { "type": "DamageTaken", amount: number, remaining_hp: number, } | { "type": "ErrorRaised", message: string, };
+
+export type CraftingStation = "Workbench" | "Forge" | "Anvil";
+
+export type UiCommand = { "type": "TransitionTo", screen: GameScreen, } | { "type": "PushScreen", screen: GameScreen, } | { "type": "PopScreen" } | { "type": "ReplaceScreen", screen: GameScreen, } | { "type": "SetRegion", region: Region, } | { "type": "SetPlayerName", name: string, } | { "type": "SetProfile", profile: PlayerProfile, } | { "type": "EnterZone", zone: string, } | { "type": "BuyItem", item_id: string, quantity: number, } | { "type": "Craft", recipe_id: string, station: CraftingStation | null, } | { "type": "EquipItem", item_id: string, } | { "type": "UseAbility", ability_id: string, };
+
+export type CommandResult = { "status": "Ok" } | { "status": "Error", code: string, message: string, };
+
+export type ErrorSeverity = "info" | "warning" | "error" | "fatal";
+
+export type ErrorReport = { code: string, severity: ErrorSeverity, message: string, context: { [key in string]?: string }, };
+
+export type ChatChannel = "Local" | "Zone" | "Global" | "Party" | { "Whisper": { to: string, } };
+
+export type ChatLine = { channel: ChatChannel, from: string, text: string, };
+
+export type PartyMember = { id: number, name: string, level: number, hp: number, max_hp: number, };
+
+export type PartyView = { leader: number, 
+/**
+ * Members in the order they joined, leader included
+ */
+members: Array<PartyMember>, };
+
+export type TradeOffer = { items: Array<ItemStack>, gold: number, };
+
+export type TradeSideView = { offer: TradeOffer, locked: boolean, confirmed: boolean, };
+
+export type TradeView = { partner_id: number, partner_name: string, mine: TradeSideView, theirs: TradeSideView, };
+
+export const STATE_VIEW_VERSION = 14;
@@ -20,6 +20,8 @@ interface GameInstance {
 interface WasmBindings {
   default(): Promise<void>;
   start_game(canvasId: string): GameInstance;
+  start_game_with(canvasId: string, backend: string): Promise<GameInstance>;
+  drain_panic_reports(): string;
 }
 
 declare global {
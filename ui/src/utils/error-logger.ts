@@ -3,6 +3,8 @@
  * Provides functionality to collect error details and system context for bug reporting
  */
 
+import type { ErrorReport } from '../types/game-state.generated';
+
 export interface ErrorDetails {
   id: string;
   timestamp: string;
@@ -14,6 +16,8 @@ export interface ErrorDetails {
   colno?: number;
   context: SystemContext;
   gameState?: GameContext;
+  code?: string;
+  details?: Record<string, string | undefined>;
 }
 
 export interface SystemContext {
@@ -141,6 +145,8 @@ export class ErrorLogger {
     lineno?: number;
     colno?: number;
     gameState?: GameContext;
+    code?: string;
+    details?: Record<string, string | undefined>;
   }): void {
     const errorDetails: ErrorDetails = {
       id: crypto.randomUUID(),
@@ -153,6 +159,8 @@ export class ErrorLogger {
       colno: params.colno,
       context: this.getSystemContext(),
       gameState: params.gameState,
+      code: params.code,
+      details: params.details,
     };
 
     // Add to errors array
@@ -189,6 +197,24 @@ export class ErrorLogger {
     });
   }
 
+  /**
+   * Log structured errors drained from the Rust game core
+   */
+  public logGameReports(reports: ErrorReport[]): void {
+    for (const report of reports) {
+      this.logError({
+        type:
+          report.severity === 'fatal' || report.severity === 'error'
+            ? 'error'
+            : report.severity,
+        message: `Game Error: ${report.message}`,
+        source: 'wasm',
+        code: report.code,
+        details: report.context,
+      });
+    }
+  }
+
   public getErrors(): ErrorDetails[] {
     return [...this.errors];
   }
@@ -219,6 +245,8 @@ export class ErrorLogger {
         column: error.colno,
         system: error.context,
         gameState: error.gameState,
+        code: error.code,
+        details: error.details,
       })),
     };
 
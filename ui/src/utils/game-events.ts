@@ -0,0 +1,28 @@
+/**
+ * Game event subscriptions
+ * The game loop drains the Rust core's event queue once per frame, outside
+ * any call into the game, and hands the events to whoever subscribed here
+ */
+
+import type { GameEvent } from '../types/game-state.generated';
+
+type GameEventListener = (event: GameEvent) => void;
+
+const listeners = new Set<GameEventListener>();
+
+/**
+ * Call `listener` for every game event, returning a function to unsubscribe
+ */
+export const onGameEvent = (listener: GameEventListener): (() => void) => {
+  listeners.add(listener);
+  return () => listeners.delete(listener);
+};
+
+/**
+ * Deliver events drained from the game to every listener, in order
+ */
+export const publishGameEvents = (events: GameEvent[]) => {
+  for (const event of events) {
+    listeners.forEach(listener => listener(event));
+  }
+};
@@ -0,0 +1,60 @@
+/**
+ * Server latency probing
+ * Measures how long a WebSocket handshake takes to each game server and
+ * reports the results back to the game, which picks the best region
+ */
+
+import type { ServerStatus } from '../types/game-state.generated';
+import type { EnhancedGameInstance } from '../components/GameTypes';
+
+const PING_TIMEOUT_MS = 3000;
+
+/**
+ * Time a WebSocket handshake, resolving to null if the server can't be reached
+ */
+export const measureLatency = (
+  endpoint: string,
+  timeoutMs = PING_TIMEOUT_MS
+): Promise<number | null> =>
+  new Promise(resolve => {
+    const started = performance.now();
+    let socket: WebSocket;
+    try {
+      socket = new WebSocket(endpoint);
+    } catch {
+      resolve(null);
+      return;
+    }
+
+    const finish = (latency: number | null) => {
+      clearTimeout(timer);
+      socket.onopen = null;
+      socket.onerror = null;
+      socket.close();
+      resolve(latency);
+    };
+    const timer = setTimeout(() => finish(null), timeoutMs);
+
+    socket.onopen = () => finish(performance.now() - started);
+    socket.onerror = () => finish(null);
+  });
+
+/**
+ * Ping every known server once and record the results in the game
+ */
+export const probeServers = async (
+  game: EnhancedGameInstance
+): Promise<ServerStatus[]> => {
+  const servers: ServerStatus[] = JSON.parse(game.get_servers());
+
+  await Promise.all(
+    servers.map(async server => {
+      const latency = await measureLatency(server.endpoint);
+      if (latency !== null) {
+        game.record_server_latency(server.id, latency);
+      }
+    })
+  );
+
+  return JSON.parse(game.get_servers());
+};